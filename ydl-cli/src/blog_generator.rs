@@ -8,11 +8,37 @@ use async_openai::{
     },
 };
 use std::env;
-use tracing::{debug, info};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 use ydl::{VideoMetadata, YdlError, YdlResult};
 
+/// Model used for blog generation
+const MODEL: &str = "gpt-5";
+
+/// Input tokens the model accepts in a single request
+const MODEL_INPUT_TOKENS: usize = 272_000;
+
+/// Output tokens reserved for the generated blog post
+const MAX_COMPLETION_TOKENS: u32 = 20_000;
+
+/// Transcript size (in characters) handed to the model per chunk when condensing
+const CHUNK_CHARS: usize = 200_000;
+
+/// Output tokens reserved for the notes condensed from one transcript chunk
+const CHUNK_NOTES_TOKENS: u32 = 8_000;
+
+/// Transcript size (in characters) sent to the model unless configured otherwise
+pub const DEFAULT_TRANSCRIPT_CHARS: usize = 8_000;
+
 pub struct BlogGenerator {
     client: Client<OpenAIConfig>,
+    /// Limit on each OpenAI request; `None` waits as long as the API takes
+    timeout: Option<Duration>,
+    /// Aborts generation between or during requests when cancelled
+    cancel: CancellationToken,
+    /// Transcript is cut to this many characters; `None` sends all of it
+    max_transcript_chars: Option<usize>,
 }
 
 impl BlogGenerator {
@@ -23,9 +49,61 @@ impl BlogGenerator {
         })?;
 
         let config = OpenAIConfig::new().with_api_key(api_key);
-        let client = Client::with_config(config);
+        let generator = Self::with_client(Client::with_config(config));
+        match env::var("OPENAI_PROXY") {
+            Ok(proxy) if !proxy.is_empty() => generator.with_proxy(&proxy),
+            _ => Ok(generator),
+        }
+    }
+
+    pub fn with_client(client: Client<OpenAIConfig>) -> Self {
+        Self {
+            client,
+            timeout: None,
+            cancel: CancellationToken::new(),
+            max_transcript_chars: Some(DEFAULT_TRANSCRIPT_CHARS),
+        }
+    }
+
+    /// Send OpenAI requests through `proxy`
+    ///
+    /// The proxy configured for YouTube is never used for OpenAI; without this,
+    /// requests follow `HTTPS_PROXY`/`ALL_PROXY` like any other program.
+    pub fn with_proxy(self, proxy: &str) -> YdlResult<Self> {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| YdlError::Configuration {
+            message: format!("Invalid blog proxy URL: {}", e),
+        })?;
+        let http_client = reqwest::Client::builder()
+            .proxy(proxy)
+            .build()
+            .map_err(|e| YdlError::Configuration {
+                message: format!("Failed to create OpenAI HTTP client: {}", e),
+            })?;
+
+        Ok(Self {
+            client: self.client.with_http_client(http_client),
+            ..self
+        })
+    }
+
+    /// Fail any OpenAI request that takes longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 
-        Ok(Self { client })
+    /// Stop generation as soon as `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Cut the transcript to `max_chars` characters, or send it whole when `None`
+    ///
+    /// Whole transcripts too long for the model's context are condensed in chunks first.
+    pub fn with_max_transcript_chars(mut self, max_chars: Option<usize>) -> Self {
+        self.max_transcript_chars = max_chars;
+        self
     }
 
     pub async fn generate_blog(
@@ -41,11 +119,78 @@ impl BlogGenerator {
             subtitle_content.len()
         );
 
+        let subtitle_content = self.limit_transcript(subtitle_content);
         let system_prompt = self.build_system_prompt(target_language);
-        let user_prompt = self.build_user_prompt(subtitle_content, metadata);
+        let mut user_prompt = self.build_user_prompt(subtitle_content, metadata);
+
+        let estimated = Self::estimate_tokens(&system_prompt) + Self::estimate_tokens(&user_prompt);
+        info!("Estimated prompt size: {} tokens", estimated);
+
+        // Transcripts that would overflow the context are condensed chunk by chunk first
+        if estimated > MODEL_INPUT_TOKENS {
+            warn!(
+                "Prompt of ~{} tokens exceeds the {} token context, condensing the transcript in chunks",
+                estimated, MODEL_INPUT_TOKENS
+            );
+            let notes = self.condense_transcript(subtitle_content).await?;
+            user_prompt = self.build_user_prompt(&notes, metadata);
+        }
+
+        let blog_content = self
+            .complete(system_prompt, user_prompt, MAX_COMPLETION_TOKENS)
+            .await?;
+
+        info!(
+            "Successfully generated blog with {} characters",
+            blog_content.len()
+        );
+        Ok(blog_content)
+    }
+
+    /// Rough token count of `text`, using the common four-characters-per-token heuristic
+    ///
+    /// Good enough to decide whether a prompt fits the context; not an exact tokenizer.
+    pub fn estimate_tokens(text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+
+    /// Apply the configured transcript limit, ending at a sentence where possible
+    fn limit_transcript<'a>(&self, subtitle_content: &'a str) -> &'a str {
+        match self.max_transcript_chars {
+            Some(max_chars) => truncate_content(subtitle_content, max_chars),
+            None => subtitle_content,
+        }
+    }
+
+    /// Condense a long transcript into detailed notes, one request per chunk
+    async fn condense_transcript(&self, subtitle_content: &str) -> YdlResult<String> {
+        let chunks = split_into_chunks(subtitle_content, CHUNK_CHARS);
+        let mut notes = Vec::with_capacity(chunks.len());
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            info!("Condensing transcript chunk {}/{}", index + 1, chunks.len());
+            let system_prompt = "You condense part of a video transcript into detailed notes. \
+                Keep every technical detail, example, number and quote; drop filler and repetition. \
+                Reply with the notes only."
+                .to_string();
+            notes.push(
+                self.complete(system_prompt, chunk.to_string(), CHUNK_NOTES_TOKENS)
+                    .await?,
+            );
+        }
+
+        Ok(notes.join("\n\n"))
+    }
 
+    /// Send one system + user message exchange and return the reply text
+    async fn complete(
+        &self,
+        system_prompt: String,
+        user_prompt: String,
+        max_completion_tokens: u32,
+    ) -> YdlResult<String> {
         let request = CreateChatCompletionRequest {
-            model: "gpt-5".to_string(), // Using GPT-5 for superior content generation
+            model: MODEL.to_string(),
             messages: vec![
                 ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
                     content: ChatCompletionRequestSystemMessageContent::Text(system_prompt),
@@ -56,32 +201,46 @@ impl BlogGenerator {
                     name: None,
                 }),
             ],
-            max_completion_tokens: Some(20000),
+            max_completion_tokens: Some(max_completion_tokens),
             ..Default::default()
         };
 
-        let response =
-            self.client
-                .chat()
-                .create(request)
-                .await
-                .map_err(|e| YdlError::Processing {
-                    message: format!("OpenAI API error: {}", e),
-                })?;
+        let chat = self.client.chat();
+        let create = chat.create(request);
+        let response = tokio::select! {
+            _ = self.cancel.cancelled() => {
+                return Err(YdlError::Processing {
+                    message: "Blog generation was cancelled".to_string(),
+                });
+            }
+            result = async {
+                match self.timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, create).await.map_err(|_| {
+                        YdlError::Processing {
+                            message: format!(
+                                "OpenAI API did not respond within {} seconds",
+                                timeout.as_secs_f64()
+                            ),
+                        }
+                    }),
+                    None => Ok(create.await),
+                }
+            } => result?,
+        }
+        .map_err(|e| YdlError::Processing {
+            message: format!("OpenAI API error: {}", e),
+        })?;
 
-        let blog_content = response
+        response
             .choices
             .first()
-            .and_then(|choice| choice.message.content.as_ref())
+            .and_then(|choice| choice.message.content.as_deref())
+            .map(clean_completion)
+            .filter(|content| !content.is_empty())
             .ok_or_else(|| YdlError::Processing {
-                message: "No content received from OpenAI API".to_string(),
-            })?;
-
-        info!(
-            "Successfully generated blog with {} characters",
-            blog_content.len()
-        );
-        Ok(blog_content.clone())
+                message: "OpenAI API returned no content (no choices, or an empty reply)"
+                    .to_string(),
+            })
     }
 
     fn build_system_prompt(&self, target_language: &str) -> String {
@@ -172,10 +331,19 @@ Now, transform these subtitles into a blog post that readers will bookmark, shar
             String::new()
         };
 
+        let thumbnail_context = if let Some(thumbnail_url) = &metadata.thumbnail_url {
+            format!(
+                "Header Image: {}\n(Optionally reference it at the top of the post as ![]({}))\n",
+                thumbnail_url, thumbnail_url
+            )
+        } else {
+            String::new()
+        };
+
         format!(
             r#"Transform these YouTube video subtitles into an exceptional technical blog post:
 
-{video_context}{duration_context}
+{video_context}{duration_context}{thumbnail_context}
 Video ID: {video_id}
 URL: https://www.youtube.com/watch?v={video_id}
 
@@ -234,66 +402,197 @@ Remember: This blog post should be so good that readers will:
 Now, begin your transformation..."#,
             video_context = video_context,
             duration_context = duration_context,
+            thumbnail_context = thumbnail_context,
             video_id = metadata.video_id,
-            subtitle_content = self.truncate_content(subtitle_content, 8000), // Limit content to avoid token limits
+            subtitle_content = subtitle_content,
         )
     }
+}
+
+/// JSON fields that models put a reply in when asked for prose but answering in JSON
+const JSON_REPLY_FIELDS: &[&str] = &["content", "markdown", "blog", "text", "body"];
+
+/// Unwrap a model reply from surrounding code fences or a JSON envelope, and trim it
+///
+/// Some models and prompts answer with the whole post in a ```` ```markdown ```` block,
+/// or as a JSON string or object. Replies without such wrapping are only trimmed.
+fn clean_completion(content: &str) -> String {
+    let mut content = content.trim();
+
+    if let Some(fenced) = content.strip_prefix("```")
+        && let Some(inner) = fenced.strip_suffix("```")
+    {
+        // The opening fence line may name a language, e.g. ```markdown
+        content = match inner.split_once('\n') {
+            Some((info, body)) if !info.trim().contains(' ') => body,
+            _ => inner,
+        }
+        .trim();
+    }
+
+    if content.starts_with(['{', '"'])
+        && let Ok(value) = serde_json::from_str::<serde_json::Value>(content)
+    {
+        let text = match &value {
+            serde_json::Value::String(text) => Some(text.as_str()),
+            serde_json::Value::Object(fields) => JSON_REPLY_FIELDS
+                .iter()
+                .find_map(|key| fields.get(*key)?.as_str()),
+            _ => None,
+        };
+        if let Some(text) = text {
+            return clean_completion(text);
+        }
+    }
+
+    content.to_string()
+}
 
-    fn truncate_content<'a>(&self, content: &'a str, max_chars: usize) -> &'a str {
-        if content.len() <= max_chars {
-            content
+/// Split `content` into chunks of at most `max_chars` bytes, preferring sentence ends
+fn split_into_chunks(content: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = content.trim();
+
+    while !rest.is_empty() {
+        let chunk = truncate_content(rest, max_chars);
+        // Never loop forever on a leading multi-byte character wider than `max_chars`
+        let chunk = if chunk.is_empty() {
+            let width = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            &rest[..width]
         } else {
-            // Try to truncate at a sentence or paragraph boundary
-            let truncated = &content[..max_chars];
-            if let Some(last_period) = truncated.rfind('.') {
-                if last_period > max_chars * 3 / 4 {
-                    // If we found a period in the last quarter, use it
-                    &content[..last_period + 1]
-                } else {
-                    truncated
-                }
+            chunk
+        };
+        chunks.push(chunk);
+        rest = rest[chunk.len()..].trim_start();
+    }
+
+    chunks
+}
+
+/// Cut `content` to at most `max_chars` bytes, at a sentence end in the last quarter if possible
+fn truncate_content(content: &str, max_chars: usize) -> &str {
+    if content.len() <= max_chars {
+        content
+    } else {
+        // Try to truncate at a sentence or paragraph boundary
+        let mut boundary = max_chars;
+        while !content.is_char_boundary(boundary) {
+            boundary -= 1;
+        }
+        let truncated = &content[..boundary];
+        if let Some(last_period) = truncated.rfind('.') {
+            if last_period > max_chars * 3 / 4 {
+                // If we found a period in the last quarter, use it
+                &content[..last_period + 1]
             } else {
                 truncated
             }
+        } else {
+            truncated
         }
     }
 }
 
+/// Paragraph length to aim for when reflowing transcripts
+const PARAGRAPH_TARGET_CHARS: usize = 400;
+
+/// Reflow a line-per-cue transcript into paragraphs of whole sentences
+///
+/// Caption fragments are joined until a paragraph reaches roughly
+/// `PARAGRAPH_TARGET_CHARS` and ends a sentence. Transcripts without punctuation
+/// (common in auto-captions) are broken at twice the target instead.
+pub fn prepare_transcript(raw: &str) -> String {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+
+    for line in raw.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(line);
+
+        let sentence_end = line.ends_with(['.', '!', '?']);
+        if (current.len() >= PARAGRAPH_TARGET_CHARS && sentence_end)
+            || current.len() >= PARAGRAPH_TARGET_CHARS * 2
+        {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+    }
+
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    paragraphs.join("\n\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_truncate_content() {
-        let config = OpenAIConfig::new();
-        let generator = BlogGenerator {
-            client: Client::with_config(config), // This won't work without API key, but fine for testing truncation
-        };
-
         let short_content = "This is short.";
-        assert_eq!(
-            generator.truncate_content(short_content, 100),
-            short_content
-        );
+        assert_eq!(truncate_content(short_content, 100), short_content);
 
         let long_content = "This is a very long content that exceeds the limit. It has multiple sentences. This should be truncated properly.";
-        let truncated = generator.truncate_content(long_content, 50);
+        let truncated = truncate_content(long_content, 50);
         assert!(truncated.len() <= 50);
         assert!(truncated.ends_with('.') || truncated.len() == 50);
     }
 
+    #[test]
+    fn test_transcript_limit() {
+        let transcript = "Ownership moves values. Borrowing lends them. ".repeat(1_000);
+        let generator = BlogGenerator::with_client(Client::with_config(OpenAIConfig::new()));
+
+        // The default keeps the prompt to the first 8000 characters
+        let limited = generator.limit_transcript(&transcript);
+        assert!(limited.len() <= DEFAULT_TRANSCRIPT_CHARS);
+        assert!(limited.len() > DEFAULT_TRANSCRIPT_CHARS * 3 / 4);
+        assert!(limited.ends_with('.'));
+
+        let generator = generator.with_max_transcript_chars(Some(100));
+        assert!(generator.limit_transcript(&transcript).len() <= 100);
+
+        // Without a limit the whole transcript is sent
+        let generator = generator.with_max_transcript_chars(None);
+        assert_eq!(generator.limit_transcript(&transcript), transcript);
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        // "The quick brown fox..." is 10 tokens for GPT tokenizers
+        let estimate =
+            BlogGenerator::estimate_tokens("The quick brown fox jumps over the lazy dog.");
+        assert!((8..=14).contains(&estimate), "estimate was {}", estimate);
+        assert_eq!(BlogGenerator::estimate_tokens(""), 0);
+
+        // A long transcript is split into sentence-aligned chunks that cover all of it
+        let transcript = "Ownership moves values. Borrowing lends them. ".repeat(100);
+        let chunks = split_into_chunks(&transcript, 500);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= 500 && c.ends_with('.')));
+        assert_eq!(chunks.join(" "), transcript.trim());
+        assert_eq!(
+            split_into_chunks("日本語のテキスト", 4).concat(),
+            "日本語のテキスト"
+        );
+    }
+
     #[test]
     fn test_build_user_prompt() {
         let config = OpenAIConfig::new();
-        let generator = BlogGenerator {
-            client: Client::with_config(config), // This won't work without API key, but fine for testing prompt building
-        };
+        // This won't work without API key, but fine for testing prompt building
+        let generator = BlogGenerator::with_client(Client::with_config(config));
 
         let metadata = VideoMetadata {
             title: "Test Video".to_string(),
             video_id: "test123".to_string(),
             duration: Some(Duration::from_secs(300)),
+            thumbnail_url: None,
+            chapters: Vec::new(),
             available_subtitles: Vec::new(),
         };
 
@@ -303,14 +602,253 @@ mod tests {
         assert!(prompt.contains("test123"));
         assert!(prompt.contains("5 minutes"));
         assert!(prompt.contains("Test subtitle content"));
+        assert!(!prompt.contains("Header Image"));
+
+        let metadata = metadata
+            .with_thumbnail_url("https://i.ytimg.com/vi/test123/maxresdefault.jpg".to_string());
+        let prompt = generator.build_user_prompt("Test subtitle content", &metadata);
+        assert!(prompt.contains("Header Image: https://i.ytimg.com/vi/test123/maxresdefault.jpg"));
+    }
+
+    #[test]
+    fn test_prepare_transcript() {
+        let fragments = [
+            "so today",
+            "we're going to",
+            "look at ownership.",
+            "it is the",
+        ]
+        .repeat(40)
+        .join("\n");
+        let prepared = prepare_transcript(&fragments);
+
+        let segments: Vec<&str> = prepared.split("\n\n").collect();
+        assert!(segments.len() < fragments.lines().count() / 10);
+        assert!(segments[0].len() >= PARAGRAPH_TARGET_CHARS);
+        assert!(segments[0].ends_with("ownership."));
+        assert!(!prepared.contains("so today\nwe're"));
+
+        // Unpunctuated input is still broken into bounded paragraphs
+        let unpunctuated = "and then we".repeat(200).replace("we", "we\n");
+        assert!(
+            prepare_transcript(&unpunctuated)
+                .split("\n\n")
+                .all(|p| p.len() < PARAGRAPH_TARGET_CHARS * 2 + 20)
+        );
+    }
+
+    /// Endpoint that records each request's first line and answers it with `reply`,
+    /// or keeps the connection open without answering when there is none
+    async fn mock_endpoint(reply: Option<serde_json::Value>) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                request.extend_from_slice(&buf[..n]);
+                let line = String::from_utf8_lossy(&request)
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .to_string();
+                seen.lock().unwrap().push(line);
+
+                let Some(reply) = &reply else {
+                    held.push(socket);
+                    continue;
+                };
+                // Read the whole JSON request body before answering
+                while !String::from_utf8_lossy(&request).trim_end().ends_with('}') {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let body = reply.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (format!("http://{}", addr), requests)
+    }
+
+    /// OpenAI-compatible endpoint that accepts requests but never answers
+    async fn silent_api() -> String {
+        format!("{}/v1", mock_endpoint(None).await.0)
+    }
+
+    /// OpenAI-compatible endpoint answering every chat request with `reply`
+    async fn chat_api(reply: serde_json::Value) -> String {
+        format!("{}/v1", mock_endpoint(Some(reply)).await.0)
+    }
+
+    #[tokio::test]
+    async fn test_blog_proxy_is_separate_from_youtube_proxy() {
+        let (api, api_requests) = mock_endpoint(None).await;
+        let (youtube_proxy, youtube_proxy_requests) = mock_endpoint(None).await;
+        let (blog_proxy, blog_proxy_requests) = mock_endpoint(None).await;
+
+        let options = ydl::YdlOptions::new().proxy(&youtube_proxy);
+        let _downloader =
+            ydl::Ydl::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", options).unwrap();
+
+        let generate = |generator: BlogGenerator| async move {
+            let _ = generator
+                .with_timeout(Duration::from_millis(300))
+                .generate_blog("Some transcript.", &VideoMetadata::default(), "English")
+                .await;
+        };
+        let config = || {
+            OpenAIConfig::new()
+                .with_api_key("test")
+                .with_api_base(format!("{}/v1", api))
+        };
+
+        // Without a blog proxy, OpenAI is called directly
+        generate(BlogGenerator::with_client(Client::with_config(config()))).await;
+        assert_eq!(api_requests.lock().unwrap().len(), 1);
+
+        // With one, only the blog proxy sees the request
+        let proxied = BlogGenerator::with_client(Client::with_config(config()))
+            .with_proxy(&blog_proxy)
+            .unwrap();
+        generate(proxied).await;
+        let proxied_requests = blog_proxy_requests.lock().unwrap().clone();
+        assert_eq!(proxied_requests.len(), 1);
+        assert!(proxied_requests[0].contains(&format!("{}/v1/chat/completions", api)));
+        assert_eq!(api_requests.lock().unwrap().len(), 1);
+
+        assert!(youtube_proxy_requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_blog_proxy() {
+        let generator = BlogGenerator::with_client(Client::with_config(OpenAIConfig::new()));
+        assert!(matches!(
+            generator.with_proxy("not a url"),
+            Err(YdlError::Configuration { .. })
+        ));
+    }
+
+    fn chat_reply(choices: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": MODEL,
+            "choices": choices,
+        })
+    }
+
+    #[test]
+    fn test_clean_completion() {
+        assert_eq!(
+            clean_completion("```markdown\n# Title\n\nBody text.\n```\n"),
+            "# Title\n\nBody text."
+        );
+        assert_eq!(clean_completion("```\n# Title\n```"), "# Title");
+        assert_eq!(
+            clean_completion(r#"{"content": "```md\n# Title\n```"}"#),
+            "# Title"
+        );
+        assert_eq!(clean_completion(r##""# Title""##), "# Title");
+
+        // Fences inside the post are left alone
+        let post = "# Title\n\n```rust\nfn main() {}\n```\n\nMore.";
+        assert_eq!(clean_completion(&format!("  {}\n", post)), post);
+        assert_eq!(clean_completion("{not json"), "{not json");
+    }
+
+    #[tokio::test]
+    async fn test_generate_blog_strips_fences_and_rejects_empty_replies() {
+        let generate = |api: String| async move {
+            let config = OpenAIConfig::new().with_api_key("test").with_api_base(api);
+            BlogGenerator::with_client(Client::with_config(config))
+                .with_timeout(Duration::from_secs(5))
+                .generate_blog("Some transcript.", &VideoMetadata::default(), "English")
+                .await
+        };
+
+        let fenced = chat_reply(serde_json::json!([{
+            "index": 0,
+            "message": {"role": "assistant", "content": "```markdown\n# Post\n\nText.\n```"},
+            "finish_reason": "stop",
+        }]));
+        assert_eq!(
+            generate(chat_api(fenced).await).await.unwrap(),
+            "# Post\n\nText."
+        );
+
+        let empty = chat_reply(serde_json::json!([]));
+        match generate(chat_api(empty).await).await {
+            Err(YdlError::Processing { message }) => assert!(message.contains("no content")),
+            other => panic!("expected an empty reply error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_blog_times_out() {
+        let config = OpenAIConfig::new()
+            .with_api_key("test")
+            .with_api_base(silent_api().await);
+        let generator = BlogGenerator::with_client(Client::with_config(config))
+            .with_timeout(Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        let result = generator
+            .generate_blog("Some transcript.", &VideoMetadata::default(), "English")
+            .await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        match result {
+            Err(YdlError::Processing { message }) => assert!(message.contains("did not respond")),
+            other => panic!("expected a timeout error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_blog_can_be_cancelled() {
+        let config = OpenAIConfig::new()
+            .with_api_key("test")
+            .with_api_base(silent_api().await);
+        let cancel = CancellationToken::new();
+        let generator = BlogGenerator::with_client(Client::with_config(config))
+            .with_cancellation(cancel.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancel.cancel();
+        });
+        let result = generator
+            .generate_blog("Some transcript.", &VideoMetadata::default(), "English")
+            .await;
+        canceller.await.unwrap();
+
+        match result {
+            Err(YdlError::Processing { message }) => assert!(message.contains("cancelled")),
+            other => panic!("expected a cancellation error, got {:?}", other),
+        }
     }
 
     #[test]
     fn test_build_system_prompt() {
         let config = OpenAIConfig::new();
-        let generator = BlogGenerator {
-            client: Client::with_config(config), // This won't work without API key, but fine for testing prompt building
-        };
+        // This won't work without API key, but fine for testing prompt building
+        let generator = BlogGenerator::with_client(Client::with_config(config));
 
         let prompt = generator.build_system_prompt("English");
 
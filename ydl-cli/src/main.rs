@@ -1,13 +1,23 @@
-use clap::{Parser, ValueEnum};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::{debug, info};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use ydl::{SubtitleType, Ydl, YdlError, YdlOptions, YdlResult};
+use ydl::parser::YouTubeParser;
+use ydl::{
+    ClientType, Segmentation, SourceFormat, SubtitleTrack, SubtitleType, Timecode, Ydl, YdlError,
+    YdlOptions, YdlResult, YouTubeRef,
+};
 
 mod blog_generator;
-use blog_generator::BlogGenerator;
+mod resume;
+use blog_generator::{BlogGenerator, prepare_transcript};
+use resume::ResumeState;
 
 #[derive(Parser)]
 #[command(name = "ydl")]
@@ -15,25 +25,48 @@ use blog_generator::BlogGenerator;
 #[command(long_about = None)]
 struct Cli {
     /// YouTube video URL or video ID
-    #[arg(value_name = "URL")]
-    url: String,
+    #[arg(value_name = "URL", required_unless_present_any = ["completions", "batch"])]
+    url: Option<String>,
+
+    /// Download every URL listed in FILE (one per line, `#` comments), or on stdin with `-`
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "url", "output", "stdout", "list", "info", "diagnose", "chapters", "generate_blog",
+            "variants", "formats", "interactive",
+        ]
+    )]
+    batch: Option<PathBuf>,
+
+    /// Print a completion script for the given shell to stdout and exit
+    #[arg(long, value_enum, value_name = "SHELL", exclusive = true)]
+    completions: Option<Shell>,
 
     /// Output subtitle format
-    #[arg(short, long, value_enum, default_value = "srt")]
-    format: CliSubtitleType,
+    #[arg(short, long, value_parser = format_parser(), default_value = "srt")]
+    format: SubtitleType,
 
     /// Preferred language code (e.g., en, es, fr)
     #[arg(short, long)]
     language: Option<String>,
 
-    /// Output file path (default: auto-generated)
+    /// Output file path (default: auto-generated); `-` prints to stdout
     #[arg(short, long)]
     output: Option<PathBuf>,
 
+    /// Print the subtitles to stdout instead of saving a file (same as `-o -`)
+    #[arg(long, conflicts_with_all = ["output", "formats", "variants", "generate_blog"])]
+    stdout: bool,
+
     /// Output directory (default: current directory)
     #[arg(short = 'D', long)]
     output_dir: Option<PathBuf>,
 
+    /// Fail instead of creating a missing output directory
+    #[arg(long)]
+    no_create_dirs: bool,
+
     /// List available subtitle tracks instead of downloading
     #[arg(long)]
     list: bool,
@@ -42,6 +75,10 @@ struct Cli {
     #[arg(long)]
     info: bool,
 
+    /// Export the video's chapters as subtitles instead of its captions
+    #[arg(long)]
+    chapters: bool,
+
     /// Disable auto-generated subtitles (auto-generated subtitles are allowed by default)
     #[arg(long)]
     no_auto: bool,
@@ -50,42 +87,149 @@ struct Cli {
     #[arg(long)]
     no_prefer_manual: bool,
 
+    /// Machine-translate the selected track into this language (YouTube auto-translate)
+    #[arg(long, value_name = "LANG", conflicts_with_all = ["vss_id", "variants", "formats"])]
+    translate_to: Option<String>,
+
+    /// Save every track of --language (manual and auto-generated) as <name>.<lang>.<type>.<ext>
+    #[arg(long, requires = "language")]
+    variants: bool,
+
+    /// Never use machine-translated tracks; --language must match a genuine track
+    #[arg(long)]
+    no_translations: bool,
+
     /// Disable content cleaning (HTML tags, formatting)
     #[arg(long)]
     no_clean: bool,
 
+    /// Keep line breaks within a cue instead of joining them into one line
+    #[arg(long)]
+    preserve_line_breaks: bool,
+
     /// Disable subtitle timing validation
     #[arg(long)]
     no_validate: bool,
 
+    /// Titles whose slug is shorter than this get the video ID appended to the filename
+    #[arg(long, default_value_t = DEFAULT_MIN_SLUG_LENGTH)]
+    min_slug_length: usize,
+
     /// Maximum retry attempts
     #[arg(long, default_value = "3")]
     max_retries: u32,
 
-    /// Request timeout in seconds
-    #[arg(long, default_value = "30")]
-    timeout: u64,
+    /// Seconds a whole request may take (default: no limit, only stalled reads time out)
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Seconds to wait for each read of a response before giving up on a stalled download
+    #[arg(long, default_value = "10")]
+    read_timeout: u64,
+
+    /// Seconds to wait for a connection to be established
+    #[arg(long, default_value = "10")]
+    connect_timeout: u64,
 
     /// Custom User-Agent string
     #[arg(long)]
     user_agent: Option<String>,
 
-    /// Proxy URL (http://proxy:port)
+    /// Add an HLS X-TIMESTAMP-MAP header to VTT output with this MPEG-TS offset (90 kHz ticks)
+    #[arg(long, value_name = "TICKS")]
+    hls_mpegts: Option<u64>,
+
+    /// Drop intro/outro cues separated from the main body by this many seconds of silence
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "30"
+    )]
+    trim_silence: Option<u64>,
+
+    /// Shift every cue by this many milliseconds (negative moves cues earlier)
+    #[arg(long, value_name = "MS", allow_hyphen_values = true)]
+    shift_ms: Option<i64>,
+
+    /// Move all cues so the first one starts at this time (HH:MM:SS[.mmm], MM:SS or seconds)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    rebase: Option<std::time::Duration>,
+
+    /// Merge TXT output into paragraphs, ending one at a sentence end or a pause longer than this
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2"
+    )]
+    txt_paragraphs: Option<u64>,
+
+    /// Extra InnerTube context fields as JSON, e.g. '{"client":{"clientScreen":"EMBED"}}'
+    #[arg(long, value_name = "JSON", value_parser = parse_json)]
+    innertube_context: Option<serde_json::Value>,
+
+    /// InnerTube clients to discover tracks with, in order: web, tv, ios or android
+    /// (default: tv,web,ios,android)
+    #[arg(long, value_name = "CLIENTS", value_delimiter = ',')]
+    client: Option<Vec<ClientType>>,
+
+    /// Proxy URL (http://proxy:port); overrides HTTPS_PROXY, HTTP_PROXY and ALL_PROXY
     #[arg(long)]
     proxy: Option<String>,
 
+    /// Netscape-format cookies file, or a raw "SID=...; HSID=..." Cookie header, to send
+    /// with requests (for age-restricted and members-only videos); overrides YDL_COOKIES
+    #[arg(long, value_name = "FILE|HEADER")]
+    cookies: Option<String>,
+
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
 
+    /// Log every network request (method, URL, status, size, timing) with credentials redacted
+    #[arg(long)]
+    trace_network: bool,
+
     /// Download multiple formats (comma-separated)
-    #[arg(long, value_delimiter = ',')]
-    formats: Option<Vec<CliSubtitleType>>,
+    #[arg(long, value_parser = format_parser(), value_delimiter = ',')]
+    formats: Option<Vec<SubtitleType>>,
 
     /// Force overwrite existing files
     #[arg(long)]
     force: bool,
 
+    /// Re-read every written file and fail if it differs from the downloaded content
+    #[arg(long)]
+    verify_write: bool,
+
+    /// Skip videos already completed by an earlier run of the same multi-video link or batch
+    #[arg(long)]
+    resume: bool,
+
+    /// For a video opened from a playlist (v=...&list=...), download it and the rest
+    /// of the playlist after it
+    #[arg(long)]
+    from_index: bool,
+
+    /// When the URL has a start time (t=90s, t=1m30s), only output cues from that point on
+    #[arg(long)]
+    from_start: bool,
+
+    /// For a channel URL, download the channel's uploads, newest first
+    #[arg(long)]
+    channel_uploads: bool,
+
+    /// Maximum number of uploads to download with --channel-uploads
+    #[arg(long, requires = "channel_uploads")]
+    limit: Option<usize>,
+
+    /// Query every InnerTube client separately and report what each one returns
+    #[arg(long)]
+    diagnose: bool,
+
     /// Generate technical blog from subtitles
     #[arg(long)]
     generate_blog: bool,
@@ -93,63 +237,410 @@ struct Cli {
     /// Blog language for generation (default: Chinese)
     #[arg(long, default_value = "chinese")]
     blog_lang: String,
+
+    /// Give up on a blog generation request after this many seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 600)]
+    blog_timeout: u64,
+
+    /// Send at most this many characters of the transcript to the blog generator
+    /// (0 sends all of it, condensing long transcripts in chunks)
+    #[arg(long, value_name = "CHARS", default_value_t = blog_generator::DEFAULT_TRANSCRIPT_CHARS)]
+    blog_max_chars: usize,
+
+    /// Proxy URL for blog generation requests to OpenAI; overrides OPENAI_PROXY
+    /// (--proxy only applies to YouTube)
+    #[arg(long, value_name = "URL")]
+    blog_proxy: Option<String>,
+
+    /// Send the transcript to the blog generator as-is, without reflowing it into paragraphs
+    #[arg(long)]
+    raw_transcript: bool,
+
+    /// Prefix each plain-text paragraph with a youtu.be link to its start time
+    #[arg(long)]
+    timestamp_links: bool,
+
+    /// Download the track with this VSS ID (see --list), e.g. "a.en" for auto-generated English
+    #[arg(long)]
+    vss_id: Option<String>,
+
+    /// Pick the track to download from a numbered menu (needs a terminal)
+    #[arg(long, conflicts_with_all = ["vss_id", "translate_to", "variants", "formats"])]
+    interactive: bool,
+
+    /// Caption format to request from YouTube: srv3, json3 or vtt
+    /// (overrides the automatic srv3 default; with --format raw, saves it unprocessed)
+    #[arg(long)]
+    source_fmt: Option<SourceFormat>,
+
+    /// Detect speaker labels (">> BOB:", "- Alice:"); JSON puts them in a separate field
+    #[arg(long)]
+    detect_speakers: bool,
+
+    /// Cue segmentation: original, non-overlapping or sentences
+    /// (default: non-overlapping for auto-generated tracks, original otherwise)
+    #[arg(long)]
+    segmentation: Option<Segmentation>,
+
+    /// SRT cue times: clock, frames (HH:MM:SS:FF, drop-frame at 29.97) or frames-ndf
+    #[arg(long)]
+    timecode: Option<Timecode>,
+
+    /// Frame rate for frame timecodes and SCC output (default: 29.97)
+    #[arg(long, value_name = "FPS")]
+    frame_rate: Option<f64>,
+
+    /// Retry other tracks when the cues cover less than this fraction of the video (0.0-1.0)
+    #[arg(long, value_name = "FRACTION")]
+    min_coverage: Option<f64>,
+
+    /// Download and score every candidate track, then pick the best one by coverage,
+    /// cue count, reading speed, language and manual/auto
+    #[arg(long)]
+    select_by_quality: bool,
+
+    /// Sort, de-duplicate and de-overlap cues for strict players (VLC, TVs)
+    #[arg(long)]
+    strict: bool,
+
+    /// Word-wrap SRT and VTT cues to lines of at most this many characters
+    #[arg(long, value_name = "CHARS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_line_len: Option<u64>,
+
+    /// Most lines per cue when wrapping with --max-line-len
+    #[arg(
+        long,
+        value_name = "LINES",
+        default_value_t = 2,
+        requires = "max_line_len",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_lines: u64,
+
+    /// Skip malformed IDs in watch_videos links instead of failing
+    #[arg(long)]
+    skip_invalid_ids: bool,
 }
 
-#[derive(Clone, Copy, ValueEnum)]
-enum CliSubtitleType {
-    Srt,
-    Vtt,
-    Txt,
-    Json,
-    Raw,
+/// `--output` value that writes to stdout instead of a file
+const STDOUT_PATH: &str = "-";
+
+/// `--batch` value that reads the URL list from stdin
+const STDIN_PATH: &str = "-";
+
+impl Cli {
+    /// Whether subtitles go to stdout (`--stdout` or `-o -`), moving status messages to stderr
+    fn to_stdout(&self) -> bool {
+        self.stdout || self.output.as_deref().is_some_and(is_stdout)
+    }
 }
 
-impl From<CliSubtitleType> for SubtitleType {
-    fn from(cli_type: CliSubtitleType) -> Self {
-        match cli_type {
-            CliSubtitleType::Srt => SubtitleType::Srt,
-            CliSubtitleType::Vtt => SubtitleType::Vtt,
-            CliSubtitleType::Txt => SubtitleType::Txt,
-            CliSubtitleType::Json => SubtitleType::Json,
-            CliSubtitleType::Raw => SubtitleType::Raw,
+/// Whether `path` is the stdout sentinel `-`
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new(STDOUT_PATH)
+}
+
+/// Print a status message: to stdout normally, to stderr when subtitles go to stdout
+macro_rules! status {
+    ($cli:expr, $($arg:tt)*) => {
+        if $cli.to_stdout() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
         }
-    }
+    };
+}
+
+/// `--format` values, generated from every `SubtitleType`
+fn format_parser() -> impl TypedValueParser<Value = SubtitleType> {
+    PossibleValuesParser::new(SubtitleType::ALL.iter().map(|format| format.name()))
+        .map(|name| name.parse().expect("every format name parses"))
 }
 
 #[tokio::main]
 async fn main() -> YdlResult<()> {
     let cli = Cli::parse();
 
+    if let Some(shell) = cli.completions {
+        print_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
     // Initialize logging
-    init_logging(cli.verbose);
+    init_logging(cli.verbose, cli.trace_network, cli.to_stdout());
 
-    info!("Starting ydl for URL: {}", cli.url);
+    // Surface filesystem problems before any network request
+    if let Some(dir) = output_target_dir(&cli) {
+        exit_on_error(preflight_output_dir(&dir, !cli.no_create_dirs).await)?;
+    }
+
+    if let Some(batch) = &cli.batch {
+        return exit_on_error(run_batch(batch, &build_options(&cli), &cli).await);
+    }
+    // clap requires the URL whenever no completion script or batch file is given
+    let url = cli.url.clone().unwrap_or_default();
+
+    info!("Starting ydl for URL: {}", url);
 
     // Build options from CLI arguments
-    let options = build_options(&cli);
+    let mut options = build_options(&cli);
+
+    // "Watch multiple" links expand into one download per video
+    let parser = YouTubeParser::new();
+    if parser.is_watch_videos_url(&url) {
+        let video_ids = parser.parse_watch_videos_url(&url, cli.skip_invalid_ids)?;
+        status!(cli, "Found {} videos in watch_videos link", video_ids.len());
+        return exit_on_error(run_videos(watch_urls(&video_ids), &options, &cli, false).await);
+    }
+
+    // Playlist links (or a video in a playlist, with --from-index) expand the same way
+    let position = parser.parse_playlist_position(&url);
+    if cli.from_index && position.is_none() {
+        return Err(YdlError::Configuration {
+            message: format!(
+                "--from-index needs a URL with a list= parameter, got {}",
+                url
+            ),
+        });
+    }
+    if let Some(position) = position
+        && (position.video_id.is_none() || cli.from_index)
+    {
+        let mut video_ids = ydl::playlist_video_ids(&position.playlist, options.clone()).await?;
+        status!(cli, "Found {} videos in playlist", video_ids.len());
+        if cli.from_index {
+            video_ids = position.remaining(video_ids);
+            status!(
+                cli,
+                "Starting from the current entry, {} videos left",
+                video_ids.len()
+            );
+        }
+        return exit_on_error(run_videos(watch_urls(&video_ids), &options, &cli, false).await);
+    }
+
+    // Channel links expand into the channel's uploads
+    if cli.channel_uploads {
+        let YouTubeRef::Channel(channel) = parser.classify_url(&url)? else {
+            return Err(YdlError::Configuration {
+                message: format!("--channel-uploads needs a channel URL, got {}", url),
+            });
+        };
+        let video_ids = ydl::channel_uploads(&channel, cli.limit, options.clone()).await?;
+        status!(cli, "Found {} uploads in channel", video_ids.len());
+        return exit_on_error(run_videos(watch_urls(&video_ids), &options, &cli, false).await);
+    }
+
+    if cli.from_start {
+        match parser
+            .parse_url_full(&url)
+            .ok()
+            .and_then(|parsed| parsed.start)
+        {
+            Some(start) => {
+                status!(
+                    cli,
+                    "Starting at {}s from the URL's start time",
+                    start.as_secs()
+                );
+                options = options.start_at(start);
+            }
+            None => eprintln!("Warning: --from-start given, but the URL has no start time"),
+        }
+    }
 
     // Create the downloader
-    let downloader = Ydl::new(&cli.url, options)?;
+    let downloader = Ydl::new(&url, options)?;
+
+    exit_on_error(run_operation(&downloader, &cli).await)
+}
+
+/// Explain a failed run on stderr and exit with status 1
+fn exit_on_error(result: YdlResult<()>) -> YdlResult<()> {
+    if let Err(e) = result {
+        handle_download_error(&e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Watch page URLs of `video_ids`
+fn watch_urls(video_ids: &[String]) -> Vec<String> {
+    video_ids
+        .iter()
+        .map(|video_id| format!("https://www.youtube.com/watch?v={}", video_id))
+        .collect()
+}
+
+/// Run the requested operation for each URL, honoring --resume
+///
+/// With `continue_on_error`, a failed URL is reported and the rest still run; a
+/// summary is printed at the end and the process exits with status 1 if any
+/// failed. Otherwise the first failure is returned.
+async fn run_videos(
+    urls: Vec<String>,
+    options: &YdlOptions,
+    cli: &Cli,
+    continue_on_error: bool,
+) -> YdlResult<()> {
+    let mut state = if cli.resume {
+        let state = load_resume_state(cli).await?;
+        status!(cli, "Recording progress in {}", state.path().display());
+        Some(state)
+    } else {
+        None
+    };
+
+    let mut failed = Vec::new();
+    for (i, url) in urls.iter().enumerate() {
+        if continue_on_error {
+            status!(cli, "\n[{}/{}] {}", i + 1, urls.len(), url);
+        }
+        let result = run_video(url, options, cli, state.as_mut()).await;
+        match result {
+            Ok(()) => {}
+            Err(e) if continue_on_error => {
+                handle_download_error(&e);
+                failed.push(url.as_str());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if continue_on_error {
+        status!(
+            cli,
+            "\nBatch finished: {} succeeded, {} failed",
+            urls.len() - failed.len(),
+            failed.len()
+        );
+        if !failed.is_empty() {
+            for url in &failed {
+                eprintln!("   failed: {}", url);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Resume state of this run: the same link or batch file, written in the same formats
+async fn load_resume_state(cli: &Cli) -> YdlResult<ResumeState> {
+    let dir = cli.output_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    let source = match &cli.batch {
+        Some(batch) => batch.display().to_string(),
+        None => cli.url.clone().unwrap_or_default(),
+    };
+    let operation = if cli.chapters {
+        "chapters"
+    } else if cli.generate_blog {
+        "blog"
+    } else if cli.variants {
+        "variants"
+    } else {
+        "subtitles"
+    };
+    let formats = match &cli.formats {
+        Some(formats) => formats.iter().map(SubtitleType::name).collect::<Vec<_>>(),
+        None => vec![cli.format.name()],
+    };
+
+    let run = format!("{}\n{} {}", source, operation, formats.join(","));
+    ResumeState::load(&dir, &run).await
+}
+
+/// Run the requested operation for one URL of a multi-video run, unless `state`
+/// records its video as completed by a previous run
+async fn run_video(
+    url: &str,
+    options: &YdlOptions,
+    cli: &Cli,
+    state: Option<&mut ResumeState>,
+) -> YdlResult<()> {
+    let downloader = Ydl::new(url, options.clone())?;
+    let video_id = downloader.video_id();
+    if let Some(state) = &state
+        && state.is_complete(video_id)
+    {
+        status!(cli, "Skipping {} (completed in a previous run)", video_id);
+        return Ok(());
+    }
+
+    run_operation(&downloader, cli).await?;
+
+    if let Some(state) = state {
+        state.mark_complete(video_id).await?;
+    }
+    Ok(())
+}
+
+/// Run the requested operation for each URL of a `--batch` list, continuing past failures
+async fn run_batch(batch: &Path, options: &YdlOptions, cli: &Cli) -> YdlResult<()> {
+    let content = if batch == Path::new(STDIN_PATH) {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        fs::read_to_string(batch).await?
+    };
+    let urls: Vec<String> = batch_urls(&content)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    status!(cli, "Found {} URLs in batch list", urls.len());
+
+    run_videos(urls, options, cli, true).await
+}
+
+/// URLs of a batch list: one per line, skipping blank lines and `#` comments
+fn batch_urls(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// Execute the requested operation for a single video
+async fn run_operation(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
+    // `--stdout` conflicts with these in clap; `-o -` has to be checked here
+    if cli.to_stdout() && (cli.formats.is_some() || cli.variants || cli.generate_blog) {
+        return Err(YdlError::Configuration {
+            message: "Only a single subtitle format can be written to stdout".to_string(),
+        });
+    }
 
-    // Execute the requested operation
     if cli.list {
-        list_subtitles(&downloader).await?;
+        list_subtitles(downloader).await?;
+    } else if cli.diagnose {
+        diagnose_clients(downloader).await;
     } else if cli.info {
-        show_metadata(&downloader).await?;
+        show_metadata(downloader).await?;
+    } else if cli.chapters {
+        download_chapters(downloader, cli.format, cli).await?;
     } else if cli.generate_blog {
-        generate_blog(&downloader, &cli).await?;
+        generate_blog(downloader, cli).await?;
+    } else if cli.variants {
+        download_variants(downloader, cli.format, cli).await?;
     } else if let Some(formats) = &cli.formats {
-        download_multiple_formats(&downloader, formats, &cli).await?;
+        download_multiple_formats(downloader, formats, cli).await?;
     } else {
-        download_single_format(&downloader, cli.format.into(), &cli).await?;
+        download_single_format(downloader, cli.format, cli).await?;
     }
 
     Ok(())
 }
 
+/// Write the completion script for `shell` to `out`
+fn print_completions(shell: Shell, out: &mut dyn std::io::Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
 /// Initialize logging based on verbosity level
-fn init_logging(verbose: bool) {
-    let env_filter = if verbose {
+///
+/// Logs go to stderr when `to_stderr` is set, keeping stdout for the subtitles.
+fn init_logging(verbose: bool, trace_network: bool, to_stderr: bool) {
+    let mut env_filter = if verbose {
         tracing_subscriber::EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| "ydl_cli=debug,ydl=debug".into())
     } else {
@@ -157,11 +648,24 @@ fn init_logging(verbose: bool) {
             .unwrap_or_else(|_| "ydl_cli=info,ydl=info".into())
     };
 
+    if trace_network {
+        let directive = format!("{}=trace", ydl::network::TRACE_TARGET)
+            .parse()
+            .expect("Valid network trace directive");
+        env_filter = env_filter.add_directive(directive);
+    }
+
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::fmt::layer()
                 .with_target(false)
-                .with_level(verbose),
+                .with_level(verbose)
+                .with_writer(writer),
         )
         .with(env_filter)
         .init();
@@ -172,15 +676,27 @@ fn build_options(cli: &Cli) -> YdlOptions {
     let mut options = YdlOptions::new()
         .allow_auto_generated(!cli.no_auto) // Inverted logic - auto is allowed by default
         .prefer_manual(!cli.no_prefer_manual)
+        .no_translations(cli.no_translations)
+        .create_dirs(!cli.no_create_dirs)
         .clean_content(!cli.no_clean)
+        .preserve_line_breaks(cli.preserve_line_breaks)
         .validate_timing(!cli.no_validate)
         .max_retries(cli.max_retries)
-        .timeout(cli.timeout);
+        .read_timeout(cli.read_timeout)
+        .connect_timeout(cli.connect_timeout)
+        .timestamp_links(cli.timestamp_links)
+        .detect_speakers(cli.detect_speakers)
+        .strict_output(cli.strict)
+        .select_by_quality(cli.select_by_quality);
 
     if let Some(language) = &cli.language {
         options = options.language(language);
     }
 
+    if let Some(timeout) = cli.timeout {
+        options = options.timeout(timeout);
+    }
+
     if let Some(user_agent) = &cli.user_agent {
         options = options.user_agent(user_agent);
     }
@@ -189,9 +705,107 @@ fn build_options(cli: &Cli) -> YdlOptions {
         options = options.proxy(proxy);
     }
 
+    if let Some(dir) = &cli.output_dir {
+        options = options.output_dir(dir);
+    }
+
+    if let Some(cookies) = &cli.cookies {
+        options = options.cookies(cookies);
+    }
+
+    if let Some(source_format) = cli.source_fmt {
+        options = options.source_format(source_format);
+    }
+
+    if let Some(segmentation) = cli.segmentation {
+        options = options.segmentation(segmentation);
+    }
+
+    if let Some(timecode) = cli.timecode {
+        options = options.timecode(timecode);
+    }
+
+    if let Some(fps) = cli.frame_rate {
+        options = options.frame_rate(fps);
+    }
+
+    if let Some(fraction) = cli.min_coverage {
+        options = options.min_caption_coverage(fraction);
+    }
+
+    if let Some(gap) = cli.trim_silence {
+        options = options.trim_silence(std::time::Duration::from_secs(gap));
+    }
+
+    if let Some(offset) = cli.shift_ms {
+        options = options.shift_ms(offset);
+    }
+
+    if let Some(clients) = &cli.client {
+        options = options.innertube_clients(clients.clone());
+    }
+
+    if let Some(start) = cli.rebase {
+        options = options.rebase(start);
+    }
+
+    if let Some(length) = cli.max_line_len {
+        options = options
+            .max_line_length(length as usize)
+            .max_lines(cli.max_lines as usize);
+    }
+
+    if let Some(gap) = cli.txt_paragraphs {
+        options = options.txt_paragraphs(std::time::Duration::from_secs(gap));
+    }
+
+    if let Some(mpegts) = cli.hls_mpegts {
+        options = options.vtt_timestamp_map(mpegts);
+    }
+
+    if let Some(context) = &cli.innertube_context {
+        options = options.innertube_context(context.clone());
+    }
+
     options
 }
 
+/// Parse `HH:MM:SS`, `MM:SS` or plain seconds, each with optional fractional seconds
+fn parse_time(value: &str) -> Result<std::time::Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid time \"{}\", expected HH:MM:SS, MM:SS or seconds",
+            value
+        )
+    };
+
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let (seconds, minutes) = parts.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 || (!minutes.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+
+    let mut whole = 0u64;
+    for (i, part) in minutes.iter().enumerate() {
+        let part: u64 = part.parse().map_err(|_| invalid())?;
+        // Minutes are below 60 when hours precede them
+        if i > 0 && part >= 60 {
+            return Err(invalid());
+        }
+        whole = whole * 60 + part;
+    }
+
+    Ok(std::time::Duration::from_secs(whole * 60) + std::time::Duration::from_secs_f64(seconds))
+}
+
+fn parse_json(value: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(value).map_err(|e| format!("invalid JSON: {}", e))
+}
+
 /// Generate technical blog from subtitles
 async fn generate_blog(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
     println!(
@@ -226,7 +840,15 @@ async fn generate_blog(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
             match downloader.subtitle_with_retry(SubtitleType::Txt).await {
                 Ok(content) => {
                     // Save the text file for future reference
-                    if let Err(e) = write_subtitle_file(&text_path, &content, cli.force).await {
+                    if let Err(e) = write_subtitle_file(
+                        &text_path,
+                        &content,
+                        cli.force,
+                        cli.verify_write,
+                        !cli.no_create_dirs,
+                    )
+                    .await
+                    {
                         eprintln!("Warning: Could not save text file: {}", e);
                     } else {
                         println!("Saved plain text to: {}", text_path.display());
@@ -241,6 +863,13 @@ async fn generate_blog(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
         }
     };
 
+    // Caption fragments make for choppy prompts, so reflow them into prose first
+    let subtitle_content = if cli.raw_transcript {
+        subtitle_content
+    } else {
+        prepare_transcript(&subtitle_content)
+    };
+
     // Get video metadata for context
     let metadata = match downloader.metadata().await {
         Ok(metadata) => metadata,
@@ -252,11 +881,32 @@ async fn generate_blog(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
     };
 
     // Initialize blog generator
-    let blog_generator = match BlogGenerator::new().await {
-        Ok(generator) => generator,
+    let generator = BlogGenerator::new()
+        .await
+        .and_then(|generator| match &cli.blog_proxy {
+            Some(proxy) => generator.with_proxy(proxy),
+            None => Ok(generator),
+        });
+    let blog_generator = match generator {
+        Ok(generator) => {
+            // Ctrl-C aborts the request cleanly instead of killing the process mid-write
+            let cancel = tokio_util::sync::CancellationToken::new();
+            let on_interrupt = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    on_interrupt.cancel();
+                }
+            });
+            generator
+                .with_timeout(std::time::Duration::from_secs(cli.blog_timeout))
+                .with_max_transcript_chars(Some(cli.blog_max_chars).filter(|&chars| chars > 0))
+                .with_cancellation(cancel)
+        }
         Err(e) => {
             eprintln!("❌ Failed to initialize blog generator: {}", e);
-            eprintln!("   Make sure OPENAI_API_KEY environment variable is set");
+            if std::env::var_os("OPENAI_API_KEY").is_none() {
+                eprintln!("   Make sure OPENAI_API_KEY environment variable is set");
+            }
             std::process::exit(1);
         }
     };
@@ -270,25 +920,15 @@ async fn generate_blog(downloader: &Ydl, cli: &Cli) -> YdlResult<()> {
     {
         Ok(blog_content) => {
             // Determine output path for blog using title slug
-            let blog_filename = if !metadata.title.is_empty() {
-                let slug = create_slug(&metadata.title);
-                if !slug.is_empty() {
-                    format!("{}_blog.md", slug)
-                } else {
-                    format!("{}_blog.md", downloader.video_id())
-                }
-            } else {
-                format!("{}_blog.md", downloader.video_id())
-            };
+            let blog_filename = format!(
+                "{}_blog.md",
+                file_stem(&metadata.title, downloader.video_id(), cli.min_slug_length)
+            );
 
-            let blog_path = if let Some(dir) = &cli.output_dir {
-                dir.join(blog_filename)
-            } else {
-                PathBuf::from(blog_filename)
-            };
+            let blog_path = downloader.output_path(&blog_filename);
 
             // Write the blog content
-            match write_blog_file(&blog_path, &blog_content, cli.force).await {
+            match write_blog_file(&blog_path, &blog_content, cli.force, !cli.no_create_dirs).await {
                 Ok(_) => {
                     println!(
                         "✅ Successfully generated technical blog: {}",
@@ -325,22 +965,32 @@ async fn list_subtitles(downloader: &Ydl) -> YdlResult<()> {
                 return Ok(());
             }
 
+            let has_default = tracks.iter().any(|track| track.is_default);
             println!("\nAvailable subtitle tracks:");
             println!(
-                "{:<8} {:<20} {:<15} {:<12}",
-                "Code", "Name", "Type", "Translatable"
+                "{:<8} {:<20} {:<15} {:<12} {:<10}",
+                "Code", "Name", "Type", "Translatable", "VSS ID"
             );
-            println!("{}", "─".repeat(60));
+            println!("{}", "─".repeat(70));
 
             for track in tracks {
+                let code = if track.is_default {
+                    format!("{}*", track.language_code)
+                } else {
+                    track.language_code.clone()
+                };
                 println!(
-                    "{:<8} {:<20} {:<15} {:<12}",
-                    track.language_code,
+                    "{:<8} {:<20} {:<15} {:<12} {:<10}",
+                    code,
                     truncate(&track.language_name, 20),
                     track.track_type.to_string(),
-                    if track.is_translatable { "Yes" } else { "No" }
+                    if track.is_translatable { "Yes" } else { "No" },
+                    track.vss_id.as_deref().unwrap_or("-")
                 );
             }
+            if has_default {
+                println!("\n* YouTube's default track");
+            }
         }
         Err(e) => {
             eprintln!("Error discovering subtitles: {}", e);
@@ -351,6 +1001,105 @@ async fn list_subtitles(downloader: &Ydl) -> YdlResult<()> {
     Ok(())
 }
 
+/// Let the user pick one of the video's tracks from a numbered menu on the terminal
+///
+/// The menu goes to stderr when `to_stderr` is set, so it does not mix with the subtitles.
+async fn choose_track(downloader: &Ydl, to_stderr: bool) -> YdlResult<SubtitleTrack> {
+    if !std::io::stdin().is_terminal() {
+        return Err(YdlError::Configuration {
+            message: "--interactive needs a terminal; use --language or --vss-id (see --list) \
+                      to choose a track instead"
+                .to_string(),
+        });
+    }
+
+    let mut tracks = downloader.available_subtitles().await?;
+    if tracks.is_empty() {
+        return Err(YdlError::NoSubtitlesAvailable {
+            video_id: downloader.video_id().to_string(),
+        });
+    }
+
+    let mut out: Box<dyn Write> = if to_stderr {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+    let index = prompt_track(&tracks, &mut std::io::stdin().lock(), &mut out)?;
+    Ok(tracks.swap_remove(index))
+}
+
+/// Print a numbered menu of `tracks` and read choices from `input` until one is valid
+fn prompt_track(
+    tracks: &[SubtitleTrack],
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> YdlResult<usize> {
+    writeln!(out, "\nAvailable subtitle tracks:")?;
+    for (i, track) in tracks.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>3}) {:<8} {:<20} {}",
+            i + 1,
+            track.language_code,
+            truncate(&track.language_name, 20),
+            track.track_type
+        )?;
+    }
+
+    loop {
+        write!(out, "Choose a track [1-{}]: ", tracks.len())?;
+        out.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Err(YdlError::Configuration {
+                message: "No subtitle track selected".to_string(),
+            });
+        }
+        match parse_selection(&line, tracks) {
+            Some(index) => return Ok(index),
+            None => writeln!(out, "Invalid choice: {}", line.trim())?,
+        }
+    }
+}
+
+/// Parse a menu choice: a track number, or a VSS ID or language code from the menu
+fn parse_selection(input: &str, tracks: &[SubtitleTrack]) -> Option<usize> {
+    let input = input.trim();
+    if let Ok(number) = input.parse::<usize>() {
+        return (1..=tracks.len()).contains(&number).then(|| number - 1);
+    }
+
+    tracks
+        .iter()
+        .position(|track| track.vss_id.as_deref() == Some(input))
+        .or_else(|| {
+            tracks
+                .iter()
+                .position(|track| track.language_code.eq_ignore_ascii_case(input))
+        })
+}
+
+/// Print what each InnerTube client returns for the video
+async fn diagnose_clients(downloader: &Ydl) {
+    println!(
+        "Probing InnerTube clients for video: {}",
+        downloader.video_id()
+    );
+
+    println!("\n{:<32} {:<8} Result", "Client", "Tracks");
+    println!("{}", "─".repeat(70));
+
+    for (client, result) in downloader.probe_all_clients().await {
+        match result {
+            Ok(0) => println!("{:<32} {:<8} no caption tracks", client.client_name(), 0),
+            Ok(count) => println!("{:<32} {:<8} ok", client.client_name(), count),
+            Err(e) => println!("{:<32} {:<8} {}", client.client_name(), "-", e),
+        }
+    }
+}
+
 /// Show video metadata
 async fn show_metadata(downloader: &Ydl) -> YdlResult<()> {
     println!("Getting metadata for video: {}", downloader.video_id());
@@ -371,6 +1120,10 @@ async fn show_metadata(downloader: &Ydl) -> YdlResult<()> {
 
             println!("URL: {}", downloader.normalized_url());
 
+            if let Some(thumbnail_url) = &metadata.thumbnail_url {
+                println!("Thumbnail: {}", thumbnail_url);
+            }
+
             if !metadata.available_subtitles.is_empty() {
                 println!(
                     "\nAvailable Subtitles: {} tracks",
@@ -398,27 +1151,146 @@ async fn download_single_format(
     format: SubtitleType,
     cli: &Cli,
 ) -> YdlResult<()> {
-    println!(
+    status!(
+        cli,
         "Downloading {} subtitles for video: {}",
         format,
         downloader.video_id()
     );
 
-    match downloader.subtitle_with_retry(format).await {
+    let track = if cli.interactive {
+        Some(choose_track(downloader, cli.to_stdout()).await?)
+    } else {
+        None
+    };
+
+    // Refuse to overwrite before spending any bandwidth on the download
+    let output_path = determine_output_path(downloader, format, cli).await?;
+    preflight_output_path(&output_path, cli.force)?;
+
+    // A raw capture in an explicitly chosen source format is saved exactly as received
+    let result = if let Some(track) = &track {
+        downloader
+            .subtitle_with_track(track, format)
+            .await
+            .map(|result| result.content)
+    } else if let Some(vss_id) = &cli.vss_id {
+        downloader.subtitle_by_vss_id(vss_id, format).await
+    } else if let Some(target_lang) = &cli.translate_to {
+        downloader
+            .translated_subtitle(target_lang, format)
+            .await
+            .map(|result| result.content)
+    } else if format == SubtitleType::Raw && cli.source_fmt.is_some() {
+        downloader.raw_subtitle().await
+    } else {
+        downloader.subtitle_with_retry(format).await
+    };
+
+    let content = result?;
+    write_subtitle_file(
+        &output_path,
+        &content,
+        cli.force,
+        cli.verify_write,
+        !cli.no_create_dirs,
+    )
+    .await?;
+
+    if !cli.to_stdout() {
+        println!("Successfully saved subtitles to: {}", output_path.display());
+    }
+    info!(
+        "Downloaded {} characters of {} content",
+        content.len(),
+        format
+    );
+
+    // If we downloaded SRT format, also save a plain text version
+    if format == SubtitleType::Srt && !cli.to_stdout() {
+        save_plain_text_version(downloader, track.as_ref(), &output_path, cli).await?;
+    }
+
+    Ok(())
+}
+
+/// Download the video's chapter list rendered as subtitles
+async fn download_chapters(downloader: &Ydl, format: SubtitleType, cli: &Cli) -> YdlResult<()> {
+    status!(
+        cli,
+        "Exporting chapters as {} for video: {}",
+        format,
+        downloader.video_id()
+    );
+
+    let mut output_path = determine_output_path(downloader, format, cli).await?;
+    if cli.output.is_none()
+        && !cli.to_stdout()
+        && let Some(stem) = output_path.file_stem().and_then(|s| s.to_str())
+    {
+        output_path.set_file_name(format!("{}_chapters.{}", stem, format.extension()));
+    }
+    preflight_output_path(&output_path, cli.force)?;
+
+    match downloader.chapters_as_subtitles(format).await {
         Ok(content) => {
-            let output_path = determine_output_path(downloader, format, cli).await?;
-            write_subtitle_file(&output_path, &content, cli.force).await?;
+            write_subtitle_file(
+                &output_path,
+                &content,
+                cli.force,
+                cli.verify_write,
+                !cli.no_create_dirs,
+            )
+            .await?;
+            if !cli.to_stdout() {
+                println!("Successfully saved chapters to: {}", output_path.display());
+            }
+        }
+        Err(e) => {
+            handle_download_error(&e);
+            std::process::exit(1);
+        }
+    }
 
-            println!("Successfully saved subtitles to: {}", output_path.display());
-            info!(
-                "Downloaded {} characters of {} content",
-                content.len(),
-                format
-            );
+    Ok(())
+}
 
-            // If we downloaded SRT format, also save a plain text version
-            if format == SubtitleType::Srt {
-                save_plain_text_version(downloader, &output_path, cli).await?;
+/// Download every track type of the requested language, one file per track
+async fn download_variants(downloader: &Ydl, format: SubtitleType, cli: &Cli) -> YdlResult<()> {
+    let language = cli.language.as_deref().unwrap_or("en");
+    println!(
+        "Downloading {} subtitle variants for video: {}",
+        language,
+        downloader.video_id()
+    );
+
+    let base_path = determine_output_path(downloader, format, cli).await?;
+    let stem = base_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_else(|| downloader.video_id())
+        .to_string();
+
+    match downloader.subtitle_variants(language, format).await {
+        Ok(results) => {
+            for result in results {
+                let output_path = base_path.with_file_name(result.variant_file_name(&stem));
+                preflight_output_path(&output_path, cli.force)?;
+                write_subtitle_file(
+                    &output_path,
+                    &result.content,
+                    cli.force,
+                    cli.verify_write,
+                    !cli.no_create_dirs,
+                )
+                .await?;
+
+                println!(
+                    "Saved {} {} subtitles to: {}",
+                    result.track_type,
+                    result.language,
+                    output_path.display()
+                );
             }
         }
         Err(e) => {
@@ -433,10 +1305,10 @@ async fn download_single_format(
 /// Download multiple subtitle formats
 async fn download_multiple_formats(
     downloader: &Ydl,
-    formats: &[CliSubtitleType],
+    formats: &[SubtitleType],
     cli: &Cli,
 ) -> YdlResult<()> {
-    let subtitle_types: Vec<SubtitleType> = formats.iter().map(|f| (*f).into()).collect();
+    let subtitle_types = formats.to_vec();
 
     println!(
         "Downloading {} formats for video: {}",
@@ -444,11 +1316,23 @@ async fn download_multiple_formats(
         downloader.video_id()
     );
 
+    for subtitle_type in &subtitle_types {
+        let output_path = determine_output_path(downloader, *subtitle_type, cli).await?;
+        preflight_output_path(&output_path, cli.force)?;
+    }
+
     match downloader.subtitles(&subtitle_types).await {
         Ok(results) => {
             for result in results {
                 let output_path = determine_output_path(downloader, result.format, cli).await?;
-                write_subtitle_file(&output_path, &result.content, cli.force).await?;
+                write_subtitle_file(
+                    &output_path,
+                    &result.content,
+                    cli.force,
+                    cli.verify_write,
+                    !cli.no_create_dirs,
+                )
+                .await?;
 
                 println!(
                     "Saved {} subtitles to: {}",
@@ -464,7 +1348,7 @@ async fn download_multiple_formats(
 
                 // If we downloaded SRT format, also save a plain text version
                 if result.format == SubtitleType::Srt {
-                    save_plain_text_version(downloader, &output_path, cli).await?;
+                    save_plain_text_version(downloader, None, &output_path, cli).await?;
                 }
             }
 
@@ -483,15 +1367,36 @@ async fn download_multiple_formats(
 }
 
 /// Save a plain text version of the subtitles (for SRT files)
-async fn save_plain_text_version(downloader: &Ydl, srt_path: &Path, cli: &Cli) -> YdlResult<()> {
+///
+/// Uses `track` if one was picked interactively, so both files hold the same captions.
+async fn save_plain_text_version(
+    downloader: &Ydl,
+    track: Option<&SubtitleTrack>,
+    srt_path: &Path,
+    cli: &Cli,
+) -> YdlResult<()> {
     // Download the subtitles as plain text
-    match downloader.subtitle_with_retry(SubtitleType::Txt).await {
+    let text = match track {
+        Some(track) => downloader
+            .subtitle_with_track(track, SubtitleType::Txt)
+            .await
+            .map(|result| result.content),
+        None => downloader.subtitle_with_retry(SubtitleType::Txt).await,
+    };
+    match text {
         Ok(text_content) => {
             // Create the text file path by replacing the extension
             let text_path = srt_path.with_extension("txt");
 
             // Write the plain text file
-            write_subtitle_file(&text_path, &text_content, cli.force).await?;
+            write_subtitle_file(
+                &text_path,
+                &text_content,
+                cli.force,
+                cli.verify_write,
+                !cli.no_create_dirs,
+            )
+            .await?;
 
             println!("Also saved plain text to: {}", text_path.display());
             info!(
@@ -533,12 +1438,42 @@ fn create_slug(title: &str) -> String {
         .collect()
 }
 
+/// Default for --min-slug-length
+const DEFAULT_MIN_SLUG_LENGTH: usize = 3;
+
+/// Words that carry no meaning on their own in a filename
+const SLUG_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "at", "by", "for", "in", "is", "it", "of", "on", "or", "the", "to",
+];
+
+/// Build a filename stem from a video title, falling back to the video ID
+///
+/// A slug that is empty becomes the video ID; one that is shorter than `min_len`
+/// or made up only of stopwords keeps the slug but gets the video ID appended
+/// (`<slug>-<id>`), so videos with short titles like "Q&A" do not collide.
+fn file_stem(title: &str, video_id: &str, min_len: usize) -> String {
+    let slug = create_slug(title);
+    if slug.is_empty() {
+        return video_id.to_string();
+    }
+
+    let only_stopwords = slug.split('-').all(|word| SLUG_STOPWORDS.contains(&word));
+    if slug.chars().count() < min_len || only_stopwords {
+        format!("{}-{}", slug, video_id)
+    } else {
+        slug
+    }
+}
+
 /// Determine the output file path
 async fn determine_output_path(
     downloader: &Ydl,
     format: SubtitleType,
     cli: &Cli,
 ) -> YdlResult<PathBuf> {
+    if cli.to_stdout() {
+        return Ok(PathBuf::from(STDOUT_PATH));
+    }
     if let Some(output) = &cli.output {
         return Ok(output.clone());
     }
@@ -546,13 +1481,8 @@ async fn determine_output_path(
     // Try to get video title for filename
     let filename = match downloader.metadata().await {
         Ok(metadata) if !metadata.title.is_empty() => {
-            let slug = create_slug(&metadata.title);
-            if !slug.is_empty() {
-                format!("{}.{}", slug, format.extension())
-            } else {
-                // Fallback to video ID if slug is empty
-                format!("{}.{}", downloader.video_id(), format.extension())
-            }
+            let stem = file_stem(&metadata.title, downloader.video_id(), cli.min_slug_length);
+            format!("{}.{}", stem, format.extension())
         }
         _ => {
             // Fallback to video ID if metadata fetch fails
@@ -560,16 +1490,46 @@ async fn determine_output_path(
         }
     };
 
-    if let Some(dir) = &cli.output_dir {
-        Ok(dir.join(filename))
-    } else {
-        Ok(PathBuf::from(filename))
+    Ok(downloader.output_path(&filename))
+}
+
+/// Directory the requested operation saves its files in, or `None` if it saves nothing
+fn output_target_dir(cli: &Cli) -> Option<PathBuf> {
+    if cli.list || cli.diagnose || cli.info || cli.to_stdout() {
+        return None;
     }
+    let dir = match &cli.output {
+        Some(output) => output
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(Path::to_path_buf),
+        None => cli.output_dir.clone(),
+    };
+    Some(dir.unwrap_or_else(|| PathBuf::from(".")))
 }
 
-/// Write subtitle content to file
-async fn write_subtitle_file(path: &PathBuf, content: &str, force: bool) -> YdlResult<()> {
-    // Check if file exists and force flag
+/// Verify that `dir` exists or (if `create_dirs` is set) can be created, and that it
+/// is writable, before discovery starts
+async fn preflight_output_dir(dir: &Path, create_dirs: bool) -> YdlResult<()> {
+    ydl::output::ensure_dir(dir, create_dirs).await?;
+
+    // Probe writability with a temporary file that is removed on drop
+    tempfile::NamedTempFile::new_in(dir).map_err(|e| YdlError::FileSystem {
+        source: std::io::Error::new(
+            e.kind(),
+            format!("Output directory is not writable: {}: {}", dir.display(), e),
+        ),
+    })?;
+
+    Ok(())
+}
+
+/// Verify that the output file does not already exist (unless `force` is set) before
+/// starting a download
+fn preflight_output_path(path: &Path, force: bool) -> YdlResult<()> {
+    if is_stdout(path) {
+        return Ok(());
+    }
     if path.exists() && !force {
         return Err(YdlError::FileSystem {
             source: std::io::Error::new(
@@ -582,22 +1542,89 @@ async fn write_subtitle_file(path: &PathBuf, content: &str, force: bool) -> YdlR
         });
     }
 
-    // Create parent directories if needed
-    if let Some(parent) = path.parent()
-        && !parent.exists()
-    {
-        fs::create_dir_all(parent).await?;
+    Ok(())
+}
+
+/// Write subtitle content to file, or to stdout if `path` is `-`
+async fn write_subtitle_file(
+    path: &PathBuf,
+    content: &str,
+    force: bool,
+    verify: bool,
+    create_dirs: bool,
+) -> YdlResult<()> {
+    if is_stdout(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()?;
+        return Ok(());
     }
 
-    // Write the file
-    fs::write(path, content).await?;
+    // Check if file exists and force flag
+    if path.exists() && !force {
+        return Err(YdlError::FileSystem {
+            source: std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "File already exists: {}. Use --force to overwrite.",
+                    path.display()
+                ),
+            ),
+        });
+    }
+
+    // Create parent directories if needed and allowed
+    ydl::output::ensure_output_dir(path, create_dirs).await?;
+
+    // Write the file, syncing it to disk first when it is going to be verified
+    if verify {
+        use tokio::io::AsyncWriteExt;
+
+        let mut file = fs::File::create(path).await?;
+        file.write_all(content.as_bytes()).await?;
+        file.sync_all().await?;
+        verify_written_file(path, content).await?;
+    } else {
+        fs::write(path, content).await?;
+    }
 
     debug!("Written {} bytes to {}", content.len(), path.display());
     Ok(())
 }
 
+/// Check that the file at `path` holds exactly `content`, catching truncated writes
+async fn verify_written_file(path: &Path, content: &str) -> YdlResult<()> {
+    let written = fs::read(path).await?;
+    if written != content.as_bytes() {
+        return Err(YdlError::FileSystem {
+            source: std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Verification failed for {}: expected {} bytes, found {} bytes{}",
+                    path.display(),
+                    content.len(),
+                    written.len(),
+                    if written.len() == content.len() {
+                        " with different content"
+                    } else {
+                        ""
+                    }
+                ),
+            ),
+        });
+    }
+
+    debug!("Verified {} bytes in {}", written.len(), path.display());
+    Ok(())
+}
+
 /// Write blog content to file
-async fn write_blog_file(path: &PathBuf, content: &str, force: bool) -> YdlResult<()> {
+async fn write_blog_file(
+    path: &PathBuf,
+    content: &str,
+    force: bool,
+    create_dirs: bool,
+) -> YdlResult<()> {
     // Check if file exists and force flag
     if path.exists() && !force {
         return Err(YdlError::FileSystem {
@@ -611,12 +1638,8 @@ async fn write_blog_file(path: &PathBuf, content: &str, force: bool) -> YdlResul
         });
     }
 
-    // Create parent directories if needed
-    if let Some(parent) = path.parent()
-        && !parent.exists()
-    {
-        fs::create_dir_all(parent).await?;
-    }
+    // Create parent directories if needed and allowed
+    ydl::output::ensure_output_dir(path, create_dirs).await?;
 
     // Write the file
     fs::write(path, content).await?;
@@ -646,9 +1669,31 @@ fn handle_download_error(error: &YdlError) {
             eprintln!("❌ Video is age-restricted: {}", video_id);
             eprintln!("   Age verification is required to access this content.");
         }
+        YdlError::ConsentRequired { url } => {
+            eprintln!("❌ YouTube asked for cookie consent: {}", url);
+            eprintln!("   Accept it in a browser and pass its cookies with --cookies.");
+        }
         YdlError::NoSubtitlesAvailable { video_id } => {
             eprintln!("❌ No subtitles available for video: {}", video_id);
             eprintln!("   Try using --allow-auto to include auto-generated subtitles.");
+            eprintln!(
+                "   Some videos only list captions when signed in (--cookies) or to other clients (--client)."
+            );
+        }
+        YdlError::CaptionsDisabled { video_id } => {
+            eprintln!("❌ Captions are disabled for video: {}", video_id);
+            eprintln!(
+                "   The uploader turned captions off; this is a video setting, not a ydl bug."
+            );
+        }
+        YdlError::CaptionsUnavailableInRegion { video_id } => {
+            eprintln!("❌ Captions are blocked in this region: {}", video_id);
+            eprintln!("   The video plays, but every caption download came back empty.");
+            eprintln!("   Try again through a proxy in another region (--proxy).");
+        }
+        YdlError::NoChaptersAvailable { video_id } => {
+            eprintln!("❌ No chapters available for video: {}", video_id);
+            eprintln!("   The video description does not contain a chapter list.");
         }
         YdlError::OnlyAutoGenerated { video_id } => {
             eprintln!("❌ Only auto-generated subtitles available: {}", video_id);
@@ -658,6 +1703,16 @@ fn handle_download_error(error: &YdlError) {
             eprintln!("❌ Language not available: {}", language);
             eprintln!("   Use --list to see available subtitle languages.");
         }
+        YdlError::TrackNotAvailable { vss_id } => {
+            eprintln!("❌ Subtitle track not available: {}", vss_id);
+            eprintln!("   Use --list to see the VSS IDs of available tracks.");
+        }
+        YdlError::ProtectedCaptionUrl { video_id, reason } => {
+            eprintln!("❌ Caption download throttled: {}", video_id);
+            eprintln!("   YouTube protected the caption URL because {}.", reason);
+            eprintln!("   ydl cannot compute these parameters; try signed-in cookies (--cookies),");
+            eprintln!("   another InnerTube client (--client ios,android), or a later retry.");
+        }
         YdlError::RateLimited { retry_after } => {
             eprintln!("❌ Rate limited by YouTube");
             eprintln!("   Please wait {} seconds and try again.", retry_after);
@@ -694,15 +1749,108 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_cli_subtitle_type_conversion() {
-        assert_eq!(SubtitleType::from(CliSubtitleType::Srt), SubtitleType::Srt);
-        assert_eq!(SubtitleType::from(CliSubtitleType::Vtt), SubtitleType::Vtt);
-        assert_eq!(SubtitleType::from(CliSubtitleType::Txt), SubtitleType::Txt);
-        assert_eq!(
-            SubtitleType::from(CliSubtitleType::Json),
-            SubtitleType::Json
+    fn test_cli_accepts_every_subtitle_type() {
+        for format in SubtitleType::ALL {
+            let cli = Cli::try_parse_from(["ydl", "dQw4w9WgXcQ", "--format", format.name()])
+                .unwrap_or_else(|e| panic!("--format {format}: {e}"));
+            assert_eq!(cli.format, *format);
+        }
+
+        let names: Vec<&str> = SubtitleType::ALL.iter().map(|f| f.name()).collect();
+        let cli =
+            Cli::try_parse_from(["ydl", "dQw4w9WgXcQ", "--formats", &names.join(",")]).unwrap();
+        assert_eq!(cli.formats.as_deref(), Some(SubtitleType::ALL));
+
+        assert!(Cli::try_parse_from(["ydl", "dQw4w9WgXcQ", "--format", "docx"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resume_state_per_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().to_str().unwrap();
+        let playlist = "https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf";
+        let state = |args: &[&str]| {
+            let mut full = vec!["ydl", playlist, "--resume", "--output-dir", out];
+            full.extend_from_slice(args);
+            let cli = Cli::parse_from(full);
+            async move { load_resume_state(&cli).await.unwrap() }
+        };
+
+        let mut srt = state(&["-f", "srt"]).await;
+        srt.mark_complete("dQw4w9WgXcQ").await.unwrap();
+
+        // Running again in another format downloads everything again
+        assert!(state(&["-f", "srt"]).await.is_complete("dQw4w9WgXcQ"));
+        assert!(!state(&["-f", "vtt"]).await.is_complete("dQw4w9WgXcQ"));
+        assert!(
+            !state(&["--formats", "srt,vtt"])
+                .await
+                .is_complete("dQw4w9WgXcQ")
         );
-        assert_eq!(SubtitleType::from(CliSubtitleType::Raw), SubtitleType::Raw);
+
+        // So does an unrelated batch into the same directory
+        let batch = Cli::parse_from(["ydl", "--batch", "urls.txt", "--resume", "-D", out]);
+        let batch = load_resume_state(&batch).await.unwrap();
+        assert!(!batch.is_complete("dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn test_completions_for_each_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut script = Vec::new();
+            print_completions(shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            // --format values come from the value enum
+            assert!(script.contains("--format"), "{shell} completions");
+            assert!(script.contains("vtt"), "{shell} completions");
+        }
+
+        let cli = Cli::try_parse_from(["ydl", "--completions", "zsh"]).unwrap();
+        assert_eq!(cli.completions, Some(Shell::Zsh));
+        assert!(Cli::try_parse_from(["ydl"]).is_err());
+    }
+
+    #[test]
+    fn test_interactive_selection() {
+        let tracks = vec![
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                ydl::SubtitleTrackType::Manual,
+            )
+            .with_vss_id(".en".to_string()),
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English (auto-generated)".to_string(),
+                ydl::SubtitleTrackType::AutoGenerated,
+            )
+            .with_vss_id("a.en".to_string()),
+            SubtitleTrack::new(
+                "fr".to_string(),
+                "French".to_string(),
+                ydl::SubtitleTrackType::Manual,
+            ),
+        ];
+
+        assert_eq!(parse_selection("2\n", &tracks), Some(1));
+        assert_eq!(parse_selection(" a.en ", &tracks), Some(1));
+        assert_eq!(parse_selection("FR", &tracks), Some(2));
+        assert_eq!(parse_selection("0", &tracks), None);
+        assert_eq!(parse_selection("4", &tracks), None);
+        assert_eq!(parse_selection("de", &tracks), None);
+
+        // Invalid choices are reported and asked again
+        let mut out = Vec::new();
+        let index = prompt_track(&tracks, &mut "9\nxx\n3\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(index, 2);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("  2) en       English (auto-gen..."));
+        assert!(out.contains("Invalid choice: 9"));
+        assert!(out.contains("Invalid choice: xx"));
+
+        // Running out of input is an error rather than a hang
+        let result = prompt_track(&tracks, &mut "".as_bytes(), &mut Vec::new());
+        assert!(matches!(result, Err(YdlError::Configuration { .. })));
     }
 
     #[test]
@@ -724,33 +1872,29 @@ mod tests {
         assert_eq!(create_slug("CamelCase-Title_Here"), "camelcase-title-here");
     }
 
+    #[test]
+    fn test_file_stem_falls_back_to_video_id() {
+        let id = "dQw4w9WgXcQ";
+        let min = DEFAULT_MIN_SLUG_LENGTH;
+
+        assert_eq!(
+            file_stem("Rust in 100 Seconds", id, min),
+            "rust-in-100-seconds"
+        );
+        // A single character is too short to identify the video on its own
+        assert_eq!(file_stem("X", id, min), "x-dQw4w9WgXcQ");
+        // Emoji have no slug characters at all
+        assert_eq!(file_stem("🔥🔥🔥", id, min), "dQw4w9WgXcQ");
+        assert_eq!(file_stem("The", id, min), "the-dQw4w9WgXcQ");
+        assert_eq!(file_stem("X", id, 0), "x");
+    }
+
     #[tokio::test]
     async fn test_determine_output_path() {
         let options = YdlOptions::default();
         let downloader = Ydl::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ", options).unwrap();
 
-        let cli = Cli {
-            url: "test".to_string(),
-            format: CliSubtitleType::Srt,
-            language: None,
-            output: None,
-            output_dir: None,
-            list: false,
-            info: false,
-            no_auto: false,
-            no_prefer_manual: false,
-            no_clean: false,
-            no_validate: false,
-            max_retries: 3,
-            timeout: 30,
-            user_agent: None,
-            proxy: None,
-            verbose: false,
-            formats: None,
-            force: false,
-            generate_blog: false,
-            blog_lang: "chinese".to_string(),
-        };
+        let cli = Cli::parse_from(["ydl", "test"]);
 
         let path = determine_output_path(&downloader, SubtitleType::Srt, &cli)
             .await
@@ -759,6 +1903,149 @@ mod tests {
         assert!(!path.to_str().unwrap().is_empty());
     }
 
+    #[test]
+    fn test_batch_input() {
+        let list = "# lectures\n\
+            https://www.youtube.com/watch?v=dQw4w9WgXcQ\n\
+            \n   \n\
+              https://youtu.be/jNQXAC9IVRw  \n\
+            #https://youtu.be/skipped0000\n";
+        assert_eq!(
+            batch_urls(list),
+            vec![
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                "https://youtu.be/jNQXAC9IVRw"
+            ]
+        );
+
+        // The list replaces the URL argument
+        let cli = Cli::try_parse_from(["ydl", "--batch", "-", "-f", "vtt"]).unwrap();
+        assert_eq!(cli.batch.as_deref(), Some(Path::new(STDIN_PATH)));
+        assert!(Cli::try_parse_from(["ydl", "--batch", "urls.txt", "dQw4w9WgXcQ"]).is_err());
+        assert!(Cli::try_parse_from(["ydl", "--batch", "urls.txt", "--list"]).is_err());
+        assert!(Cli::try_parse_from(["ydl", "--batch", "urls.txt", "-o", "x.srt"]).is_err());
+        assert!(Cli::try_parse_from(["ydl", "--batch", "urls.txt", "--stdout"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stdout_mode() {
+        let downloader = Ydl::new(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            YdlOptions::default(),
+        )
+        .unwrap();
+
+        for args in [
+            &["ydl", "--stdout", "dQw4w9WgXcQ"][..],
+            &["ydl", "-o", "-", "dQw4w9WgXcQ"],
+        ] {
+            let cli = Cli::parse_from(args);
+            assert!(cli.to_stdout());
+            let path = determine_output_path(&downloader, SubtitleType::Srt, &cli)
+                .await
+                .unwrap();
+            assert!(is_stdout(&path));
+            preflight_output_path(&path, false).unwrap();
+        }
+        assert!(!Cli::parse_from(["ydl", "-o", "out.srt", "dQw4w9WgXcQ"]).to_stdout());
+
+        // Several files cannot share stdout
+        assert!(Cli::try_parse_from(["ydl", "--stdout", "--formats", "srt,vtt", "x"]).is_err());
+        let cli = Cli::parse_from(["ydl", "-o", "-", "--formats", "srt,vtt", "dQw4w9WgXcQ"]);
+        assert!(matches!(
+            run_operation(&downloader, &cli).await,
+            Err(YdlError::Configuration { .. })
+        ));
+    }
+
+    #[test]
+    fn test_timecode_flags() {
+        let cli = Cli::try_parse_from([
+            "ydl",
+            "--timecode",
+            "frames",
+            "--frame-rate",
+            "24",
+            "dQw4w9WgXcQ",
+        ])
+        .unwrap();
+        assert_eq!(cli.timecode, Some(Timecode::Frames));
+        assert_eq!(cli.frame_rate, Some(24.0));
+    }
+
+    #[test]
+    fn test_shift_ms_accepts_negative_offsets() {
+        let cli = Cli::try_parse_from(["ydl", "--shift-ms", "-1500", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.shift_ms, Some(-1500));
+        assert_eq!(cli.url.as_deref(), Some("dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn test_client_flag() {
+        let cli = Cli::try_parse_from(["ydl", "--client", "ios,web", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.client, Some(vec![ClientType::Ios, ClientType::Web]));
+        assert!(Cli::try_parse_from(["ydl", "--client", "ios,mweb", "dQw4w9WgXcQ"]).is_err());
+    }
+
+    #[test]
+    fn test_rebase_flag() {
+        let cli = Cli::try_parse_from(["ydl", "--rebase", "00:00:10", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.rebase, Some(std::time::Duration::from_secs(10)));
+
+        assert_eq!(
+            parse_time("1:02:03.5"),
+            Ok(std::time::Duration::from_millis(3_723_500))
+        );
+        assert_eq!(parse_time("2:30"), Ok(std::time::Duration::from_secs(150)));
+        assert_eq!(parse_time("0"), Ok(std::time::Duration::ZERO));
+        assert!(parse_time("1:75").is_err());
+        assert!(parse_time("1:60:00").is_err());
+        assert!(parse_time("-5").is_err());
+        assert!(parse_time("ten").is_err());
+    }
+
+    #[test]
+    fn test_max_line_len_flag() {
+        let cli = Cli::try_parse_from(["ydl", "--max-line-len", "42", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.max_line_len, Some(42));
+        assert_eq!(cli.max_lines, 2);
+
+        let cli = Cli::try_parse_from([
+            "ydl",
+            "--max-line-len",
+            "32",
+            "--max-lines",
+            "3",
+            "dQw4w9WgXcQ",
+        ])
+        .unwrap();
+        assert_eq!((cli.max_line_len, cli.max_lines), (Some(32), 3));
+
+        assert!(Cli::try_parse_from(["ydl", "--max-line-len", "0", "dQw4w9WgXcQ"]).is_err());
+    }
+
+    #[test]
+    fn test_txt_paragraphs_flag() {
+        let cli = Cli::try_parse_from(["ydl", "--txt-paragraphs", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.txt_paragraphs, Some(2));
+
+        let cli = Cli::try_parse_from(["ydl", "--txt-paragraphs=5", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.txt_paragraphs, Some(5));
+
+        let cli = Cli::try_parse_from(["ydl", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.txt_paragraphs, None);
+    }
+
+    #[test]
+    fn test_trim_silence_flag() {
+        let cli = Cli::try_parse_from(["ydl", "--trim-silence", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.trim_silence, Some(30));
+        assert_eq!(cli.url.as_deref(), Some("dQw4w9WgXcQ"));
+
+        let cli = Cli::try_parse_from(["ydl", "--trim-silence=45", "dQw4w9WgXcQ"]).unwrap();
+        assert_eq!(cli.trim_silence, Some(45));
+    }
+
     #[tokio::test]
     async fn test_write_subtitle_file_creates_dirs() {
         use tempfile::tempdir;
@@ -766,8 +2053,113 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let file_path = temp_dir.path().join("subdir").join("test.srt");
 
-        let result = write_subtitle_file(&file_path, "test content", false).await;
+        let err = write_subtitle_file(&file_path, "test content", false, false, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+        assert!(!file_path.exists());
+
+        let result = write_subtitle_file(&file_path, "test content", false, false, true).await;
         assert!(result.is_ok());
         assert!(file_path.exists());
     }
+
+    #[tokio::test]
+    async fn test_verify_write_detects_short_write() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("test.srt");
+        let content = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+        write_subtitle_file(&file_path, content, false, true, true)
+            .await
+            .unwrap();
+
+        // Simulate a write that was cut short by a full disk
+        std::fs::write(&file_path, &content[..10]).unwrap();
+        let err = verify_written_file(&file_path, content).await.unwrap_err();
+        assert!(err.to_string().contains("Verification failed"));
+
+        // Same length, different bytes
+        std::fs::write(&file_path, content.replace("Hello", "Hallo")).unwrap();
+        assert!(verify_written_file(&file_path, content).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_output_path() {
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+
+        // Missing directories are created up front
+        let nested = temp_dir.path().join("a").join("b");
+        assert!(preflight_output_dir(&nested, true).await.is_ok());
+        assert!(nested.is_dir());
+
+        // Existing files are rejected unless forced
+        let existing = temp_dir.path().join("existing.srt");
+        std::fs::write(&existing, "old").unwrap();
+        let err = preflight_output_path(&existing, false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert!(preflight_output_path(&existing, true).is_ok());
+
+        // Unless directory creation is disabled
+        let missing = temp_dir.path().join("c");
+        assert!(matches!(
+            preflight_output_dir(&missing, false).await,
+            Err(YdlError::FileSystem { .. })
+        ));
+        assert!(!missing.exists());
+
+        // A parent that is a regular file cannot be created as a directory
+        assert!(matches!(
+            preflight_output_dir(&existing.join("d"), true).await,
+            Err(YdlError::FileSystem { .. })
+        ));
+
+        // The directory checked up front is the one the files are saved in
+        let target = |args: &[&str]| output_target_dir(&Cli::parse_from(args));
+        assert_eq!(target(&["ydl", "dQw4w9WgXcQ"]), Some(PathBuf::from(".")));
+        assert_eq!(
+            target(&["ydl", "-D", "subs", "dQw4w9WgXcQ"]),
+            Some(PathBuf::from("subs"))
+        );
+        assert_eq!(
+            target(&["ydl", "-o", "out/talk.srt", "dQw4w9WgXcQ"]),
+            Some(PathBuf::from("out"))
+        );
+        assert_eq!(
+            target(&["ydl", "-o", "talk.srt", "x"]),
+            Some(PathBuf::from("."))
+        );
+        assert_eq!(target(&["ydl", "--stdout", "dQw4w9WgXcQ"]), None);
+        assert_eq!(target(&["ydl", "--list", "dQw4w9WgXcQ"]), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_preflight_read_only_dir() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+
+        // Root bypasses permission bits, so the check cannot fail; new files are
+        // owned by the effective user, which tells us who we run as
+        if std::fs::metadata(temp_dir.path()).unwrap().uid() == 0 {
+            eprintln!("skipping test_preflight_read_only_dir: running as root");
+            return;
+        }
+
+        let read_only = temp_dir.path().join("read-only");
+        std::fs::create_dir(&read_only).unwrap();
+        std::fs::set_permissions(&read_only, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        let err = preflight_output_dir(&read_only, true).await.unwrap_err();
+        assert!(matches!(err, YdlError::FileSystem { .. }));
+        assert!(err.to_string().contains("not writable"));
+
+        std::fs::set_permissions(&read_only, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
 }
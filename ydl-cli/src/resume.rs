@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use ydl::YdlResult;
+
+/// Prefix of the state files written next to the downloads of multi-video runs
+pub const RESUME_FILE_PREFIX: &str = ".ydl-resume";
+
+/// Completed video IDs of a multi-video run, persisted one ID per line
+///
+/// Each ID is appended as soon as its video completes, so an interrupted run
+/// loses at most the item that was in flight. Every run (source link or batch
+/// file, plus what is written for each video) has its own state file, so runs
+/// sharing an output directory never skip each other's videos.
+pub struct ResumeState {
+    path: PathBuf,
+    completed: HashSet<String>,
+}
+
+impl ResumeState {
+    /// Load the state file of `run` in `dir`, starting empty if there is none yet
+    pub async fn load(dir: &Path, run: &str) -> YdlResult<Self> {
+        let path = dir.join(format!("{}-{:016x}", RESUME_FILE_PREFIX, run_hash(run)));
+        let completed = match fs::read_to_string(&path).await {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self { path, completed })
+    }
+
+    pub fn is_complete(&self, video_id: &str) -> bool {
+        self.completed.contains(video_id)
+    }
+
+    /// Record `video_id` as done, appending it to the state file immediately
+    pub async fn mark_complete(&mut self, video_id: &str) -> YdlResult<()> {
+        if !self.completed.insert(video_id.to_string()) {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(format!("{}\n", video_id).as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// FNV-1a hash of `run`, stable across builds so state files are found again
+fn run_hash(run: &str) -> u64 {
+    run.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let ids = ["dQw4w9WgXcQ", "jNQXAC9IVRw"];
+        let run = "https://www.youtube.com/playlist?list=PLtest\nsrt";
+
+        // First run completes only the first video before being interrupted
+        let mut state = ResumeState::load(dir.path(), run).await.unwrap();
+        assert!(ids.iter().all(|id| !state.is_complete(id)));
+        state.mark_complete(ids[0]).await.unwrap();
+        state.mark_complete(ids[0]).await.unwrap();
+
+        // The second run picks up where the first left off
+        let state = ResumeState::load(dir.path(), run).await.unwrap();
+        let pending: Vec<&str> = ids
+            .iter()
+            .copied()
+            .filter(|id| !state.is_complete(id))
+            .collect();
+        assert_eq!(pending, vec!["jNQXAC9IVRw"]);
+
+        let content = std::fs::read_to_string(state.path()).unwrap();
+        assert_eq!(content, "dQw4w9WgXcQ\n");
+
+        // Another run in the same directory keeps its own progress
+        let other = ResumeState::load(dir.path(), "urls.txt\nsrt")
+            .await
+            .unwrap();
+        assert_ne!(other.path(), state.path());
+        assert!(!other.is_complete(ids[0]));
+    }
+}
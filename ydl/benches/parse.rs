@@ -0,0 +1,36 @@
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use ydl::processor::ContentProcessor;
+
+/// An srv3 document shaped like a long auto-generated track, with per-word `<s>` timing
+fn srv3_fixture(cues: usize) -> String {
+    let mut xml =
+        String::from(r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3"><body>"#);
+    for i in 0..cues {
+        let start = i * 2000;
+        xml.push_str(&format!(
+            r#"<p t="{}" d="2500" w="1"><s ac="0">so</s><s t="400" ac="0"> today</s><s t="800" ac="0"> we&#39;re</s><s t="1200" ac="0"> looking</s><s t="1600" ac="0"> at #{}</s></p>"#,
+            start, i
+        ));
+    }
+    xml.push_str("</body></timedtext>");
+    xml
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let processor = ContentProcessor::new();
+    let mut group = c.benchmark_group("parse_srv3");
+
+    for cues in [100, 2000] {
+        let fixture = srv3_fixture(cues);
+        group.throughput(Throughput::Bytes(fixture.len() as u64));
+        group.bench_function(format!("{}_cues", cues), |b| {
+            b.iter(|| processor.parse_content(black_box(&fixture), "en").unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
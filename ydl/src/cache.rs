@@ -0,0 +1,172 @@
+// Session-wide caching of subtitle discovery results, shared between Ydl instances
+use crate::types::SubtitleTrack;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default number of videos kept by [`AvailabilityCache::default`]
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Default time-to-live of cached discovery results
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Bounded LRU cache of discovered subtitle tracks, keyed by video ID
+///
+/// Share one cache between `Ydl` instances by setting the same
+/// `Arc<AvailabilityCache>` on each instance's options. Entries expire `ttl` after
+/// they were stored; once `capacity` videos are cached, the least recently used
+/// one is evicted. Tracks are cached before language and type filtering, so
+/// instances with different preferences can share a cache.
+#[derive(Debug)]
+pub struct AvailabilityCache {
+    ttl: Duration,
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    /// Monotonic counter stamped on entries when they are used, for LRU eviction
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    tracks: Vec<SubtitleTrack>,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+impl Default for AvailabilityCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_TTL)
+    }
+}
+
+impl AvailabilityCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            ttl,
+            capacity: capacity.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Cached tracks for `video_id`, if stored less than `ttl` ago
+    pub fn get(&self, video_id: &str) -> Option<Vec<SubtitleTrack>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let now = state.clock;
+
+        match state.entries.get_mut(video_id) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => {
+                entry.last_used = now;
+                Some(entry.tracks.clone())
+            }
+            Some(_) => {
+                state.entries.remove(video_id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store the discovered tracks for `video_id`, evicting the least recently used entry if full
+    pub fn insert(&self, video_id: &str, tracks: Vec<SubtitleTrack>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let now = state.clock;
+
+        if !state.entries.contains_key(video_id) && state.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+
+            if state.entries.len() >= self.capacity
+                && let Some(oldest) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(id, _)| id.clone())
+            {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        state.entries.insert(
+            video_id.to_string(),
+            CacheEntry {
+                tracks,
+                stored_at: Instant::now(),
+                last_used: now,
+            },
+        );
+    }
+
+    /// Number of stored entries, including any that have expired but not been evicted yet
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entries
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SubtitleTrackType;
+
+    fn tracks(language: &str) -> Vec<SubtitleTrack> {
+        vec![SubtitleTrack::new(
+            language.to_string(),
+            language.to_uppercase(),
+            SubtitleTrackType::Manual,
+        )]
+    }
+
+    #[test]
+    fn test_cache_hit_and_separate_videos() {
+        let cache = AvailabilityCache::default();
+        cache.insert("aaaaaaaaaaa", tracks("en"));
+        cache.insert("bbbbbbbbbbb", tracks("fr"));
+
+        assert_eq!(cache.get("aaaaaaaaaaa").unwrap()[0].language_code, "en");
+        assert_eq!(cache.get("bbbbbbbbbbb").unwrap()[0].language_code, "fr");
+        assert!(cache.get("ccccccccccc").is_none());
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let cache = AvailabilityCache::new(8, Duration::from_millis(30));
+        cache.insert("aaaaaaaaaaa", tracks("en"));
+        assert!(cache.get("aaaaaaaaaaa").is_some());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(cache.get("aaaaaaaaaaa").is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = AvailabilityCache::new(2, DEFAULT_CACHE_TTL);
+        cache.insert("aaaaaaaaaaa", tracks("en"));
+        cache.insert("bbbbbbbbbbb", tracks("fr"));
+
+        // Touch the first video so the second becomes the eviction candidate
+        cache.get("aaaaaaaaaaa");
+        cache.insert("ccccccccccc", tracks("de"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("aaaaaaaaaaa").is_some());
+        assert!(cache.get("bbbbbbbbbbb").is_none());
+        assert!(cache.get("ccccccccccc").is_some());
+    }
+}
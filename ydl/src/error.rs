@@ -9,11 +9,11 @@ pub enum YdlError {
     #[error("Invalid video ID format: {video_id}")]
     InvalidVideoId { video_id: String },
 
+    #[error("Invalid playlist ID format: {playlist_id}")]
+    InvalidPlaylistId { playlist_id: String },
+
     #[error("Network error: {source}")]
-    Network {
-        #[from]
-        source: reqwest::Error,
-    },
+    Network { source: reqwest::Error },
 
     #[error("Video not found or unavailable: {video_id}")]
     VideoNotFound { video_id: String },
@@ -27,15 +27,45 @@ pub enum YdlError {
     #[error("Age-restricted content requires verification: {video_id}")]
     AgeRestricted { video_id: String },
 
+    #[error("YouTube redirected to its cookie-consent page: {url}")]
+    ConsentRequired { url: String },
+
+    #[error("Playlist not found or unavailable: {playlist_id}")]
+    PlaylistNotFound { playlist_id: String },
+
+    #[error("Channel could not be resolved: {channel}")]
+    ChannelNotFound { channel: String },
+
+    #[error("Channel has no uploads: {channel_id}")]
+    NoChannelUploads { channel_id: String },
+
     #[error("No subtitles available for video: {video_id}")]
     NoSubtitlesAvailable { video_id: String },
 
+    #[error("Captions are disabled by the uploader: {video_id}")]
+    CaptionsDisabled { video_id: String },
+
+    #[error("Captions of {video_id} are not available in this region; try a proxy elsewhere")]
+    CaptionsUnavailableInRegion { video_id: String },
+
+    #[error("No chapters available for video: {video_id}")]
+    NoChaptersAvailable { video_id: String },
+
     #[error("Only auto-generated subtitles available for video: {video_id}")]
     OnlyAutoGenerated { video_id: String },
 
     #[error("Requested language not available: {language}")]
     LanguageNotAvailable { language: String },
 
+    #[error("Subtitle track not available: {vss_id}")]
+    TrackNotAvailable { vss_id: String },
+
+    #[error("Caption URL for {video_id} is throttled because {reason}")]
+    ProtectedCaptionUrl {
+        video_id: String,
+        reason: &'static str,
+    },
+
     #[error("Unsupported subtitle format: {format}")]
     UnsupportedFormat { format: String },
 
@@ -51,8 +81,14 @@ pub enum YdlError {
         source: std::io::Error,
     },
 
-    #[error("Subtitle parsing error: {message}")]
-    SubtitleParsing { message: String },
+    #[error("Subtitle parsing error: {message}{}", location(.line, .context))]
+    SubtitleParsing {
+        message: String,
+        /// 1-based line of the content where parsing failed, when known
+        line: Option<usize>,
+        /// The offending line, shortened to a snippet
+        context: Option<String>,
+    },
 
     #[error("Format conversion error: from {from} to {to}")]
     FormatConversion { from: String, to: String },
@@ -94,7 +130,34 @@ pub enum YdlError {
     Encoding { message: String },
 }
 
+impl From<reqwest::Error> for YdlError {
+    /// Redirects refused by [`redirect_policy`](crate::network::redirect_policy) keep
+    /// their specific error; every other failure is a network error
+    fn from(source: reqwest::Error) -> Self {
+        crate::network::refused_redirect_error(&source).unwrap_or(YdlError::Network { source })
+    }
+}
+
+/// The ` at line N: snippet` suffix of a parsing error, empty if the location is unknown
+fn location(line: &Option<usize>, context: &Option<String>) -> String {
+    match (line, context) {
+        (Some(line), Some(context)) => format!(" at line {}: {}", line, context),
+        (Some(line), None) => format!(" at line {}", line),
+        (None, Some(context)) => format!(": {}", context),
+        (None, None) => String::new(),
+    }
+}
+
 impl YdlError {
+    /// A parsing error with no location
+    pub fn subtitle_parsing(message: impl Into<String>) -> Self {
+        YdlError::SubtitleParsing {
+            message: message.into(),
+            line: None,
+            context: None,
+        }
+    }
+
     /// Check if the error is retryable
     pub fn is_retryable(&self) -> bool {
         matches!(
@@ -106,6 +169,13 @@ impl YdlError {
         )
     }
 
+    /// Check if the error is a response body that failed mid-read (truncated or undecodable)
+    ///
+    /// These are transient and, like other network errors, retryable.
+    pub fn is_body_read_error(&self) -> bool {
+        matches!(self, YdlError::Network { source } if source.is_body() || source.is_decode())
+    }
+
     /// Get suggested retry delay in seconds
     pub fn retry_delay(&self) -> Option<u64> {
         match self {
@@ -133,8 +203,11 @@ impl YdlError {
         matches!(
             self,
             YdlError::NoSubtitlesAvailable { .. }
+                | YdlError::CaptionsDisabled { .. }
+                | YdlError::CaptionsUnavailableInRegion { .. }
                 | YdlError::OnlyAutoGenerated { .. }
                 | YdlError::LanguageNotAvailable { .. }
+                | YdlError::TrackNotAvailable { .. }
         )
     }
 }
@@ -146,6 +219,24 @@ pub type YdlResult<T> = Result<T, YdlError>;
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_subtitle_parsing_display() {
+        assert_eq!(
+            YdlError::subtitle_parsing("No valid SRT entries found").to_string(),
+            "Subtitle parsing error: No valid SRT entries found"
+        );
+
+        let located = YdlError::SubtitleParsing {
+            message: "Malformed VTT block".to_string(),
+            line: Some(12),
+            context: None,
+        };
+        assert_eq!(
+            located.to_string(),
+            "Subtitle parsing error: Malformed VTT block at line 12"
+        );
+    }
+
     #[test]
     fn test_error_is_retryable() {
         // Create a network error without relying on conversion
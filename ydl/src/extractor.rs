@@ -1,16 +1,32 @@
 use crate::error::{YdlError, YdlResult};
-use crate::types::{PlayerResponse, SubtitleTrack, SubtitleTrackType, VideoMetadata, YdlOptions};
-use crate::youtube_client::YouTubeSubtitleExtractor;
-use reqwest::Client;
+use crate::metrics::Metrics;
+use crate::network::{self, HttpClient, HttpConfig};
+use crate::parser::ChannelRef;
+use crate::processor::ContentProcessor;
+use crate::types::{
+    CaptionTracks, Chapter, MAX_READABLE_CPS, PlayerResponse, SubtitleEntry, SubtitleTrack,
+    SubtitleTrackType, SubtitleType, TrackListRenderer, VideoMetadata, YdlOptions,
+};
+use crate::youtube_client::{ClientType, YouTubeSubtitleExtractor};
+use reqwest::StatusCode;
 use std::collections::HashMap;
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, info, trace, warn};
 
 /// YouTube subtitle extractor for discovering and downloading subtitles
 pub struct SubtitleExtractor {
-    client: Client,
+    client: HttpClient,
     options: YdlOptions,
     youtube_client: YouTubeSubtitleExtractor,
+    /// Scheme and host of watch page URLs
+    web_base: String,
+    /// Whether cookies are sent, so InnerTube requests need the session's visitorData
+    signed_in: bool,
+    /// Whether the video is a Short, so mobile InnerTube clients are tried first
+    short: bool,
+    /// Parses downloaded tracks to validate, score and measure them, built once since
+    /// compiling its regexes is costly
+    processor: ContentProcessor,
 }
 
 impl SubtitleExtractor {
@@ -45,78 +61,223 @@ impl SubtitleExtractor {
         // Remove Accept-Encoding to get uncompressed response
         // reqwest will handle compression automatically if we don't set this
 
-        let mut client_builder = Client::builder()
-            .default_headers(headers)
-            .timeout(Duration::from_secs(options.timeout_seconds))
-            .redirect(reqwest::redirect::Policy::limited(10));
-
-        // Add proxy if specified
-        if let Some(proxy_url) = &options.proxy {
-            let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| YdlError::Configuration {
-                message: format!("Invalid proxy URL: {}", e),
-            })?;
-            client_builder = client_builder.proxy(proxy);
+        let http_config = HttpConfig::from_options(&options)?;
+        let client = http_config.client(headers, |builder| {
+            builder.redirect(network::redirect_policy())
+        })?;
+
+        let client_types = options
+            .innertube_clients
+            .as_deref()
+            .unwrap_or(ClientType::ALL);
+        let mut youtube_client = YouTubeSubtitleExtractor::with_config(client_types, &http_config)?
+            .with_metrics(options.metrics.clone());
+        if let Some(context) = &options.innertube_context {
+            youtube_client = youtube_client.with_context_overrides(context.clone())?;
         }
 
-        let client = client_builder
-            .build()
-            .map_err(|e| YdlError::Configuration {
-                message: format!("Failed to create HTTP client: {}", e),
-            })?;
-
-        let youtube_client = YouTubeSubtitleExtractor::new()?;
+        // Only the parse matters here; trimming or filtering options could drop every cue
+        let processor = ContentProcessor::with_options(
+            YdlOptions::new().lenient_blocks(options.lenient_blocks),
+        );
 
         Ok(Self {
             client,
             options,
             youtube_client,
+            web_base: "https://www.youtube.com".to_string(),
+            signed_in: http_config.has_cookies(),
+            short: false,
+            processor,
         })
     }
 
-    /// Discover available subtitle tracks for a video
+    /// Treat videos as YouTube Shorts, asking the mobile InnerTube clients first
+    pub fn for_short(mut self, short: bool) -> Self {
+        self.short = short;
+        self
+    }
+
+    /// Fetch watch pages from `base` instead of youtube.com
+    #[cfg(test)]
+    pub(crate) fn with_web_base(mut self, base: &str) -> Self {
+        self.web_base = base.to_string();
+        self
+    }
+
+    /// Send InnerTube requests to `endpoint` instead of YouTube
+    #[cfg(test)]
+    pub(crate) fn with_player_endpoint(mut self, endpoint: &str) -> Self {
+        self.youtube_client = self.youtube_client.with_player_endpoint(endpoint);
+        self
+    }
+
+    /// Send InnerTube browse requests to `endpoint` instead of YouTube
+    #[cfg(test)]
+    pub(crate) fn with_browse_endpoint(mut self, endpoint: &str) -> Self {
+        self.youtube_client = self.youtube_client.with_browse_endpoint(endpoint);
+        self
+    }
+
+    /// Discover available subtitle tracks for a video, filtered by the configured preferences
     pub async fn discover_tracks(&self, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
+        let tracks = self.discover_all_tracks(video_id).await?;
+
+        // Filter based on options
+        self.filter_tracks(tracks, video_id)
+    }
+
+    /// Discover every subtitle track of a video, ignoring language and type preferences
+    ///
+    /// Results are served from and stored in the configured availability cache, if any.
+    pub async fn discover_all_tracks(&self, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
+        let cache = self.options.availability_cache.as_ref();
+        if let Some(tracks) = cache.and_then(|cache| cache.get(video_id)) {
+            debug!("Using cached subtitle tracks for video: {}", video_id);
+            self.options.metrics.cache_hit(video_id);
+            return Ok(tracks);
+        }
+
+        let tracks = self.discover_uncached(video_id).await?;
+        if let Some(cache) = cache {
+            cache.insert(video_id, tracks.clone());
+        }
+        Ok(tracks)
+    }
+
+    /// Discover tracks from YouTube, trying each discovery method in turn
+    async fn discover_uncached(&self, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
         info!("Discovering subtitle tracks for video: {}", video_id);
 
+        // Signed-in InnerTube requests are only honored with the session's visitorData
+        if self.signed_in && self.youtube_client.visitor_data().is_none() {
+            self.load_visitor_data(video_id).await;
+        }
+
         // Try different methods to find subtitles
         let mut tracks = Vec::new();
 
         // Method 1: Try InnerTube API first (most reliable)
-        if let Ok(innertube_tracks) = self.youtube_client.discover_tracks(video_id).await {
-            info!("Found {} tracks via InnerTube API", innertube_tracks.len());
-            tracks.extend(innertube_tracks);
+        let mut disabled = None;
+        let innertube = if self.short {
+            self.youtube_client.discover_short_tracks(video_id).await
+        } else {
+            self.youtube_client.discover_tracks(video_id).await
+        };
+        match innertube {
+            Ok(innertube_tracks) => {
+                info!("Found {} tracks via InnerTube API", innertube_tracks.len());
+                tracks.extend(innertube_tracks);
+            }
+            Err(e @ YdlError::CaptionsDisabled { .. }) => disabled = Some(e),
+            Err(_) => {}
         }
 
-        // Method 2: Try to get from watch page as fallback
-        if tracks.is_empty()
-            && let Ok(page_tracks) = self.discover_from_watch_page(video_id).await
-        {
-            tracks.extend(page_tracks);
+        // The fallback method that found the tracks, if the InnerTube API did not
+        let mut fallback = None;
+
+        // Method 2: Try to get from watch page as fallback, or the embedded player if
+        // the watch page is restricted
+        let watch_url = format!("{}/watch?v={}", self.web_base, video_id);
+        let mut restricted = None;
+        if tracks.is_empty() {
+            match self
+                .discover_from_watch_page_or_embed(&watch_url, video_id)
+                .await
+            {
+                Ok((page_tracks, method)) => {
+                    if !page_tracks.is_empty() {
+                        fallback = Some(method);
+                    }
+                    tracks.extend(page_tracks);
+                }
+                Err(e @ YdlError::VideoRestricted { .. }) => restricted = Some(e),
+                Err(_) => {}
+            }
         }
 
         // Method 3: Try mobile endpoint if no tracks found
         if tracks.is_empty()
             && let Ok(mobile_tracks) = self.discover_from_mobile_page(video_id).await
+            && !mobile_tracks.is_empty()
         {
+            self.options.metrics.client_succeeded("mobile_page");
+            fallback = Some("mobile_page");
             tracks.extend(mobile_tracks);
         }
 
         // Method 4: Try direct API approach
         if tracks.is_empty()
             && let Ok(api_tracks) = self.discover_from_api(video_id).await
+            && !api_tracks.is_empty()
         {
+            self.options.metrics.client_succeeded("get_video_info");
+            fallback = Some("get_video_info");
             tracks.extend(api_tracks);
         }
 
-        // Filter based on options
-        self.filter_tracks(tracks, video_id)
+        if let Some(method) = fallback {
+            warn!(
+                "InnerTube API found no tracks for {}, fell back to {}",
+                video_id, method
+            );
+            self.options.metrics.fallback_used(method);
+        }
+
+        match restricted.or(disabled) {
+            Some(e) if tracks.is_empty() => Err(e),
+            _ => Ok(tracks),
+        }
+    }
+
+    /// Discover tracks from the watch page, retrying with the embedded player client
+    /// when the page reports the video as restricted
+    ///
+    /// Some videos are blocked on youtube.com but still play when embedded elsewhere;
+    /// the TV embedded client can fetch their captions. Returns the tracks along with
+    /// the method that found them (`watch_page` or `embedded_player`).
+    async fn discover_from_watch_page_or_embed(
+        &self,
+        watch_url: &str,
+        video_id: &str,
+    ) -> YdlResult<(Vec<SubtitleTrack>, &'static str)> {
+        match self.discover_from_watch_page(watch_url, video_id).await {
+            Ok(page_tracks) => {
+                if !page_tracks.is_empty() {
+                    self.options.metrics.client_succeeded("watch_page");
+                }
+                Ok((page_tracks, "watch_page"))
+            }
+            Err(restricted @ YdlError::VideoRestricted { .. }) => {
+                info!("Watch page is restricted, retrying with the embedded player client");
+                self.youtube_client
+                    .discover_with_client(ClientType::TvEmbedded, video_id)
+                    .await
+                    .map(|tracks| (tracks, "embedded_player"))
+                    .map_err(|e| {
+                        debug!("Embedded player client found no tracks: {}", e);
+                        restricted
+                    })
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /// Get video metadata including available subtitles
     pub async fn get_video_metadata(&self, video_id: &str) -> YdlResult<VideoMetadata> {
+        let metadata = self.get_video_details(video_id).await?;
+
+        // Get available subtitles
+        let tracks = self.discover_tracks(video_id).await?;
+        Ok(metadata.with_subtitles(tracks))
+    }
+
+    /// Get video metadata from the watch page, without discovering subtitle tracks
+    pub async fn get_video_details(&self, video_id: &str) -> YdlResult<VideoMetadata> {
         info!("Getting video metadata for: {}", video_id);
 
-        let url = format!("https://www.youtube.com/watch?v={}", video_id);
-        let response = self.client.get(&url).send().await?;
+        let url = format!("{}/watch?v={}", self.web_base, video_id);
+        let response = network::send(self.client.get(&url), "web", &*self.options.metrics).await?;
 
         if !response.status().is_success() {
             return Err(self.map_http_error(response.status(), video_id));
@@ -138,25 +299,180 @@ impl SubtitleExtractor {
             metadata = metadata.with_duration(Duration::from_secs(length));
         }
 
-        // Get available subtitles
-        let tracks = self.discover_tracks(video_id).await?;
-        metadata = metadata.with_subtitles(tracks);
+        if let Some(thumbnail_url) = player_response
+            .video_details
+            .as_ref()
+            .and_then(|d| d.best_thumbnail_url())
+        {
+            metadata = metadata.with_thumbnail_url(thumbnail_url.to_string());
+        }
+
+        if let Some(description) = player_response
+            .video_details
+            .as_ref()
+            .and_then(|d| d.short_description.as_deref())
+        {
+            metadata = metadata.with_chapters(Chapter::from_description(description));
+        }
 
         Ok(metadata)
     }
 
+    /// URL to download a track's content from, with the `fmt=` parameter applied
+    ///
+    /// Interstitial parameters are stripped first. An explicit
+    /// `YdlOptions::source_format` replaces any existing `fmt=`; otherwise srv3
+    /// (YouTube's XML format, which parses well) is added when none is present. With
+    /// `respect_base_url_fmt`, the base URL is only ever appended to.
+    fn content_url(&self, base_url: &str) -> String {
+        if self.options.respect_base_url_fmt {
+            if has_query_param(base_url, "fmt") {
+                return base_url.to_string();
+            }
+            let fmt = self.options.source_format.map_or("srv3", |f| f.as_param());
+            let separator = if base_url.contains('?') { "&" } else { "?" };
+            return format!("{}{separator}fmt={}", base_url, fmt);
+        }
+
+        let base_url = &network::strip_interstitial_params(base_url);
+        if let Some(source_format) = self.options.source_format {
+            return set_query_param(base_url, "fmt", source_format.as_param());
+        }
+
+        if has_query_param(base_url, "fmt") {
+            base_url.to_string()
+        } else {
+            let separator = if base_url.contains('?') { "&" } else { "?" };
+            format!("{}{separator}fmt=srv3", base_url)
+        }
+    }
+
+    /// Download `track` machine-translated into `target_lang` via YouTube's `tlang` parameter
+    ///
+    /// Fails with `LanguageNotAvailable` if the track is not translatable.
+    pub async fn download_translated(
+        &self,
+        track: &SubtitleTrack,
+        video_id: &str,
+        target_lang: &str,
+    ) -> YdlResult<String> {
+        let not_available = || YdlError::LanguageNotAvailable {
+            language: target_lang.to_string(),
+        };
+        if !track.is_translatable {
+            return Err(not_available());
+        }
+        let base_url = track.url.as_deref().ok_or_else(not_available)?;
+
+        info!(
+            "Translating {} track of {} into {}",
+            track.language_code, video_id, target_lang
+        );
+        let translated = SubtitleTrack {
+            language_code: target_lang.to_string(),
+            url: Some(set_query_param(base_url, "tlang", target_lang)),
+            translated_from: Some(track.language_code.clone()),
+            ..track.clone()
+        };
+        self.download_content(&translated, video_id).await
+    }
+
     /// Download subtitle content from a track
     pub async fn download_content(
         &self,
         track: &SubtitleTrack,
         video_id: &str,
     ) -> YdlResult<String> {
+        self.fetch_content(track, video_id)
+            .await?
+            .map_err(empty_content_error)
+    }
+
+    /// Download `selected`, telling captions blocked in this region apart from a broken track
+    ///
+    /// If `selected` downloads without captions, every other original track is tried
+    /// until one has captions; translations of them would only repeat the same
+    /// download. When all come back empty too and the player reports the video as
+    /// playable here, the captions are taken to be region-restricted. A video that
+    /// does not play is reported as restricted, and otherwise the error for
+    /// `selected` is returned.
+    pub async fn download_checking_region(
+        &self,
+        tracks: &[SubtitleTrack],
+        selected: &SubtitleTrack,
+        video_id: &str,
+    ) -> YdlResult<String> {
+        let bytes = match self.fetch_content(selected, video_id).await? {
+            Ok(content) => return Ok(content),
+            Err(bytes) => bytes,
+        };
+
+        let others = tracks
+            .iter()
+            .filter(|track| !std::ptr::eq(*track, selected) && !track.is_translated());
+        for track in others {
+            match self.fetch_content(track, video_id).await {
+                Ok(Err(_)) => debug!("{} track of {} is empty too", track.language_code, video_id),
+                _ => {
+                    debug!(
+                        "{} track of {} is not empty, so only the selected track is broken",
+                        track.language_code, video_id
+                    );
+                    return Err(empty_content_error(bytes));
+                }
+            }
+        }
+
+        match self.youtube_client.playability(video_id).await {
+            Some(status) if status.is_ok() => {
+                warn!(
+                    "Every caption track of {} downloaded empty; they may be blocked in this region",
+                    video_id
+                );
+                Err(YdlError::CaptionsUnavailableInRegion {
+                    video_id: video_id.to_string(),
+                })
+            }
+            Some(status) => {
+                debug!(
+                    "{} does not play here: {} ({})",
+                    video_id,
+                    status.status,
+                    status.reason.as_deref().unwrap_or("no reason given")
+                );
+                Err(YdlError::VideoRestricted {
+                    video_id: video_id.to_string(),
+                })
+            }
+            None => Err(empty_content_error(bytes)),
+        }
+    }
+
+    /// Download a track's content, or the size of a response that holds no captions
+    async fn fetch_content(
+        &self,
+        track: &SubtitleTrack,
+        video_id: &str,
+    ) -> YdlResult<Result<String, usize>> {
+        let protection = track
+            .url
+            .as_deref()
+            .and_then(network::caption_url_protection);
+        if let Some(reason) = protection {
+            warn!(
+                "Caption URL for {} is likely throttled: {}",
+                video_id, reason
+            );
+        }
+
         // If we have a URL from the track, try to use it
         if let Some(base_url) = &track.url {
+            let url = self.content_url(base_url);
+
             // First try with the InnerTube client (which handles authentication better)
             info!("Downloading subtitle content via InnerTube client");
-            match self.youtube_client.download_content(base_url).await {
-                Ok(content) if !content.is_empty() => {
+            match self.youtube_client.download_content(&url).await {
+                Ok(content) if self.has_captions(&content) => {
                     debug!(
                         "Downloaded {} bytes of subtitle content via InnerTube",
                         content.len()
@@ -170,58 +486,61 @@ impl SubtitleExtractor {
                         debug!("Saved subtitle content to /tmp/subtitle_content.xml for debugging");
                     }
 
-                    return Ok(content);
+                    return Ok(Ok(content));
                 }
                 Err(e) => {
                     debug!("InnerTube download failed: {}, trying direct download", e);
                 }
-                _ => {}
+                Ok(content) => {
+                    debug!(
+                        "InnerTube download has no captions ({} bytes), trying direct download",
+                        content.len()
+                    );
+                }
             }
 
             // Fallback to direct download
-            // Add format parameter - srv3 is YouTube's XML format that works well
-            let url = if base_url.contains("fmt=") {
-                base_url.clone()
-            } else {
-                let separator = if base_url.contains('?') { "&" } else { "?" };
-                format!("{}{separator}fmt=srv3", base_url)
-            };
+            info!("Trying direct download from: {}", network::redact_url(&url));
+            let (status, content) =
+                fetch_text(&self.client, &url, "web", &*self.options.metrics).await?;
 
-            info!("Trying direct download from: {}", url);
-            let response = self.client.get(&url).send().await?;
-
-            if response.status().is_success() {
-                let content = response.text().await?;
-                if !content.is_empty() {
-                    debug!("Downloaded {} bytes of subtitle content", content.len());
-                    return Ok(content);
-                }
+            if status.is_success() && self.has_captions(&content) {
+                debug!("Downloaded {} bytes of subtitle content", content.len());
+                return Ok(Ok(content));
             }
         }
 
         // Fallback: construct a simple subtitle URL
         // This works for many videos that have auto-generated subtitles
-        let fallback_url = format!(
-            "https://www.youtube.com/api/timedtext?v={}&lang={}&fmt=srv3",
-            video_id, track.language_code
-        );
+        let fallback_url = self.content_url(&format!(
+            "{}/api/timedtext?v={}&lang={}",
+            self.web_base, video_id, track.language_code
+        ));
 
         info!("Trying fallback subtitle URL: {}", fallback_url);
-        let response = self.client.get(&fallback_url).send().await?;
+        let (status, content) =
+            fetch_text(&self.client, &fallback_url, "web", &*self.options.metrics).await?;
 
-        if !response.status().is_success() {
+        // The unsigned fallback failing too means the protection, not the track, is the problem
+        if let Some(reason) = protection
+            && (!status.is_success() || !self.has_captions(&content))
+        {
+            return Err(YdlError::ProtectedCaptionUrl {
+                video_id: video_id.to_string(),
+                reason,
+            });
+        }
+
+        if !status.is_success() {
             return Err(YdlError::SubtitleDiscoveryError {
-                message: format!("HTTP {}: Failed to download subtitles", response.status()),
+                message: format!("HTTP {}: Failed to download subtitles", status),
             });
         }
 
-        let content = response.text().await?;
         debug!("Downloaded {} bytes of subtitle content", content.len());
 
-        if content.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "Empty subtitle content received".to_string(),
-            });
+        if !self.has_captions(&content) {
+            return Ok(Err(content.len()));
         }
 
         debug!(
@@ -229,21 +548,246 @@ impl SubtitleExtractor {
             &content.chars().take(500).collect::<String>()
         );
 
-        Ok(content)
+        Ok(Ok(content))
     }
 
-    /// Discover subtitles from the main watch page
-    async fn discover_from_watch_page(&self, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
-        debug!("Trying to discover subtitles from watch page");
+    /// Whether downloaded content is plausibly captions: at least `min_content_bytes`
+    /// long, and parsing to at least one cue in a timed caption format
+    ///
+    /// The plain-text fallback accepts any text, such as an HTML error page or `{}`,
+    /// so content that only parses as plain text is rejected.
+    fn has_captions(&self, content: &str) -> bool {
+        if content.is_empty() || content.len() < self.options.min_content_bytes {
+            return false;
+        }
 
-        let url = format!("https://www.youtube.com/watch?v={}", video_id);
-        let response = self.client.get(&url).send().await?;
+        self.processor
+            .parse_content(content, "")
+            .is_ok_and(|parsed| {
+                parsed.original_format != SubtitleType::Txt && !parsed.entries.is_empty()
+            })
+    }
 
-        if !response.status().is_success() {
-            return Err(self.map_http_error(response.status(), video_id));
+    /// Download every track and record its quality score (see
+    /// [`QualityWeights`](crate::QualityWeights))
+    ///
+    /// Tracks that fail to download are left unscored. Coverage is measured against the
+    /// video's length, or against the longest track if the length is unknown.
+    ///
+    /// Returns the downloaded content of each track, in the order of `tracks`, so the
+    /// winner need not be downloaded again.
+    pub async fn score_tracks(
+        &self,
+        tracks: &mut [SubtitleTrack],
+        video_id: &str,
+    ) -> Vec<Option<String>> {
+        let mut downloaded = Vec::with_capacity(tracks.len());
+        let mut parsed = Vec::with_capacity(tracks.len());
+        for track in tracks.iter() {
+            let entries = match self.download_content(track, video_id).await {
+                Ok(content) => {
+                    let entries = self
+                        .processor
+                        .parse_content(&content, &track.language_code)
+                        .map(|parsed| parsed.entries)
+                        .ok();
+                    downloaded.push(Some(content));
+                    entries
+                }
+                Err(e) => {
+                    debug!("Not scoring {} track: {}", track.language_code, e);
+                    downloaded.push(None);
+                    None
+                }
+            };
+            parsed.push(entries);
         }
 
-        let html = response.text().await?;
+        let length = match self.youtube_client.video_duration(video_id).await {
+            Some(length) => length,
+            None => parsed
+                .iter()
+                .flatten()
+                .flat_map(|entries| entries.iter().map(|e| e.end))
+                .max()
+                .unwrap_or_default(),
+        };
+        let most_cues = parsed.iter().flatten().map(Vec::len).max().unwrap_or(0);
+
+        let weights = &self.options.quality_weights;
+        for (track, entries) in tracks.iter_mut().zip(parsed) {
+            let Some(entries) = entries else {
+                continue;
+            };
+
+            let manual = match track.track_type {
+                SubtitleTrackType::Manual => 1.0,
+                SubtitleTrackType::Community => 0.5,
+                SubtitleTrackType::AutoGenerated => 0.0,
+            };
+            let language = self
+                .options
+                .language
+                .as_ref()
+                .is_none_or(|lang| *lang == track.language_code);
+            let entry_share = if most_cues == 0 {
+                0.0
+            } else {
+                entries.len() as f64 / most_cues as f64
+            };
+
+            let score = weights.manual * manual
+                + weights.coverage * entries_coverage(&entries, length)
+                + weights.entries * entry_share
+                + weights.reading_speed * readable_share(&entries)
+                + weights.language * if language { 1.0 } else { 0.0 };
+            debug!(
+                "Quality of {} ({}) track: {:.3}",
+                track.language_code, track.track_type, score
+            );
+            track.quality_score = Some(score);
+        }
+        downloaded
+    }
+
+    /// Number of tracks each InnerTube client returns for `video_id`, or why it failed
+    pub async fn probe_all_clients(&self, video_id: &str) -> Vec<(ClientType, YdlResult<usize>)> {
+        self.youtube_client.probe_all(video_id).await
+    }
+
+    /// Download `selected`, falling back to the video's other tracks in the same language
+    /// when its cues cover less than `min_coverage` of the video
+    ///
+    /// `downloaded` is the content of `selected` if it was already fetched. Returns the
+    /// content along with the track it came from. If no track does better, the sparse
+    /// download of `selected` is kept.
+    pub async fn download_with_coverage_check<'a>(
+        &self,
+        tracks: &'a [SubtitleTrack],
+        selected: &'a SubtitleTrack,
+        video_id: &str,
+        min_coverage: f64,
+        downloaded: Option<String>,
+    ) -> YdlResult<(String, &'a SubtitleTrack)> {
+        let content = match downloaded {
+            Some(content) => content,
+            None => {
+                self.download_checking_region(tracks, selected, video_id)
+                    .await?
+            }
+        };
+        let Some(length) = self.youtube_client.video_duration(video_id).await else {
+            debug!(
+                "Length of {} unknown, skipping the coverage check",
+                video_id
+            );
+            return Ok((content, selected));
+        };
+
+        let (coverage, cues) = caption_coverage(&self.processor, &content, length);
+        if coverage >= min_coverage {
+            return Ok((content, selected));
+        }
+        warn!(
+            "Track {} of {} covers only {:.1}% of the video ({} cues in {:?}), trying other tracks",
+            selected.language_code,
+            video_id,
+            coverage * 100.0,
+            cues,
+            length
+        );
+
+        let alternatives = tracks.iter().filter(|track| {
+            track.language_code == selected.language_code && !std::ptr::eq(*track, selected)
+        });
+        for track in alternatives {
+            match self.download_content(track, video_id).await {
+                Ok(other)
+                    if caption_coverage(&self.processor, &other, length).0 >= min_coverage =>
+                {
+                    info!(
+                        "Using {} track of {} instead",
+                        track.track_type, track.language_code
+                    );
+                    return Ok((other, track));
+                }
+                Ok(_) => debug!("{} track is sparse too", track.track_type),
+                Err(e) => debug!("{} track failed to download: {}", track.track_type, e),
+            }
+        }
+
+        warn!(
+            "No better track found for {}, keeping the sparse download",
+            video_id
+        );
+        Ok((content, selected))
+    }
+
+    /// Fetch a watch page, rotating User-Agents while YouTube serves a bot-check page
+    ///
+    /// The first request uses the client's configured User-Agent; each entry of
+    /// `YdlOptions::user_agent_pool` is then tried in turn. The last page received is
+    /// returned even if it is still a bot-check page, so parsing reports the failure.
+    async fn fetch_watch_page(&self, url: &str, video_id: &str) -> YdlResult<String> {
+        let rotations = std::iter::once(None).chain(
+            self.options
+                .user_agent_pool
+                .iter()
+                .map(|ua| Some(ua.as_str())),
+        );
+
+        let mut html = String::new();
+        for user_agent in rotations {
+            let mut request = self.client.get(url);
+            if let Some(user_agent) = user_agent {
+                let value = reqwest::header::HeaderValue::from_str(user_agent).map_err(|_| {
+                    YdlError::Configuration {
+                        message: format!("Invalid user agent in pool: {}", user_agent),
+                    }
+                })?;
+                // `headers` replaces rather than appends, overriding any per-request User-Agent
+                request = request.headers(reqwest::header::HeaderMap::from_iter([(
+                    reqwest::header::USER_AGENT,
+                    value,
+                )]));
+            }
+
+            let response = network::send(request, "web", &*self.options.metrics).await?;
+            if !response.status().is_success() {
+                return Err(self.map_http_error(response.status(), video_id));
+            }
+
+            html = response.text().await?;
+            if !is_bot_check_page(&html) {
+                return Ok(html);
+            }
+            warn!("YouTube served a bot-check page, retrying with another User-Agent");
+        }
+
+        Ok(html)
+    }
+
+    /// Read the signed-in session's visitorData from a watch page fetched with the cookies
+    async fn load_visitor_data(&self, video_id: &str) {
+        let watch_url = format!("{}/watch?v={}", self.web_base, video_id);
+        match self.fetch_watch_page(&watch_url, video_id).await {
+            Ok(html) => match extract_visitor_data(&html) {
+                Some(visitor_data) => self.youtube_client.set_visitor_data(visitor_data),
+                None => debug!("No visitorData on the watch page for {}", video_id),
+            },
+            Err(e) => debug!("Could not fetch visitorData for {}: {}", video_id, e),
+        }
+    }
+
+    /// Discover subtitles from the main watch page
+    async fn discover_from_watch_page(
+        &self,
+        url: &str,
+        video_id: &str,
+    ) -> YdlResult<Vec<SubtitleTrack>> {
+        debug!("Trying to discover subtitles from watch page");
+
+        let html = self.fetch_watch_page(url, video_id).await?;
 
         // Debug: save HTML to file for inspection
         #[cfg(debug_assertions)]
@@ -253,7 +797,14 @@ impl SubtitleExtractor {
             debug!("Saved HTML to /tmp/youtube_watch_page.html for debugging");
         }
 
-        let player_response = self.extract_player_response(&html)?;
+        let player_response = match self.extract_player_response(&html) {
+            Ok(player_response) => player_response,
+            Err(e) => {
+                return self
+                    .extract_tracks_from_initial_data(&html, video_id)
+                    .unwrap_or(Err(e));
+            }
+        };
 
         // Extract tracks but construct simpler URLs that work
         let mut tracks = Vec::new();
@@ -261,7 +812,8 @@ impl SubtitleExtractor {
             && let Some(tracklist) = &captions.player_captions_tracklist_renderer
             && let Some(caption_tracks) = &tracklist.caption_tracks
         {
-            for track in caption_tracks {
+            let default_index = tracklist.default_caption_index();
+            for (index, track) in caption_tracks.iter().enumerate() {
                 // Instead of using the base_url from player response (which needs auth),
                 // construct a simple URL that often works for public videos
                 let simple_url = format!(
@@ -269,42 +821,54 @@ impl SubtitleExtractor {
                     video_id, track.language_code
                 );
 
-                let language_name = track
-                    .name
-                    .as_ref()
-                    .and_then(|n| {
-                        n.simple_text.as_deref().or_else(|| {
-                            n.runs
-                                .as_ref()
-                                .and_then(|runs| runs.first().map(|r| r.text.as_str()))
-                        })
-                    })
-                    .unwrap_or(&track.language_code);
+                let (language_code, language_name, is_auto) = track.normalized_name();
 
-                let track_type = if track.kind == Some("asr".to_string()) {
+                let track_type = if is_auto || track.kind.as_deref() == Some("asr") {
                     SubtitleTrackType::AutoGenerated
                 } else {
                     SubtitleTrackType::Manual
                 };
 
-                let subtitle_track = SubtitleTrack::new(
-                    track.language_code.clone(),
-                    language_name.to_string(),
-                    track_type,
-                )
-                .with_url(simple_url)
-                .with_translatable(track.is_translatable.unwrap_or(false));
+                let subtitle_track = SubtitleTrack::new(language_code, language_name, track_type)
+                    .with_url(simple_url)
+                    .with_translatable(track.is_translatable.unwrap_or(false))
+                    .with_vss_id(track.vss_id.clone())
+                    .with_default(default_index == Some(index));
 
                 tracks.push(subtitle_track);
             }
         }
 
-        if tracks.is_empty() {
-            // Fallback to the original method if our simple approach doesn't work
-            self.extract_tracks_from_player_response(&player_response, video_id)
-        } else {
-            Ok(tracks)
+        if !tracks.is_empty() {
+            return Ok(tracks);
+        }
+
+        // A stripped player response sometimes leaves the captions in ytInitialData
+        if let Some(result) = self.extract_tracks_from_initial_data(&html, video_id) {
+            return result;
         }
+
+        // Fallback to the original method if our simple approach doesn't work
+        self.extract_tracks_from_player_response(&player_response, video_id)
+    }
+
+    /// Tracks from a captions renderer embedded in the page's `ytInitialData`, if any
+    fn extract_tracks_from_initial_data(
+        &self,
+        html: &str,
+        video_id: &str,
+    ) -> Option<YdlResult<Vec<SubtitleTrack>>> {
+        let renderer = extract_initial_data_captions(html)?;
+        debug!("Found captions renderer in ytInitialData");
+
+        let player_response = PlayerResponse {
+            captions: Some(CaptionTracks {
+                player_captions_tracklist_renderer: Some(renderer),
+            }),
+            video_details: None,
+            playability_status: None,
+        };
+        Some(self.extract_tracks_from_player_response(&player_response, video_id))
     }
 
     /// Discover subtitles from mobile endpoint
@@ -312,7 +876,7 @@ impl SubtitleExtractor {
         debug!("Trying to discover subtitles from mobile page");
 
         let url = format!("https://m.youtube.com/watch?v={}", video_id);
-        let response = self.client.get(&url).send().await?;
+        let response = network::send(self.client.get(&url), "web", &*self.options.metrics).await?;
 
         if !response.status().is_success() {
             return Err(self.map_http_error(response.status(), video_id));
@@ -334,7 +898,7 @@ impl SubtitleExtractor {
             video_id
         );
 
-        let response = self.client.get(&url).send().await?;
+        let response = network::send(self.client.get(&url), "web", &*self.options.metrics).await?;
 
         if !response.status().is_success() {
             return Err(YdlError::SubtitleDiscoveryError {
@@ -418,6 +982,108 @@ impl SubtitleExtractor {
         })
     }
 
+    /// Video IDs of a channel's uploads, newest first, up to `limit` if given
+    ///
+    /// Handles and legacy names are resolved to the channel ID through the channel
+    /// page; the uploads playlist (`UC...` → `UU...`) is then paged through with
+    /// InnerTube browse continuations.
+    pub async fn list_channel_uploads(
+        &self,
+        channel: &ChannelRef,
+        limit: Option<usize>,
+    ) -> YdlResult<Vec<String>> {
+        let channel_id = match channel {
+            ChannelRef::Id(id) => id.clone(),
+            _ => self.channel_id_from_page(&channel.page_url()).await?,
+        };
+        info!("Listing uploads of channel {}", channel_id);
+
+        let suffix = channel_id
+            .strip_prefix("UC")
+            .ok_or_else(|| YdlError::ChannelNotFound {
+                channel: channel_id.clone(),
+            })?;
+        let ids = self
+            .browse_video_ids(&format!("VLUU{}", suffix), limit)
+            .await?;
+
+        if ids.is_empty() {
+            return Err(YdlError::NoChannelUploads { channel_id });
+        }
+        debug!("Found {} uploads for channel {}", ids.len(), channel_id);
+        Ok(ids)
+    }
+
+    /// Video IDs of a playlist, in playlist order, paged through InnerTube browse
+    ///
+    /// Continuations are followed, so playlists longer than one page are listed in full.
+    pub async fn browse_playlist_video_ids(&self, playlist_id: &str) -> YdlResult<Vec<String>> {
+        info!("Browsing playlist: {}", playlist_id);
+
+        let ids = self
+            .browse_video_ids(&format!("VL{}", playlist_id), None)
+            .await?;
+
+        if ids.is_empty() {
+            return Err(YdlError::PlaylistNotFound {
+                playlist_id: playlist_id.to_string(),
+            });
+        }
+        debug!("Found {} videos in playlist {}", ids.len(), playlist_id);
+        Ok(ids)
+    }
+
+    /// Page through a browse ID and its continuations, collecting unique video IDs
+    async fn browse_video_ids(
+        &self,
+        browse_id: &str,
+        limit: Option<usize>,
+    ) -> YdlResult<Vec<String>> {
+        let mut request = serde_json::json!({ "browseId": browse_id });
+        let mut ids: Vec<String> = Vec::new();
+
+        while limit.is_none_or(|limit| ids.len() < limit) {
+            let Some(page) = self.youtube_client.browse(request).await? else {
+                break;
+            };
+            let (page_ids, continuation) = parse_browse_page(&page);
+            let before = ids.len();
+            for id in page_ids {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+
+            // A page without new videos means YouTube is looping or the list ended
+            let Some(token) = continuation.filter(|_| ids.len() > before) else {
+                break;
+            };
+            request = serde_json::json!({ "continuation": token });
+        }
+
+        if let Some(limit) = limit {
+            ids.truncate(limit);
+        }
+        Ok(ids)
+    }
+
+    /// Resolve a channel page (`/@handle`, `/c/name`, `/user/name`) to its `UC...` ID
+    async fn channel_id_from_page(&self, page_url: &str) -> YdlResult<String> {
+        let not_found = || YdlError::ChannelNotFound {
+            channel: page_url.to_string(),
+        };
+
+        let (status, html) =
+            fetch_text(&self.client, page_url, "web", &*self.options.metrics).await?;
+        match status.as_u16() {
+            200..=299 => {}
+            404 => return Err(not_found()),
+            _ => return Err(self.map_http_error(status, page_url)),
+        }
+
+        extract_channel_id(&html).ok_or_else(not_found)
+    }
+
     /// Extract video title from HTML
     fn extract_video_title(&self, html: &str) -> YdlResult<String> {
         // Try to find title in various places
@@ -457,22 +1123,12 @@ impl SubtitleExtractor {
                 debug!("Found tracklist renderer");
                 if let Some(caption_tracks) = &tracklist.caption_tracks {
                     debug!("Found {} caption tracks", caption_tracks.len());
-                    for track in caption_tracks {
-                        let language_name = track
-                            .name
-                            .as_ref()
-                            .and_then(|n| {
-                                n.simple_text.as_deref().or_else(|| {
-                                    n.runs
-                                        .as_ref()
-                                        .and_then(|runs| runs.first().map(|r| r.text.as_str()))
-                                })
-                            })
-                            .unwrap_or(&track.language_code)
-                            .to_string();
-
-                        // Determine track type based on kind or vss_id
-                        let track_type = if track.kind.as_deref() == Some("asr") {
+                    let default_index = tracklist.default_caption_index();
+                    for (index, track) in caption_tracks.iter().enumerate() {
+                        let (language_code, language_name, is_auto) = track.normalized_name();
+
+                        // Determine track type based on kind or the name's suffix
+                        let track_type = if is_auto || track.kind.as_deref() == Some("asr") {
                             SubtitleTrackType::AutoGenerated
                         } else {
                             SubtitleTrackType::Manual
@@ -480,19 +1136,18 @@ impl SubtitleExtractor {
 
                         debug!(
                             "Found subtitle track: lang={}, name={}, type={:?}, has_url={}",
-                            track.language_code,
+                            &language_code,
                             &language_name,
                             &track_type,
-                            !track.base_url.is_empty()
+                            !track.url().is_empty()
                         );
 
-                        let subtitle_track = SubtitleTrack::new(
-                            track.language_code.clone(),
-                            language_name,
-                            track_type,
-                        )
-                        .with_url(track.base_url.clone())
-                        .with_translatable(track.is_translatable.unwrap_or(false));
+                        let subtitle_track =
+                            SubtitleTrack::new(language_code, language_name, track_type)
+                                .with_url(track.url())
+                                .with_translatable(track.is_translatable.unwrap_or(false))
+                                .with_vss_id(track.vss_id.clone())
+                                .with_default(default_index == Some(index));
 
                         tracks.push(subtitle_track);
                     }
@@ -510,7 +1165,7 @@ impl SubtitleExtractor {
     }
 
     /// Filter tracks based on options
-    fn filter_tracks(
+    pub(crate) fn filter_tracks(
         &self,
         tracks: Vec<SubtitleTrack>,
         video_id: &str,
@@ -523,6 +1178,21 @@ impl SubtitleExtractor {
 
         let mut filtered = tracks;
 
+        if self.options.no_translations {
+            filtered.retain(|track| !track.is_translated());
+
+            // Without translations, the requested language must exist as a genuine track
+            if let Some(preferred_lang) = &self.options.language
+                && !filtered
+                    .iter()
+                    .any(|track| track.language_code == *preferred_lang)
+            {
+                return Err(YdlError::LanguageNotAvailable {
+                    language: preferred_lang.clone(),
+                });
+            }
+        }
+
         // Filter by language preference
         if let Some(preferred_lang) = &self.options.language {
             let lang_matches: Vec<_> = filtered
@@ -586,6 +1256,43 @@ impl SubtitleExtractor {
         }
     }
 
+    /// Select the track with exactly the given vssId (e.g. `.en` or `a.en`)
+    pub fn select_by_vss_id<'a>(
+        &self,
+        tracks: &'a [SubtitleTrack],
+        vss_id: &str,
+    ) -> Option<&'a SubtitleTrack> {
+        tracks
+            .iter()
+            .find(|track| track.vss_id.as_deref() == Some(vss_id))
+    }
+
+    /// Every track in `language`, at most one per track type: manual, then
+    /// auto-generated, then community
+    ///
+    /// Manual/auto preferences are ignored; translations are skipped if
+    /// `no_translations` is set.
+    pub fn select_variants<'a>(
+        &self,
+        tracks: &'a [SubtitleTrack],
+        language: &str,
+    ) -> Vec<&'a SubtitleTrack> {
+        [
+            SubtitleTrackType::Manual,
+            SubtitleTrackType::AutoGenerated,
+            SubtitleTrackType::Community,
+        ]
+        .iter()
+        .filter_map(|track_type| {
+            tracks.iter().find(|track| {
+                track.language_code == language
+                    && track.track_type == *track_type
+                    && !(self.options.no_translations && track.is_translated())
+            })
+        })
+        .collect()
+    }
+
     /// Select the best subtitle track based on preferences
     pub fn select_best_track<'a>(
         &'a self,
@@ -595,11 +1302,30 @@ impl SubtitleExtractor {
             return None;
         }
 
-        // If language is specified, prefer that, but also consider manual preference
-        if let Some(preferred_lang) = &self.options.language {
-            // First try to find a manual track in the preferred language
-            if self.options.prefer_manual
-                && let Some(track) = tracks.iter().find(|t| {
+        let eligible = |t: &&SubtitleTrack| !(self.options.no_translations && t.is_translated());
+
+        // Rank scored tracks by quality; the first of equally good tracks wins
+        if self.options.select_by_quality
+            && let Some(best) = tracks
+                .iter()
+                .filter(eligible)
+                .filter(|t| t.quality_score.is_some())
+                .reduce(|best, t| {
+                    if t.quality_score > best.quality_score {
+                        t
+                    } else {
+                        best
+                    }
+                })
+        {
+            return Some(best);
+        }
+
+        // If language is specified, prefer that, but also consider manual preference
+        if let Some(preferred_lang) = &self.options.language {
+            // First try to find a manual track in the preferred language
+            if self.options.prefer_manual
+                && let Some(track) = tracks.iter().filter(eligible).find(|t| {
                     t.language_code == *preferred_lang && t.track_type == SubtitleTrackType::Manual
                 })
             {
@@ -607,33 +1333,1027 @@ impl SubtitleExtractor {
             }
 
             // Then try any track in the preferred language
-            if let Some(track) = tracks.iter().find(|t| t.language_code == *preferred_lang) {
+            if let Some(track) = tracks
+                .iter()
+                .filter(eligible)
+                .find(|t| t.language_code == *preferred_lang)
+            {
                 return Some(track);
             }
         }
 
+        // Without a language preference, follow YouTube's own default track, unless it is
+        // auto-generated while manual tracks are preferred and available
+        if self.options.language.is_none()
+            && let Some(default) = tracks.iter().filter(eligible).find(|t| t.is_default)
+            && (!self.options.prefer_manual
+                || default.track_type == SubtitleTrackType::Manual
+                || !tracks
+                    .iter()
+                    .filter(eligible)
+                    .any(|t| t.track_type == SubtitleTrackType::Manual))
+        {
+            return Some(default);
+        }
+
         // Prefer manual over auto-generated (for any language)
         if self.options.prefer_manual
             && let Some(manual) = tracks
                 .iter()
+                .filter(eligible)
                 .find(|t| t.track_type == SubtitleTrackType::Manual)
         {
             return Some(manual);
         }
 
         // Fall back to first available track
-        tracks.first()
+        tracks.iter().find(eligible)
+    }
+}
+
+/// Fraction of `length` covered by the cues of `content`, and the number of cues
+///
+/// Overlapping cues are only counted once. Unparseable content covers nothing.
+fn caption_coverage(processor: &ContentProcessor, content: &str, length: Duration) -> (f64, usize) {
+    let Ok(parsed) = processor.parse_content(content, "") else {
+        return (0.0, 0);
+    };
+    (
+        entries_coverage(&parsed.entries, length),
+        parsed.entries.len(),
+    )
+}
+
+/// Fraction of `length` covered by `entries`, counting overlapping cues once
+fn entries_coverage(entries: &[SubtitleEntry], length: Duration) -> f64 {
+    if length.is_zero() {
+        return 1.0;
+    }
+
+    let mut spans: Vec<(Duration, Duration)> = entries.iter().map(|e| (e.start, e.end)).collect();
+    spans.sort();
+
+    let mut covered = Duration::ZERO;
+    let mut reached = Duration::ZERO;
+    for (start, end) in spans {
+        let start = start.max(reached);
+        if end > start {
+            covered += end - start;
+            reached = end;
+        }
+    }
+
+    (covered.as_secs_f64() / length.as_secs_f64()).min(1.0)
+}
+
+/// Fraction of `entries` readable at no more than `MAX_READABLE_CPS` characters per second
+fn readable_share(entries: &[SubtitleEntry]) -> f64 {
+    if entries.is_empty() {
+        return 0.0;
+    }
+
+    let readable = entries
+        .iter()
+        .filter(|e| {
+            let chars = e.text.chars().filter(|c| !c.is_whitespace()).count() as f64;
+            let seconds = e.end.saturating_sub(e.start).as_secs_f64();
+            seconds > 0.0 && chars / seconds <= MAX_READABLE_CPS
+        })
+        .count();
+    readable as f64 / entries.len() as f64
+}
+
+/// The first captions tracklist renderer with tracks anywhere in the page's `ytInitialData`
+fn extract_initial_data_captions(html: &str) -> Option<TrackListRenderer> {
+    fn find(value: &serde_json::Value) -> Option<&serde_json::Value> {
+        match value {
+            serde_json::Value::Object(fields) => fields
+                .get("playerCaptionsTracklistRenderer")
+                .filter(|renderer| renderer["captionTracks"].is_array())
+                .or_else(|| fields.values().find_map(find)),
+            serde_json::Value::Array(items) => items.iter().find_map(find),
+            _ => None,
+        }
+    }
+
+    const MARKER: &str = "ytInitialData = ";
+    let start = html.find(MARKER)? + MARKER.len();
+    // Read exactly one JSON value; whatever script follows it is ignored
+    let data = serde_json::Deserializer::from_str(&html[start..])
+        .into_iter::<serde_json::Value>()
+        .next()?
+        .ok()?;
+
+    serde_json::from_value(find(&data)?.clone()).ok()
+}
+
+/// The channel ID a channel page belongs to, from its metadata
+fn extract_channel_id(html: &str) -> Option<String> {
+    const MARKERS: &[&str] = &[
+        "\"externalId\":\"",
+        "<meta itemprop=\"identifier\" content=\"",
+        "<link rel=\"canonical\" href=\"https://www.youtube.com/channel/",
+    ];
+
+    MARKERS.iter().find_map(|marker| {
+        let start = html.find(marker)? + marker.len();
+        let id = html.get(start..start + 24)?;
+        (id.starts_with("UC")
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .then(|| id.to_string())
+    })
+}
+
+/// Video IDs and the continuation token of one page of an InnerTube browse response
+fn parse_browse_page(page: &serde_json::Value) -> (Vec<String>, Option<String>) {
+    fn walk(value: &serde_json::Value, ids: &mut Vec<String>, token: &mut Option<String>) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                if let Some(id) = fields
+                    .get("playlistVideoRenderer")
+                    .and_then(|renderer| renderer["videoId"].as_str())
+                {
+                    ids.push(id.to_string());
+                }
+                if token.is_none()
+                    && let Some(next) = fields
+                        .get("continuationCommand")
+                        .and_then(|command| command["token"].as_str())
+                {
+                    *token = Some(next.to_string());
+                }
+                fields.values().for_each(|field| walk(field, ids, token));
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|item| walk(item, ids, token)),
+            _ => {}
+        }
+    }
+
+    let mut ids = Vec::new();
+    let mut token = None;
+    walk(page, &mut ids, &mut token);
+    (ids, token)
+}
+
+/// The session's visitorData from a watch page's `ytcfg` or player response
+fn extract_visitor_data(html: &str) -> Option<String> {
+    ["\"VISITOR_DATA\":\"", "\"visitorData\":\""]
+        .iter()
+        .find_map(|key| {
+            let start = html.find(key)? + key.len();
+            let end = html[start..].find('"')?;
+            Some(html[start..start + end].to_string())
+        })
+        .filter(|visitor_data| !visitor_data.is_empty())
+}
+
+/// Whether `html` is a consent or bot-check interstitial rather than a watch page
+fn is_bot_check_page(html: &str) -> bool {
+    if html.contains("ytInitialPlayerResponse") {
+        return false;
+    }
+
+    [
+        "Before you continue",
+        "consent.youtube.com",
+        "unusual traffic",
+        "g-recaptcha",
+    ]
+    .iter()
+    .any(|marker| html.contains(marker))
+}
+
+/// Error for a download of `bytes` bytes that held no captions
+fn empty_content_error(bytes: usize) -> YdlError {
+    if bytes == 0 {
+        YdlError::subtitle_parsing("Empty subtitle content received")
+    } else {
+        YdlError::subtitle_parsing(format!(
+            "Subtitle content contains no captions ({} bytes)",
+            bytes
+        ))
+    }
+}
+
+/// Whether `url` has a `key` query parameter; unparseable URLs are searched for `key=`
+fn has_query_param(url: &str, key: &str) -> bool {
+    match url::Url::parse(url) {
+        Ok(parsed) => parsed.query_pairs().any(|(k, _)| k == key),
+        Err(_) => url.contains(&format!("{}=", key)),
+    }
+}
+
+/// Replace (or add) a query parameter, leaving unparseable URLs untouched
+fn set_query_param(url: &str, key: &str, value: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    parsed
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+
+    parsed.to_string()
+}
+
+/// GET `url` and read the body as text, retrying once if the body fails mid-read
+///
+/// Non-success responses are returned with an empty body for the caller to handle.
+pub(crate) async fn fetch_text(
+    client: &HttpClient,
+    url: &str,
+    label: &str,
+    metrics: &dyn Metrics,
+) -> YdlResult<(StatusCode, String)> {
+    let mut retried = false;
+
+    loop {
+        let response = network::send(client.get(url), label, metrics).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Ok((status, String::new()));
+        }
+
+        match response.text().await.map_err(YdlError::from) {
+            Ok(content) => {
+                trace!(target: network::TRACE_TARGET, client = label, bytes = content.len(), "body read");
+                metrics.bytes_downloaded(label, content.len());
+                return Ok((status, content));
+            }
+            Err(e) if !retried && e.is_body_read_error() => {
+                warn!(
+                    "Response body from {} failed mid-read, retrying: {}",
+                    network::redact_url(url),
+                    e
+                );
+                metrics.retry(1, &e);
+                retried = true;
+            }
+            Err(e) => return Err(e),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::tests::{MockResponse, test_server};
 
     fn test_options() -> YdlOptions {
         YdlOptions::new().timeout(10)
     }
 
+    #[test]
+    fn test_content_url_source_format() {
+        use crate::types::SourceFormat;
+
+        let base = "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&fmt=srv3";
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        assert_eq!(extractor.content_url(base), base);
+        assert_eq!(
+            extractor.content_url("https://www.youtube.com/api/timedtext?v=x"),
+            "https://www.youtube.com/api/timedtext?v=x&fmt=srv3"
+        );
+
+        let extractor =
+            SubtitleExtractor::new(test_options().source_format(SourceFormat::Json3)).unwrap();
+        assert_eq!(
+            extractor.content_url(base),
+            "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&fmt=json3"
+        );
+    }
+
+    #[test]
+    fn test_content_url_respects_base_url_fmt() {
+        use crate::types::SourceFormat;
+
+        let base = "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&fmt=vtt&bpctr=9999999999&name=a%20b";
+        let options = test_options()
+            .respect_base_url_fmt(true)
+            .source_format(SourceFormat::Json3);
+
+        let extractor = SubtitleExtractor::new(options.clone()).unwrap();
+        assert_eq!(extractor.content_url(base), base);
+
+        // Only a real fmt parameter counts as present
+        assert_eq!(
+            extractor.content_url("https://www.youtube.com/api/timedtext?v=x&xfmt=1"),
+            "https://www.youtube.com/api/timedtext?v=x&xfmt=1&fmt=json3"
+        );
+
+        let extractor = SubtitleExtractor::new(test_options().respect_base_url_fmt(true)).unwrap();
+        assert_eq!(
+            extractor.content_url("https://www.youtube.com/api/timedtext?v=x"),
+            "https://www.youtube.com/api/timedtext?v=x&fmt=srv3"
+        );
+
+        // By default the interstitial parameter is stripped and the format replaced
+        let extractor =
+            SubtitleExtractor::new(test_options().source_format(SourceFormat::Json3)).unwrap();
+        assert!(!extractor.content_url(base).contains("bpctr"));
+        assert!(extractor.content_url(base).ends_with("fmt=json3"));
+    }
+
+    #[tokio::test]
+    async fn test_watch_page_rotates_user_agent_on_bot_check() {
+        let addr = test_server(|request| {
+            // Only the second User-Agent in the pool gets the real page
+            if request.header("user-agent") == Some("second-browser") {
+                MockResponse::ok("<script>var ytInitialPlayerResponse = {};</script>")
+            } else {
+                MockResponse::ok("<html><h1>Before you continue to YouTube</h1></html>")
+            }
+        })
+        .await;
+
+        let options = test_options().user_agent_pool(vec![
+            "first-browser".to_string(),
+            "second-browser".to_string(),
+        ]);
+        let extractor = SubtitleExtractor::new(options).unwrap();
+
+        let html = extractor
+            .fetch_watch_page(
+                &format!("http://{}/watch?v=dQw4w9WgXcQ", addr),
+                "dQw4w9WgXcQ",
+            )
+            .await
+            .unwrap();
+        assert!(html.contains("ytInitialPlayerResponse"));
+
+        let extractor =
+            SubtitleExtractor::new(test_options().user_agent_pool(vec!["first-browser".into()]))
+                .unwrap();
+        let html = extractor
+            .fetch_watch_page(
+                &format!("http://{}/watch?v=dQw4w9WgXcQ", addr),
+                "dQw4w9WgXcQ",
+            )
+            .await
+            .unwrap();
+        assert!(is_bot_check_page(&html));
+    }
+
+    #[tokio::test]
+    async fn test_restricted_watch_page_retries_embedded_client() {
+        let addr = test_server(|request| {
+            // The watch page and every client but the embedded player are blocked
+            if request.header("x-youtube-client-name") == Some("85") {
+                MockResponse::ok(
+                    r#"{"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[
+                        {"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en",
+                         "name":{"simpleText":"English"},"vssId":".en","languageCode":"en"}]}}}"#,
+                )
+            } else {
+                MockResponse::status(403, "")
+            }
+        })
+        .await;
+
+        let mut extractor = SubtitleExtractor::new(test_options()).unwrap();
+        extractor.youtube_client = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+        let watch_url = format!("http://{}/watch?v=dQw4w9WgXcQ", addr);
+
+        let (tracks, method) = extractor
+            .discover_from_watch_page_or_embed(&watch_url, "dQw4w9WgXcQ")
+            .await
+            .unwrap();
+        assert_eq!(method, "embedded_player");
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].vss_id.as_deref(), Some(".en"));
+
+        // Without an embeddable fallback the restriction itself is reported
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        extractor.youtube_client = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", closed));
+        let result = extractor
+            .discover_from_watch_page_or_embed(&watch_url, "dQw4w9WgXcQ")
+            .await;
+        assert!(matches!(result, Err(YdlError::VideoRestricted { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_shorts_ask_mobile_clients_first() {
+        let clients = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let seen = clients.clone();
+        let addr = test_server(move |request| {
+            let body: serde_json::Value = serde_json::from_str(request.body()).unwrap_or_default();
+            let client = body["context"]["client"]["clientName"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            seen.lock().unwrap().push(client.clone());
+
+            // Like many Shorts, only the mobile clients see the auto-generated track
+            if client == "IOS" || client == "ANDROID" {
+                MockResponse::ok(
+                    r#"{"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&kind=asr","name":{"simpleText":"English (auto-generated)"},"vssId":"a.en","languageCode":"en","kind":"asr"}]}}}"#,
+                )
+            } else {
+                MockResponse::ok(r#"{"playabilityStatus":{"status":"OK"}}"#)
+            }
+        })
+        .await;
+
+        let parser = crate::parser::YouTubeParser::new();
+        let url = "https://www.youtube.com/shorts/dQw4w9WgXcQ";
+        let extractor = SubtitleExtractor::new(test_options())
+            .unwrap()
+            .with_web_base(&format!("http://{}", addr))
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr))
+            .for_short(parser.is_shorts_url(url));
+
+        let tracks = extractor
+            .discover_tracks(&parser.parse_url(url).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].track_type, SubtitleTrackType::AutoGenerated);
+        assert_eq!(tracks[0].language_name, "English");
+        assert!(tracks[0].url.as_deref().unwrap().contains("c=IOS"));
+        assert_eq!(*clients.lock().unwrap(), ["IOS"]);
+    }
+
+    #[tokio::test]
+    async fn test_signed_in_requests_carry_visitor_data() {
+        let (request_tx, mut request_rx) = tokio::sync::mpsc::unbounded_channel();
+        let addr = test_server(move |request| {
+            let _ = request_tx.send(request.to_string());
+            if request.starts_with("GET /watch") {
+                MockResponse::ok(r#"<script>ytcfg.set({"VISITOR_DATA":"CgtWaXNpdG9y"});</script>"#)
+            } else {
+                MockResponse::ok("{}")
+            }
+        })
+        .await;
+
+        let base = format!("http://{}", addr);
+        let extractor = SubtitleExtractor::new(test_options().cookies("SID=secret"))
+            .unwrap()
+            .with_web_base(&base)
+            .with_player_endpoint(&format!("{}/youtubei/v1/player", base));
+        let _ = extractor.discover_tracks("dQw4w9WgXcQ").await;
+
+        // The watch page is read with the cookies before any InnerTube request
+        let watch = request_rx.recv().await.unwrap();
+        assert!(watch.starts_with("GET /watch?v=dQw4w9WgXcQ"));
+        assert!(watch.to_lowercase().contains("cookie: sid=secret"));
+
+        let player = request_rx.recv().await.unwrap();
+        assert!(player.starts_with("POST /youtubei/v1/player"));
+        assert!(player.to_lowercase().contains("cookie: sid=secret"));
+        let body: serde_json::Value =
+            serde_json::from_str(player.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["context"]["client"]["visitorData"], "CgtWaXNpdG9y");
+
+        assert_eq!(
+            extract_visitor_data(r#"{"responseContext":{"visitorData":"Cgt4"}}"#).as_deref(),
+            Some("Cgt4")
+        );
+        assert_eq!(extract_visitor_data("<html></html>"), None);
+    }
+
+    #[tokio::test]
+    async fn test_browse_ignores_discovery_clients() {
+        let addr = test_server(|request| {
+            if request.contains(r#""clientName":"WEB""#) {
+                MockResponse::ok(
+                    r#"{"contents":[{"playlistVideoRenderer":{"videoId":"dQw4w9WgXcQ","index":1}}]}"#,
+                )
+            } else {
+                MockResponse::status(400, "")
+            }
+        })
+        .await;
+
+        // As with --client ios: discovery is limited to iOS, playlists still browse as WEB
+        let options = test_options().innertube_clients(vec!["ios".parse().unwrap()]);
+        let extractor = SubtitleExtractor::new(options)
+            .unwrap()
+            .with_browse_endpoint(&format!("http://{}/youtubei/v1/browse", addr));
+
+        let ids = extractor.browse_playlist_video_ids("PLtest").await.unwrap();
+        assert_eq!(ids, vec!["dQw4w9WgXcQ"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_channel_uploads_follows_continuations() {
+        // Trimmed from a captured uploads-playlist browse response and its continuation
+        const FIRST_PAGE: &str = r#"{"contents":{"twoColumnBrowseResultsRenderer":{"tabs":[{"tabRenderer":{"selected":true,"content":{"sectionListRenderer":{"contents":[{"itemSectionRenderer":{"contents":[{"playlistVideoListRenderer":{"contents":[
+            {"playlistVideoRenderer":{"videoId":"jNQXAC9IVRw","title":{"runs":[{"text":"Newest upload"}]},"index":{"simpleText":"1"}}},
+            {"playlistVideoRenderer":{"videoId":"dQw4w9WgXcQ","title":{"runs":[{"text":"Second upload"}]},"index":{"simpleText":"2"}}},
+            {"continuationItemRenderer":{"trigger":"CONTINUATION_TRIGGER_ON_ITEM_SHOWN","continuationEndpoint":{"commandMetadata":{"webCommandMetadata":{"apiUrl":"/youtubei/v1/browse"}},"continuationCommand":{"token":"4qmFsgJhEhpWTFVVdUFY","request":"CONTINUATION_REQUEST_TYPE_BROWSE"}}}}
+            ],"playlistId":"UUuAXFkgsw1L7xaCfnd5JJOw"}}]}}]}}}}]}}}"#;
+        const SECOND_PAGE: &str = r#"{"onResponseReceivedActions":[{"appendContinuationItemsAction":{"continuationItems":[
+            {"playlistVideoRenderer":{"videoId":"9bZkp7q19f0","title":{"runs":[{"text":"Oldest upload"}]},"index":{"simpleText":"3"}}}
+            ],"targetId":"VLUUuAXFkgsw1L7xaCfnd5JJOw"}}]}"#;
+
+        let addr = test_server(|request| {
+            if request.starts_with("GET /@somecreator ") {
+                MockResponse::ok(
+                    r#"<html><script>var ytInitialData = {"metadata":{"channelMetadataRenderer":{"title":"Some Creator","externalId":"UCuAXFkgsw1L7xaCfnd5JJOw"}}};</script></html>"#,
+                )
+            } else if request.contains(r#""browseId":"VLUUuAXFkgsw1L7xaCfnd5JJOw""#) {
+                MockResponse::ok(FIRST_PAGE)
+            } else if request.contains(r#""continuation":"4qmFsgJhEhpWTFVVdUFY""#) {
+                MockResponse::ok(SECOND_PAGE)
+            } else {
+                MockResponse::status(404, "")
+            }
+        })
+        .await;
+
+        let mut extractor = SubtitleExtractor::new(test_options()).unwrap();
+        extractor.youtube_client = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_browse_endpoint(&format!("http://{}/youtubei/v1/browse", addr));
+
+        let channel_id = extractor
+            .channel_id_from_page(&format!("http://{}/@somecreator", addr))
+            .await
+            .unwrap();
+        assert_eq!(channel_id, "UCuAXFkgsw1L7xaCfnd5JJOw");
+
+        let channel = ChannelRef::Id(channel_id);
+        let uploads = extractor
+            .list_channel_uploads(&channel, None)
+            .await
+            .unwrap();
+        assert_eq!(uploads, vec!["jNQXAC9IVRw", "dQw4w9WgXcQ", "9bZkp7q19f0"]);
+
+        let uploads = extractor
+            .list_channel_uploads(&channel, Some(2))
+            .await
+            .unwrap();
+        assert_eq!(uploads, vec!["jNQXAC9IVRw", "dQw4w9WgXcQ"]);
+
+        let missing = extractor
+            .channel_id_from_page(&format!("http://{}/@nobody", addr))
+            .await;
+        assert!(matches!(missing, Err(YdlError::ChannelNotFound { .. })));
+
+        let empty = ChannelRef::Id("UCAAAAAAAAAAAAAAAAAAAAAA".to_string());
+        let result = extractor.list_channel_uploads(&empty, None).await;
+        assert!(matches!(result, Err(YdlError::NoChannelUploads { .. })));
+
+        // The uploads list is also a playlist, browsed the same way
+        let videos = extractor
+            .browse_playlist_video_ids("UUuAXFkgsw1L7xaCfnd5JJOw")
+            .await
+            .unwrap();
+        assert_eq!(videos, vec!["jNQXAC9IVRw", "dQw4w9WgXcQ", "9bZkp7q19f0"]);
+
+        let result = extractor
+            .browse_playlist_video_ids("PLAAAAAAAAAAAAAAAAAAAAAA")
+            .await;
+        assert!(matches!(result, Err(YdlError::PlaylistNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_sparse_track_falls_back_to_better_covered_one() {
+        // Five cues for an hour-long video, and a track that actually covers it
+        let cue = |i: usize| format!(r#"<text start="{}" dur="3">line {}</text>"#, i * 10, i);
+        let sparse = format!(
+            "<transcript>{}</transcript>",
+            (0..5).map(cue).collect::<String>()
+        );
+        let full = format!(
+            "<transcript>{}</transcript>",
+            (0..360).map(cue).collect::<String>()
+        );
+
+        let addr = test_server(move |request| {
+            if request.starts_with("POST") {
+                MockResponse::ok(
+                    r#"{"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Long talk","lengthSeconds":"3600"}}"#,
+                )
+            } else if request.starts_with("GET /sparse") {
+                MockResponse::ok(sparse.clone())
+            } else {
+                MockResponse::ok(full.clone())
+            }
+        })
+        .await;
+
+        let mut extractor = SubtitleExtractor::new(test_options()).unwrap();
+        extractor.youtube_client = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+        let tracks = vec![
+            SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+                .with_url(format!("http://{}/sparse", addr)),
+            SubtitleTrack::new("fr".into(), "French".into(), SubtitleTrackType::Manual)
+                .with_url(format!("http://{}/full-fr", addr)),
+            SubtitleTrack::new(
+                "en".into(),
+                "English".into(),
+                SubtitleTrackType::AutoGenerated,
+            )
+            .with_url(format!("http://{}/full-en", addr)),
+        ];
+
+        let (content, track) = extractor
+            .download_with_coverage_check(&tracks, &tracks[0], "dQw4w9WgXcQ", 0.1, None)
+            .await
+            .unwrap();
+        assert_eq!(track.track_type, SubtitleTrackType::AutoGenerated);
+        assert_eq!(track.language_code, "en");
+        assert!(content.contains("line 359"));
+
+        // With nothing better in the same language, the sparse download is kept
+        let (content, track) = extractor
+            .download_with_coverage_check(&tracks[..2], &tracks[0], "dQw4w9WgXcQ", 0.1, None)
+            .await
+            .unwrap();
+        assert_eq!(track.track_type, SubtitleTrackType::Manual);
+        assert!(content.contains("line 4") && !content.contains("line 5"));
+    }
+
+    #[tokio::test]
+    async fn test_quality_selection_prefers_higher_coverage() {
+        use crate::types::QualityWeights;
+
+        // A manual track with five cues for an hour-long video, and a complete auto track
+        let cue = |i: usize| format!(r#"<text start="{}" dur="3">line {}</text>"#, i * 10, i);
+        let sparse = format!(
+            "<transcript>{}</transcript>",
+            (0..5).map(cue).collect::<String>()
+        );
+        let full = format!(
+            "<transcript>{}</transcript>",
+            (0..360).map(cue).collect::<String>()
+        );
+
+        let addr = test_server(move |request| {
+            if request.starts_with("POST") {
+                MockResponse::ok(
+                    r#"{"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Long talk","lengthSeconds":"3600"}}"#,
+                )
+            } else if request.starts_with("GET /sparse") {
+                MockResponse::ok(sparse.clone())
+            } else {
+                MockResponse::ok(full.clone())
+            }
+        })
+        .await;
+
+        let tracks = vec![
+            SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+                .with_url(format!("http://{}/sparse", addr)),
+            SubtitleTrack::new(
+                "en".into(),
+                "English".into(),
+                SubtitleTrackType::AutoGenerated,
+            )
+            .with_url(format!("http://{}/full", addr)),
+        ];
+        let extractor_with = |options: YdlOptions| {
+            let mut extractor = SubtitleExtractor::new(options).unwrap();
+            extractor.youtube_client = YouTubeSubtitleExtractor::new()
+                .unwrap()
+                .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+            extractor
+        };
+
+        // The priority heuristic picks the sparse manual track
+        let extractor = extractor_with(test_options().language("en"));
+        let best = extractor.select_best_track(&tracks).unwrap();
+        assert_eq!(best.track_type, SubtitleTrackType::Manual);
+
+        let extractor = extractor_with(test_options().language("en").select_by_quality(true));
+        let mut scored = tracks.clone();
+        extractor.score_tracks(&mut scored, "dQw4w9WgXcQ").await;
+        let (sparse_score, full_score) = (
+            scored[0].quality_score.unwrap(),
+            scored[1].quality_score.unwrap(),
+        );
+        assert!(full_score > sparse_score, "{full_score} <= {sparse_score}");
+        let best = extractor.select_best_track(&scored).unwrap();
+        assert_eq!(best.track_type, SubtitleTrackType::AutoGenerated);
+
+        // Weights are tunable: weighing only manual-ness brings the manual track back
+        let manual_only = QualityWeights {
+            manual: 1.0,
+            coverage: 0.0,
+            entries: 0.0,
+            reading_speed: 0.0,
+            language: 0.0,
+        };
+        let extractor = extractor_with(
+            test_options()
+                .select_by_quality(true)
+                .quality_weights(manual_only),
+        );
+        let mut scored = tracks.clone();
+        extractor.score_tracks(&mut scored, "dQw4w9WgXcQ").await;
+        let best = extractor.select_best_track(&scored).unwrap();
+        assert_eq!(best.track_type, SubtitleTrackType::Manual);
+    }
+
+    #[tokio::test]
+    async fn test_watch_page_captions_from_initial_data() {
+        // The player response is stripped; only ytInitialData lists the tracks
+        let addr = test_server(|_| {
+            MockResponse::ok(
+                r#"<html><script>var ytInitialPlayerResponse = {"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Stripped"}};</script><script>var ytInitialData = {"contents":{"twoColumnWatchNextResults":{"results":[{"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en","name":{"simpleText":"English"},"vssId":".en","languageCode":"en"},{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=de&kind=asr","name":{"simpleText":"German (auto-generated)"},"vssId":"a.de","languageCode":"de","kind":"asr"}]}}}]}}};</script></html>"#,
+            )
+        })
+        .await;
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        let tracks = extractor
+            .discover_from_watch_page(
+                &format!("http://{}/watch?v=dQw4w9WgXcQ", addr),
+                "dQw4w9WgXcQ",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].language_code, "en");
+        assert_eq!(tracks[0].track_type, SubtitleTrackType::Manual);
+        assert_eq!(tracks[1].track_type, SubtitleTrackType::AutoGenerated);
+        assert!(tracks[1].url.as_deref().unwrap().contains("kind=asr"));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_discovery_reports_event() {
+        use crate::metrics::tests::RecordingMetrics;
+        use std::sync::Arc;
+
+        let addr = test_server(|request| {
+            // Every InnerTube client fails; the watch page still lists the track
+            if request.starts_with("GET /watch") {
+                MockResponse::ok(
+                    r#"<html><script>var ytInitialPlayerResponse = {"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en","name":{"simpleText":"English"},"vssId":".en","languageCode":"en"}]}}};</script></html>"#,
+                )
+            } else {
+                MockResponse::status(500, "")
+            }
+        })
+        .await;
+
+        let metrics = Arc::new(RecordingMetrics::default());
+        let mut extractor = SubtitleExtractor::new(test_options().metrics(metrics.clone()))
+            .unwrap()
+            .with_web_base(&format!("http://{}", addr));
+        extractor.youtube_client = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+
+        let tracks = extractor.discover_uncached("dQw4w9WgXcQ").await.unwrap();
+        assert_eq!(tracks.len(), 1);
+
+        let events = metrics.events();
+        assert!(
+            events.contains(&"fallback watch_page".to_string()),
+            "{events:?}"
+        );
+        assert_eq!(
+            events.iter().filter(|e| e.starts_with("fallback")).count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discovery_uses_shared_cache() {
+        use crate::cache::AvailabilityCache;
+        use crate::metrics::tests::RecordingMetrics;
+        use std::sync::Arc;
+
+        let cache = Arc::new(AvailabilityCache::default());
+        cache.insert(
+            "dQw4w9WgXcQ",
+            vec![
+                SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+                    .with_vss_id(".en".into()),
+            ],
+        );
+
+        // A second instance sharing the cache is served without touching the network
+        let metrics = Arc::new(RecordingMetrics::default());
+        let options = test_options()
+            .availability_cache(cache.clone())
+            .metrics(metrics.clone());
+        let extractor = SubtitleExtractor::new(options).unwrap();
+
+        let tracks = extractor.discover_tracks("dQw4w9WgXcQ").await.unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].vss_id.as_deref(), Some(".en"));
+        assert_eq!(metrics.events(), vec!["cache dQw4w9WgXcQ".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_events_for_download() {
+        use crate::metrics::tests::RecordingMetrics;
+        use std::sync::Arc;
+
+        let addr = test_server(|_| {
+            MockResponse::ok("<transcript><text start=\"1\" dur=\"2\">Hi</text></transcript>")
+        })
+        .await;
+
+        let metrics = Arc::new(RecordingMetrics::default());
+        let extractor = SubtitleExtractor::new(test_options().metrics(metrics.clone())).unwrap();
+        let track = SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+            .with_url(format!("http://{}/api/timedtext?v=dQw4w9WgXcQ", addr));
+
+        let content = extractor
+            .download_content(&track, "dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            metrics.events(),
+            vec![
+                "request TVHTML5_SIMPLY_EMBEDDED_PLAYER Some(200)".to_string(),
+                format!("bytes TVHTML5_SIMPLY_EMBEDDED_PLAYER {}", content.len()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_skips_empty_transcript_shell() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let addr = test_server(move |_| {
+            // The first download is a non-empty shell without a single cue
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::ok(
+                    "<?xml version=\"1.0\" encoding=\"utf-8\" ?><transcript></transcript>",
+                )
+            } else {
+                MockResponse::ok("<transcript><text start=\"1\" dur=\"2\">Hi</text></transcript>")
+            }
+        })
+        .await;
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        assert!(!extractor.has_captions("<transcript></transcript>"));
+        assert!(!extractor.has_captions("{}"));
+        assert!(!extractor.has_captions("<html><body>Error 404</body></html>"));
+        assert!(
+            extractor
+                .has_captions("<transcript><text start=\"1\" dur=\"2\">Hi</text></transcript>")
+        );
+
+        let track = SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+            .with_url(format!("http://{}/api/timedtext?v=dQw4w9WgXcQ", addr));
+        let content = extractor
+            .download_content(&track, "dQw4w9WgXcQ")
+            .await
+            .unwrap();
+        assert!(content.contains("Hi"));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Content below the configured minimum is rejected even if it parses
+        let strict = SubtitleExtractor::new(test_options().min_content_bytes(1024)).unwrap();
+        assert!(
+            !strict.has_captions("<transcript><text start=\"1\" dur=\"2\">Hi</text></transcript>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_strips_interstitial_params() {
+        let addr = test_server(|request| {
+            // Like YouTube, answer a URL carrying `bpctr` with an interstitial page
+            if request.lines().next().unwrap_or("").contains("bpctr=") {
+                MockResponse::ok(
+                    "<!DOCTYPE html><html><body>Before you continue to YouTube</body></html>",
+                )
+            } else {
+                MockResponse::ok("<transcript><text start=\"1\" dur=\"2\">Hi</text></transcript>")
+            }
+        })
+        .await;
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        let track = SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+            .with_url(format!(
+                "http://{}/api/timedtext?v=dQw4w9WgXcQ&bpctr=9999999999&lang=en",
+                addr
+            ));
+
+        let content = extractor
+            .download_content(&track, "dQw4w9WgXcQ")
+            .await
+            .unwrap();
+        assert!(content.starts_with("<transcript>"));
+    }
+
+    #[tokio::test]
+    async fn test_download_translated_adds_tlang() {
+        let addr = test_server(|request| {
+            if request.lines().next().unwrap_or("").contains("tlang=en") {
+                MockResponse::ok(
+                    "<transcript><text start=\"1\" dur=\"2\">Hello</text></transcript>",
+                )
+            } else {
+                MockResponse::ok(
+                    "<transcript><text start=\"1\" dur=\"2\">Hallo</text></transcript>",
+                )
+            }
+        })
+        .await;
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        let track = SubtitleTrack::new("de".into(), "German".into(), SubtitleTrackType::Manual)
+            .with_url(format!(
+                "http://{}/api/timedtext?v=dQw4w9WgXcQ&lang=de",
+                addr
+            ))
+            .with_translatable(true);
+
+        let content = extractor
+            .download_translated(&track, "dQw4w9WgXcQ", "en")
+            .await
+            .unwrap();
+        assert!(content.contains("Hello"));
+
+        let locked = track.clone().with_translatable(false);
+        let result = extractor
+            .download_translated(&locked, "dQw4w9WgXcQ", "en")
+            .await;
+        assert!(matches!(
+            result,
+            Err(YdlError::LanguageNotAvailable { language }) if language == "en"
+        ));
+    }
+
+    #[test]
+    fn test_fetch_text_retries_truncated_body() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        let metrics = crate::metrics::tests::RecordingMetrics::default();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let counter = connections.clone();
+        let (fetched, logs) =
+            crate::processor::tests::capture_logs_at(tracing::Level::WARN, || {
+                runtime.block_on(async {
+                    let addr = test_server(move |_| {
+                        let body = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHello\n";
+                        // The first response promises the full body but drops the connection halfway
+                        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                            MockResponse::ok(body).truncated(10)
+                        } else {
+                            MockResponse::ok(body)
+                        }
+                    })
+                    .await;
+
+                    let client = HttpConfig::from_options(&YdlOptions::default())
+                        .unwrap()
+                        .client(reqwest::header::HeaderMap::new(), |builder| builder)
+                        .unwrap();
+                    fetch_text(
+                        &client,
+                        &format!("http://{}/sub.vtt?signature=secret", addr),
+                        "test",
+                        &metrics,
+                    )
+                    .await
+                })
+            });
+        let (status, content) = fetched.unwrap();
+
+        assert!(status.is_success());
+        assert!(content.contains("Hello"));
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert!(metrics.events().contains(&"retry 1".to_string()));
+
+        // The retry warning names the URL without its credentials
+        assert!(logs.contains("failed mid-read"), "{logs}");
+        assert!(logs.contains("signature=REDACTED"), "{logs}");
+        assert!(!logs.contains("secret"), "{logs}");
+    }
+
     #[tokio::test]
     async fn test_extractor_creation() {
         let options = test_options();
@@ -659,6 +2379,54 @@ mod tests {
         assert_eq!(title.unwrap(), "Test Video");
     }
 
+    #[test]
+    fn test_track_names_from_runs_match_across_paths() {
+        let player_response: PlayerResponse = serde_json::from_value(serde_json::json!({
+            "captions": {"playerCaptionsTracklistRenderer": {"captionTracks": [
+                {
+                    "baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=de",
+                    "name": {"runs": [{"text": "Deutsch"}, {"text": " (automatisch erzeugt)"}]},
+                    "vssId": "a.de",
+                    "languageCode": "de",
+                    "kind": "asr"
+                },
+                {
+                    "baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en",
+                    "name": {"simpleText": "Englisch"},
+                    "vssId": ".en",
+                    "languageCode": "en"
+                },
+                {
+                    "baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=fr",
+                    "vssId": ".fr",
+                    "languageCode": "fr"
+                }
+            ]}}
+        }))
+        .unwrap();
+
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+        let page_names: Vec<String> = extractor
+            .extract_tracks_from_player_response(&player_response, "dQw4w9WgXcQ")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.language_name)
+            .collect();
+        let innertube_names: Vec<String> =
+            crate::youtube_client::InnerTubeClient::new(ClientType::Web)
+                .unwrap()
+                .extract_subtitle_tracks(&player_response, "dQw4w9WgXcQ")
+                .into_iter()
+                .map(|t| t.language_name)
+                .collect();
+
+        assert_eq!(
+            page_names,
+            ["Deutsch (automatisch erzeugt)", "Englisch", "fr"]
+        );
+        assert_eq!(innertube_names, page_names);
+    }
+
     #[test]
     fn test_filter_tracks() {
         let extractor = SubtitleExtractor::new(test_options()).unwrap();
@@ -694,6 +2462,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_by_vss_id() {
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+
+        let tracks = vec![
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                SubtitleTrackType::Manual,
+            )
+            .with_vss_id(".en".to_string()),
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English (auto-generated)".to_string(),
+                SubtitleTrackType::AutoGenerated,
+            )
+            .with_vss_id("a.en".to_string()),
+        ];
+
+        let auto = extractor.select_by_vss_id(&tracks, "a.en").unwrap();
+        assert_eq!(auto.track_type, SubtitleTrackType::AutoGenerated);
+
+        let manual = extractor.select_by_vss_id(&tracks, ".en").unwrap();
+        assert_eq!(manual.track_type, SubtitleTrackType::Manual);
+
+        assert!(extractor.select_by_vss_id(&tracks, "en").is_none());
+    }
+
+    #[test]
+    fn test_select_variants() {
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+
+        let tracks = vec![
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English (auto-generated)".to_string(),
+                SubtitleTrackType::AutoGenerated,
+            ),
+            SubtitleTrack::new(
+                "es".to_string(),
+                "Spanish".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English (variant)".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+        ];
+
+        let variants = extractor.select_variants(&tracks, "en");
+        let types: Vec<_> = variants.iter().map(|t| t.track_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![SubtitleTrackType::Manual, SubtitleTrackType::AutoGenerated]
+        );
+        assert_eq!(variants[0].language_name, "English");
+
+        assert!(extractor.select_variants(&tracks, "fr").is_empty());
+    }
+
+    #[test]
+    fn test_select_best_track_prefers_default() {
+        let extractor = SubtitleExtractor::new(test_options()).unwrap();
+
+        let tracks = vec![
+            SubtitleTrack::new(
+                "de".to_string(),
+                "German".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                SubtitleTrackType::Manual,
+            )
+            .with_default(true),
+        ];
+        let best = extractor.select_best_track(&tracks).unwrap();
+        assert_eq!(best.language_code, "en");
+
+        // A language preference still wins over the default
+        let extractor = SubtitleExtractor::new(test_options().language("de")).unwrap();
+        let best = extractor.select_best_track(&tracks).unwrap();
+        assert_eq!(best.language_code, "de");
+    }
+
     #[test]
     fn test_select_best_track() {
         let options = YdlOptions::new().language("en").prefer_manual(true);
@@ -725,6 +2585,45 @@ mod tests {
         assert_eq!(selected.track_type, SubtitleTrackType::Manual);
     }
 
+    #[test]
+    fn test_no_translations_requires_genuine_track() {
+        let tracks = vec![
+            SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+            SubtitleTrack::new(
+                "fr".to_string(),
+                "French (translated)".to_string(),
+                SubtitleTrackType::AutoGenerated,
+            )
+            .with_translated_from("en".to_string()),
+        ];
+
+        let extractor = SubtitleExtractor::new(test_options().language("fr")).unwrap();
+        let filtered = extractor
+            .filter_tracks(tracks.clone(), "test_video_id")
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert!(filtered[0].is_translated());
+        assert_eq!(
+            extractor.select_best_track(&tracks).unwrap().language_code,
+            "fr"
+        );
+
+        let extractor =
+            SubtitleExtractor::new(test_options().language("fr").no_translations(true)).unwrap();
+        assert!(matches!(
+            extractor.filter_tracks(tracks.clone(), "test_video_id"),
+            Err(YdlError::LanguageNotAvailable { language }) if language == "fr"
+        ));
+        assert_eq!(
+            extractor.select_best_track(&tracks).unwrap().language_code,
+            "en"
+        );
+    }
+
     #[test]
     fn test_map_http_error() {
         let extractor = SubtitleExtractor::new(test_options()).unwrap();
@@ -1,21 +1,82 @@
+pub mod cache;
 pub mod error;
 pub mod extractor;
+pub mod merge;
+pub mod metrics;
+pub mod network;
+pub mod output;
 pub mod parser;
 pub mod processor;
 pub mod types;
 pub mod youtube_client;
 
+pub use cache::AvailabilityCache;
 pub use error::{YdlError, YdlResult};
+pub use metrics::{Metrics, NoopMetrics};
+pub use parser::{ChannelRef, ParsedUrl, PlaylistPosition, PlaylistRef, YouTubeRef, classify_url};
+pub use processor::CachedSubtitles;
 pub use types::{
-    ParsedSubtitles, SubtitleEntry, SubtitleResult, SubtitleTrack, SubtitleTrackType, SubtitleType,
+    Chapter, ParsedSubtitles, QualityWeights, Segmentation, SourceFormat, SubtitleEntry,
+    SubtitleResult, SubtitleTrack, SubtitleTrackType, SubtitleType, TextTransform, Timecode,
     VideoMetadata, YdlOptions,
 };
+pub use youtube_client::ClientType;
 
 use extractor::SubtitleExtractor;
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt};
 use parser::YouTubeParser;
 use processor::ContentProcessor;
-use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Shortest wait between retries, even when an error suggests retrying immediately
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Tracks to select from, with the content of those downloaded to score them
+struct Candidates {
+    tracks: Vec<SubtitleTrack>,
+    /// Content of each of `tracks`, by index, when scoring downloaded it
+    downloaded: Vec<Option<String>>,
+}
+
+impl Candidates {
+    /// Content of `track` (one of `tracks`), if it was downloaded while scoring
+    fn downloaded(&self, track: &SubtitleTrack) -> Option<String> {
+        let index = self.tracks.iter().position(|t| std::ptr::eq(t, track))?;
+        self.downloaded.get(index).cloned().flatten()
+    }
+}
+
+/// Watch-page metadata of one video, fetched on first use and shared by every clone
+///
+/// A [`Ydl`] hands one to each [`CachedSubtitles`] it makes, so both fill the same memo.
+#[derive(Clone)]
+pub(crate) struct LazyDetails {
+    extractor: Arc<SubtitleExtractor>,
+    video_id: String,
+    details: Arc<Mutex<Option<VideoMetadata>>>,
+}
+
+impl LazyDetails {
+    /// The metadata, fetched now unless an earlier call already did
+    pub(crate) async fn get(&self) -> YdlResult<VideoMetadata> {
+        let cached = self
+            .details
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(details) = cached {
+            return Ok(details);
+        }
+
+        let details = self.extractor.get_video_details(&self.video_id).await?;
+        *self.details.lock().unwrap_or_else(|e| e.into_inner()) = Some(details.clone());
+        Ok(details)
+    }
+}
 
 /// Main orchestrator for subtitle downloads
 pub struct Ydl {
@@ -24,6 +85,10 @@ pub struct Ydl {
     options: YdlOptions,
     extractor: Arc<SubtitleExtractor>,
     processor: ContentProcessor,
+    /// Every discovered track, before preference filtering, once discovery succeeded
+    tracks: Mutex<Option<Vec<SubtitleTrack>>>,
+    /// Watch-page metadata (without the subtitle list), once fetched
+    details: Arc<Mutex<Option<VideoMetadata>>>,
 }
 
 impl Ydl {
@@ -36,8 +101,9 @@ impl Ydl {
 
         debug!("Extracted video ID: {}", video_id);
 
-        let extractor = Arc::new(SubtitleExtractor::new(options.clone())?);
-        let processor = ContentProcessor::new();
+        let extractor =
+            Arc::new(SubtitleExtractor::new(options.clone())?.for_short(parser.is_shorts_url(url)));
+        let processor = ContentProcessor::with_options(options.clone()).with_video_id(&video_id);
 
         Ok(Self {
             url: url.to_string(),
@@ -45,15 +111,65 @@ impl Ydl {
             options,
             extractor,
             processor,
+            tracks: Mutex::new(None),
+            details: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Forget the tracks and metadata memoized by this instance, so the next call asks
+    /// YouTube again
+    pub fn clear_cache(&self) {
+        *self.tracks.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.details.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Every track of the video, discovered once per instance
+    ///
+    /// Concurrent first calls may each run discovery; the last result is kept.
+    async fn all_tracks(&self) -> YdlResult<Vec<SubtitleTrack>> {
+        if let Some(tracks) = self
+            .tracks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        {
+            debug!("Using memoized subtitle tracks for {}", self.video_id);
+            return Ok(tracks);
+        }
+
+        let tracks = self.extractor.discover_all_tracks(&self.video_id).await?;
+        *self.tracks.lock().unwrap_or_else(|e| e.into_inner()) = Some(tracks.clone());
+        Ok(tracks)
+    }
+
+    /// The video's tracks, filtered by the configured preferences
+    async fn tracks(&self) -> YdlResult<Vec<SubtitleTrack>> {
+        let tracks = self.all_tracks().await?;
+        self.extractor.filter_tracks(tracks, &self.video_id)
+    }
+
     /// Download subtitles in the specified format
     pub async fn subtitle(&self, subtitle_type: SubtitleType) -> YdlResult<String> {
+        self.subtitle_detailed(subtitle_type)
+            .await
+            .map(|result| result.content)
+    }
+
+    /// Download subtitles in the specified format, along with the track that was used
+    ///
+    /// The returned language and track type reflect the track actually selected after
+    /// any language or manual/auto-generated fallback.
+    pub async fn subtitle_detailed(
+        &self,
+        subtitle_type: SubtitleType,
+    ) -> YdlResult<SubtitleResult> {
         info!("Downloading subtitle in format: {:?}", subtitle_type);
 
         // Discover available subtitle tracks
-        let tracks = self.extractor.discover_tracks(&self.video_id).await?;
+        let started = Instant::now();
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
+        let discovery = started.elapsed();
 
         if tracks.is_empty() {
             return Err(YdlError::NoSubtitlesAvailable {
@@ -62,7 +178,7 @@ impl Ydl {
         }
 
         // Select the best track based on options
-        let selected_track = self.extractor.select_best_track(&tracks).ok_or_else(|| {
+        let selected_track = self.extractor.select_best_track(tracks).ok_or_else(|| {
             YdlError::NoSubtitlesAvailable {
                 video_id: self.video_id.clone(),
             }
@@ -74,21 +190,254 @@ impl Ydl {
         );
 
         // Download the subtitle content
+        let started = Instant::now();
+        let (raw_content, selected_track) =
+            self.download_selected(&candidates, selected_track).await?;
+        let download = started.elapsed();
+
+        // Process and convert the content
+        let started = Instant::now();
+        let result = self
+            .process_track_content(&raw_content, subtitle_type, selected_track)
+            .await;
+        debug!(
+            discovery = ?discovery,
+            download = ?download,
+            processing = ?started.elapsed(),
+            "Subtitle pipeline timing"
+        );
+        result
+    }
+
+    /// Download the track with the given vssId (as shown by `available_subtitles`)
+    ///
+    /// Unlike `subtitle`, language and manual/auto preferences are ignored, so this
+    /// can pick e.g. the auto-generated `a.en` track even when a manual `.en` exists.
+    pub async fn subtitle_by_vss_id(
+        &self,
+        vss_id: &str,
+        subtitle_type: SubtitleType,
+    ) -> YdlResult<String> {
+        info!(
+            "Downloading subtitle track {} as {:?}",
+            vss_id, subtitle_type
+        );
+
+        let tracks = self.all_tracks().await?;
+        let track = self
+            .extractor
+            .select_by_vss_id(&tracks, vss_id)
+            .ok_or_else(|| YdlError::TrackNotAvailable {
+                vss_id: vss_id.to_string(),
+            })?;
+
+        Ok(self
+            .subtitle_with_track(track, subtitle_type)
+            .await?
+            .content)
+    }
+
+    /// Download `track` (one of `available_subtitles`) in `subtitle_type`
+    ///
+    /// Like `subtitle_by_vss_id`, language and manual/auto preferences are ignored.
+    pub async fn subtitle_with_track(
+        &self,
+        track: &SubtitleTrack,
+        subtitle_type: SubtitleType,
+    ) -> YdlResult<SubtitleResult> {
         let raw_content = self
             .extractor
-            .download_content(selected_track, &self.video_id)
+            .download_content(track, &self.video_id)
             .await?;
 
-        // Process and convert the content
-        let processed_content = self.processor.process_content(
-            &raw_content,
+        self.process_track_content(&raw_content, subtitle_type, track)
+            .await
+    }
+
+    /// Download every track in `language` (manual, auto-generated, community) in `subtitle_type`
+    ///
+    /// Each result carries the track type it came from; use
+    /// [`SubtitleResult::variant_file_name`] to keep their files apart.
+    pub async fn subtitle_variants(
+        &self,
+        language: &str,
+        subtitle_type: SubtitleType,
+    ) -> YdlResult<Vec<SubtitleResult>> {
+        info!(
+            "Downloading all {} subtitle variants as {:?}",
+            language, subtitle_type
+        );
+
+        let tracks = self.all_tracks().await?;
+        let variants = self.extractor.select_variants(&tracks, language);
+        if variants.is_empty() {
+            return Err(YdlError::LanguageNotAvailable {
+                language: language.to_string(),
+            });
+        }
+
+        let mut results = Vec::new();
+        for track in variants {
+            let raw_content = self
+                .extractor
+                .download_content(track, &self.video_id)
+                .await?;
+            results.push(
+                self.process_track_content(&raw_content, subtitle_type, track)
+                    .await?,
+            );
+        }
+
+        Ok(results)
+    }
+
+    /// Download the best subtitle track machine-translated into `target_lang`
+    ///
+    /// If the best track cannot be translated, the first translatable candidate is
+    /// used instead. The result's language is `target_lang`.
+    pub async fn translated_subtitle(
+        &self,
+        target_lang: &str,
+        subtitle_type: SubtitleType,
+    ) -> YdlResult<SubtitleResult> {
+        info!(
+            "Downloading subtitles translated into {} as {:?}",
+            target_lang, subtitle_type
+        );
+
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
+        let track = self
+            .extractor
+            .select_best_track(tracks)
+            .filter(|track| track.is_translatable)
+            .or_else(|| tracks.iter().find(|track| track.is_translatable))
+            .ok_or_else(|| YdlError::LanguageNotAvailable {
+                language: target_lang.to_string(),
+            })?;
+
+        let raw_content = self
+            .extractor
+            .download_translated(track, &self.video_id, target_lang)
+            .await?;
+        let mut result = self
+            .process_track_content(&raw_content, subtitle_type, track)
+            .await?;
+        result.language = target_lang.to_string();
+        Ok(result)
+    }
+
+    /// Download the best subtitle track exactly as YouTube serves it, without any processing
+    pub async fn raw_subtitle(&self) -> YdlResult<String> {
+        info!("Downloading unprocessed subtitle content");
+
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
+        let selected_track = self.extractor.select_best_track(tracks).ok_or_else(|| {
+            YdlError::NoSubtitlesAvailable {
+                video_id: self.video_id.clone(),
+            }
+        })?;
+
+        match candidates.downloaded(selected_track) {
+            Some(content) => Ok(content),
+            None => {
+                self.extractor
+                    .download_content(selected_track, &self.video_id)
+                    .await
+            }
+        }
+    }
+
+    /// Tracks to select from, scored first if `select_by_quality` is set
+    async fn candidate_tracks(&self) -> YdlResult<Candidates> {
+        let mut tracks = self.tracks().await?;
+        let downloaded = if self.options.select_by_quality {
+            self.extractor
+                .score_tracks(&mut tracks, &self.video_id)
+                .await
+        } else {
+            Vec::new()
+        };
+        Ok(Candidates { tracks, downloaded })
+    }
+
+    /// Download the selected track, checking its coverage if `min_caption_coverage` is set
+    ///
+    /// Content already downloaded to score the track is reused. Returns the track the
+    /// content actually came from.
+    async fn download_selected<'a>(
+        &self,
+        candidates: &'a Candidates,
+        selected: &'a SubtitleTrack,
+    ) -> YdlResult<(String, &'a SubtitleTrack)> {
+        let downloaded = candidates.downloaded(selected);
+        match self.options.min_caption_coverage {
+            Some(min_coverage) => {
+                self.extractor
+                    .download_with_coverage_check(
+                        &candidates.tracks,
+                        selected,
+                        &self.video_id,
+                        min_coverage,
+                        downloaded,
+                    )
+                    .await
+            }
+            None => match downloaded {
+                Some(content) => Ok((content, selected)),
+                None => Ok((
+                    self.extractor
+                        .download_checking_region(&candidates.tracks, selected, &self.video_id)
+                        .await?,
+                    selected,
+                )),
+            },
+        }
+    }
+
+    /// Convert downloaded content of `track` into a result for `subtitle_type`
+    async fn process_track_content(
+        &self,
+        raw_content: &str,
+        subtitle_type: SubtitleType,
+        track: &SubtitleTrack,
+    ) -> YdlResult<SubtitleResult> {
+        // LRC files carry the title and length as tags, when the metadata can be fetched
+        let lrc_processor = match subtitle_type {
+            SubtitleType::Lrc => self.details().await.ok().map(|details| {
+                self.processor
+                    .clone()
+                    .with_metadata(&details.title, details.duration)
+            }),
+            _ => None,
+        };
+        let processor = lrc_processor.as_ref().unwrap_or(&self.processor);
+
+        let content = processor.process_content(
+            raw_content,
             subtitle_type,
-            &selected_track.language_code,
+            &track.language_code,
             self.options.clean_content,
             self.options.validate_timing,
+            &track.track_type,
         )?;
 
-        Ok(processed_content)
+        Ok(SubtitleResult::new(
+            content,
+            subtitle_type,
+            track.language_code.clone(),
+            track.track_type.clone(),
+        ))
+    }
+
+    /// Query each InnerTube client independently, reporting its track count or error
+    ///
+    /// A troubleshooting aid for "no subtitles" reports: it shows which clients
+    /// YouTube answers and what each one sees.
+    pub async fn probe_all_clients(&self) -> Vec<(ClientType, YdlResult<usize>)> {
+        info!("Probing every InnerTube client");
+        self.extractor.probe_all_clients(&self.video_id).await
     }
 
     /// Download subtitles in the specified format (async variant)
@@ -99,7 +448,7 @@ impl Ydl {
     /// List all available subtitle tracks for the video
     pub async fn available_subtitles(&self) -> YdlResult<Vec<SubtitleTrack>> {
         info!("Discovering available subtitle tracks");
-        self.extractor.discover_tracks(&self.video_id).await
+        self.tracks().await
     }
 
     /// Download multiple subtitle formats at once
@@ -107,7 +456,8 @@ impl Ydl {
         info!("Downloading multiple subtitle formats: {:?}", types);
 
         // Discover tracks once
-        let tracks = self.extractor.discover_tracks(&self.video_id).await?;
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
 
         if tracks.is_empty() {
             return Err(YdlError::NoSubtitlesAvailable {
@@ -115,37 +465,25 @@ impl Ydl {
             });
         }
 
-        let selected_track = self.extractor.select_best_track(&tracks).ok_or_else(|| {
+        let selected_track = self.extractor.select_best_track(tracks).ok_or_else(|| {
             YdlError::NoSubtitlesAvailable {
                 video_id: self.video_id.clone(),
             }
         })?;
 
         // Download content once
-        let raw_content = self
-            .extractor
-            .download_content(selected_track, &self.video_id)
-            .await?;
+        let (raw_content, selected_track) =
+            self.download_selected(&candidates, selected_track).await?;
 
         // Process for each requested format
         let mut results = Vec::new();
 
         for &subtitle_type in types {
-            match self.processor.process_content(
-                &raw_content,
-                subtitle_type,
-                &selected_track.language_code,
-                self.options.clean_content,
-                self.options.validate_timing,
-            ) {
-                Ok(content) => {
-                    results.push(SubtitleResult::new(
-                        content,
-                        subtitle_type,
-                        selected_track.language_code.clone(),
-                        selected_track.track_type.clone(),
-                    ));
-                }
+            match self
+                .process_track_content(&raw_content, subtitle_type, selected_track)
+                .await
+            {
+                Ok(result) => results.push(result),
                 Err(e) => {
                     error!("Failed to process format {:?}: {}", subtitle_type, e);
                     return Err(e);
@@ -156,10 +494,91 @@ impl Ydl {
         Ok(results)
     }
 
+    /// Download the best subtitle track and return its parsed entries
+    pub async fn parsed_subtitles(&self) -> YdlResult<ParsedSubtitles> {
+        info!("Downloading parsed subtitles");
+
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
+        let selected_track = self.extractor.select_best_track(tracks).ok_or_else(|| {
+            YdlError::NoSubtitlesAvailable {
+                video_id: self.video_id.clone(),
+            }
+        })?;
+
+        let (raw_content, selected_track) =
+            self.download_selected(&candidates, selected_track).await?;
+
+        self.processor
+            .parse_content(&raw_content, &selected_track.language_code)
+    }
+
+    /// Download and parse the best subtitle track once, for rendering in several formats
+    ///
+    /// Unlike [`Self::subtitles`], the formats need not be known up front: every
+    /// [`CachedSubtitles::to`] call renders the cached cues without downloading them
+    /// again. Only LRC output fetches the video's metadata, once, for its tags.
+    pub async fn fetch(&self) -> YdlResult<CachedSubtitles> {
+        info!("Fetching subtitles for repeated conversion");
+
+        let candidates = self.candidate_tracks().await?;
+        let tracks = &candidates.tracks;
+        let selected_track = self.extractor.select_best_track(tracks).ok_or_else(|| {
+            YdlError::NoSubtitlesAvailable {
+                video_id: self.video_id.clone(),
+            }
+        })?;
+
+        let (raw_content, selected_track) =
+            self.download_selected(&candidates, selected_track).await?;
+
+        let processor =
+            ContentProcessor::with_options(self.options.clone()).with_video_id(&self.video_id);
+        CachedSubtitles::new(
+            processor,
+            &raw_content,
+            selected_track.clone(),
+            self.lazy_details(),
+        )
+    }
+
+    /// A handle to this instance's memoized metadata that can outlive the borrow of `self`
+    fn lazy_details(&self) -> LazyDetails {
+        LazyDetails {
+            extractor: self.extractor.clone(),
+            video_id: self.video_id.clone(),
+            details: self.details.clone(),
+        }
+    }
+
     /// Get video metadata without downloading subtitles
+    ///
+    /// The result is memoized for the lifetime of the instance, along with the tracks
+    /// it lists, so a following download makes no further discovery requests.
     pub async fn metadata(&self) -> YdlResult<VideoMetadata> {
         info!("Getting video metadata");
-        self.extractor.get_video_metadata(&self.video_id).await
+        Ok(self.details().await?.with_subtitles(self.tracks().await?))
+    }
+
+    /// Watch-page metadata without the subtitle list, memoized
+    async fn details(&self) -> YdlResult<VideoMetadata> {
+        self.lazy_details().get().await
+    }
+
+    /// Render the video's chapter list as subtitles, one cue per chapter title
+    pub async fn chapters_as_subtitles(&self, subtitle_type: SubtitleType) -> YdlResult<String> {
+        info!("Converting chapters to format: {:?}", subtitle_type);
+
+        let metadata = self.metadata().await?;
+        if metadata.chapters.is_empty() {
+            return Err(YdlError::NoChaptersAvailable {
+                video_id: self.video_id.clone(),
+            });
+        }
+
+        let language = self.options.language.as_deref().unwrap_or("en");
+        self.processor
+            .convert_to_format(&metadata.chapter_entries(), subtitle_type, language)
     }
 
     /// Get the video ID for this instance
@@ -167,6 +586,30 @@ impl Ydl {
         &self.video_id
     }
 
+    /// Create one instance per video of a playlist, in playlist order
+    ///
+    /// `url` is a playlist URL, or a watch URL with a `list=` parameter; the whole
+    /// playlist is resolved through InnerTube browse, following continuations.
+    pub async fn from_playlist(url: &str, options: YdlOptions) -> YdlResult<Vec<Self>> {
+        let playlist_id = YouTubeParser::new().parse_playlist_url(url)?;
+        let video_ids = SubtitleExtractor::new(options.clone())?
+            .browse_playlist_video_ids(&playlist_id)
+            .await?;
+
+        video_ids
+            .iter()
+            .map(|video_id| {
+                let url = format!("https://www.youtube.com/watch?v={}", video_id);
+                Self::new(&url, options.clone())
+            })
+            .collect()
+    }
+
+    /// Where a file named `file_name` is written, inside the configured `output_dir`
+    pub fn output_path(&self, file_name: &str) -> std::path::PathBuf {
+        output::output_path(self.options.output_dir.as_deref(), file_name)
+    }
+
     /// Get the original URL for this instance
     pub fn url(&self) -> &str {
         &self.url
@@ -179,42 +622,43 @@ impl Ydl {
 
     /// Check if subtitles are likely available (quick check)
     pub async fn has_subtitles(&self) -> bool {
-        match self.extractor.discover_tracks(&self.video_id).await {
+        match self.tracks().await {
             Ok(tracks) => !tracks.is_empty(),
             Err(_) => false,
         }
     }
 
-    /// Download subtitle with retry logic
-    pub async fn subtitle_with_retry(&self, subtitle_type: SubtitleType) -> YdlResult<String> {
-        let mut retries = 0;
-        let max_retries = self.options.max_retries;
-
-        loop {
-            match self.subtitle(subtitle_type).await {
-                Ok(content) => return Ok(content),
-                Err(e) => {
-                    if retries >= max_retries {
-                        return Err(e);
-                    }
-
-                    if e.is_retryable() {
-                        retries += 1;
-                        let delay = e.retry_delay().unwrap_or(1);
+    /// Check if the video has a track in `lang`, matched case-insensitively against its language code
+    ///
+    /// Ignores the configured language and type preferences and returns `false` if
+    /// discovery fails.
+    pub async fn has_language(&self, lang: &str) -> bool {
+        match self.all_tracks().await {
+            Ok(tracks) => tracks
+                .iter()
+                .any(|track| track.language_code.eq_ignore_ascii_case(lang)),
+            Err(_) => false,
+        }
+    }
 
-                        debug!(
-                            "Retrying in {}s (attempt {} of {})",
-                            delay, retries, max_retries
-                        );
-                        tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
-                    } else {
-                        return Err(e);
-                    }
-                }
-            }
+    /// Check if the video has a manually created (not auto-generated) track in any language
+    pub async fn has_manual_subtitles(&self) -> bool {
+        match self.all_tracks().await {
+            Ok(tracks) => tracks
+                .iter()
+                .any(|track| track.track_type == SubtitleTrackType::Manual),
+            Err(_) => false,
         }
     }
 
+    /// Download subtitle with retry logic
+    pub async fn subtitle_with_retry(&self, subtitle_type: SubtitleType) -> YdlResult<String> {
+        retry(self.options.max_retries, &*self.options.metrics, || {
+            self.subtitle(subtitle_type)
+        })
+        .await
+    }
+
     /// Future extension for method chaining
     pub fn with_language(mut self, lang: &str) -> Self {
         self.options.language = Some(lang.to_string());
@@ -228,8 +672,175 @@ impl Ydl {
     }
 }
 
+/// Run `operation`, retrying retryable errors up to `max_retries` times
+///
+/// Waits for the error's suggested delay between attempts, but never less than
+/// [`MIN_RETRY_DELAY`], so errors suggesting no delay cannot cause a tight loop.
+async fn retry<T, F, Fut>(max_retries: u32, metrics: &dyn Metrics, mut operation: F) -> YdlResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = YdlResult<T>>,
+{
+    let mut retries = 0;
+
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if retries < max_retries && e.is_retryable() => {
+                retries += 1;
+                metrics.retry(retries, &e);
+                let delay = Duration::from_secs(e.retry_delay().unwrap_or(1)).max(MIN_RETRY_DELAY);
+
+                debug!(
+                    "Retrying in {:?} (attempt {} of {})",
+                    delay, retries, max_retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 // Convenience functions for one-off operations
 
+/// Pick the most complete captions among several uploads of the same content
+///
+/// Downloads each video's captions and returns the ID and captions with the greatest
+/// coverage. Videos whose captions cannot be fetched are skipped. Use
+/// [`merge::fill_gaps`] to patch remaining gaps from the other uploads.
+pub async fn pick_best_captions(
+    ids: &[&str],
+    options: YdlOptions,
+) -> YdlResult<(String, ParsedSubtitles)> {
+    let mut best: Option<(String, ParsedSubtitles)> = None;
+    let mut last_error = None;
+
+    for id in ids {
+        let url = format!("https://www.youtube.com/watch?v={}", id);
+        let result = match Ydl::new(&url, options.clone()) {
+            Ok(downloader) => downloader.parsed_subtitles().await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(subtitles) => {
+                let is_better = best
+                    .as_ref()
+                    .is_none_or(|(_, b)| merge::coverage(&subtitles) > merge::coverage(b));
+                if is_better {
+                    best = Some((id.to_string(), subtitles));
+                }
+            }
+            Err(e) => {
+                warn!("Skipping captions for {}: {}", id, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    best.ok_or_else(|| {
+        last_error.unwrap_or_else(|| YdlError::Configuration {
+            message: "No video IDs given".to_string(),
+        })
+    })
+}
+
+/// Stream subtitles for each video of a playlist, downloading as the consumer pulls
+///
+/// `playlist` is a playlist URL. Nothing is fetched until the stream is first polled,
+/// and at most `options.concurrency` videos are downloaded at once. Items arrive in
+/// playlist order; a video whose captions fail yields an `Err` without ending the stream.
+pub fn playlist_subtitles(
+    playlist: &str,
+    subtitle_type: SubtitleType,
+    options: YdlOptions,
+) -> YdlResult<impl Stream<Item = YdlResult<(String, SubtitleResult)>>> {
+    let playlist_id = match classify_url(playlist)? {
+        YouTubeRef::Playlist(playlist) => playlist.id,
+        _ => {
+            return Err(YdlError::InvalidUrl {
+                url: playlist.to_string(),
+            });
+        }
+    };
+
+    let extractor = SubtitleExtractor::new(options.clone())?;
+    let video_ids = playlist_video_id_stream(extractor, playlist_id);
+
+    let concurrency = options.concurrency;
+    Ok(subtitle_stream(video_ids, concurrency, move |video_id| {
+        let options = options.clone();
+        async move {
+            let url = format!("https://www.youtube.com/watch?v={}", video_id);
+            Ydl::new(&url, options)?
+                .subtitle_detailed(subtitle_type)
+                .await
+        }
+    }))
+}
+
+/// Video IDs of a playlist, in playlist order
+///
+/// Use [`PlaylistPosition::remaining`] to start from the entry a URL points at.
+pub async fn playlist_video_ids(
+    playlist: &PlaylistRef,
+    options: YdlOptions,
+) -> YdlResult<Vec<String>> {
+    SubtitleExtractor::new(options)?
+        .browse_playlist_video_ids(&playlist.id)
+        .await
+}
+
+/// Video IDs of a channel's uploads, newest first, up to `limit` if given
+pub async fn channel_uploads(
+    channel: &ChannelRef,
+    limit: Option<usize>,
+    options: YdlOptions,
+) -> YdlResult<Vec<String>> {
+    SubtitleExtractor::new(options)?
+        .list_channel_uploads(channel, limit)
+        .await
+}
+
+/// Lazily enumerate a playlist, yielding its video IDs (or the enumeration error)
+fn playlist_video_id_stream(
+    extractor: SubtitleExtractor,
+    playlist_id: String,
+) -> impl Stream<Item = YdlResult<String>> {
+    stream::once(async move { extractor.browse_playlist_video_ids(&playlist_id).await }).flat_map(
+        |result| {
+            let items: Vec<YdlResult<String>> = match result {
+                Ok(ids) => ids.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(items)
+        },
+    )
+}
+
+/// Run `download` for each video ID, keeping at most `concurrency` downloads in flight
+fn subtitle_stream<S, F, Fut>(
+    video_ids: S,
+    concurrency: usize,
+    mut download: F,
+) -> impl Stream<Item = YdlResult<(String, SubtitleResult)>>
+where
+    S: Stream<Item = YdlResult<String>>,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = YdlResult<SubtitleResult>>,
+{
+    video_ids
+        .map(move |video_id| match video_id {
+            Ok(video_id) => {
+                let download = download(video_id.clone());
+                Either::Left(async move { download.await.map(|result| (video_id, result)) })
+            }
+            Err(e) => Either::Right(future::ready(Err(e))),
+        })
+        .buffered(concurrency.max(1))
+}
+
 /// Quick function to download a subtitle
 pub async fn download_subtitle(url: &str, format: SubtitleType) -> YdlResult<String> {
     let downloader = Ydl::new(url, YdlOptions::default())?;
@@ -251,6 +862,152 @@ pub async fn get_metadata(url: &str) -> YdlResult<VideoMetadata> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::tests::{MockResponse, test_server};
+    use std::net::SocketAddr;
+
+    /// `Ydl` for dQw4w9WgXcQ that sends every request to the mock server at `addr`
+    fn mock_ydl(addr: SocketAddr) -> Ydl {
+        let options = YdlOptions::new().timeout(10);
+        let mut ydl = Ydl::new(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            options.clone(),
+        )
+        .unwrap();
+        ydl.extractor = Arc::new(
+            SubtitleExtractor::new(options)
+                .unwrap()
+                .with_web_base(&format!("http://{}", addr))
+                .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr)),
+        );
+        ydl
+    }
+
+    /// Player response listing one English track, served by the mock at `addr`
+    fn english_track(addr: SocketAddr) -> String {
+        format!(
+            r#"{{"captions":{{"playerCaptionsTracklistRenderer":{{"captionTracks":[{{"baseUrl":"http://{}/api/timedtext?v=dQw4w9WgXcQ&lang=en","name":{{"simpleText":"English"}},"vssId":".en","languageCode":"en"}}]}}}}}}"#,
+            addr
+        )
+    }
+
+    #[tokio::test]
+    async fn test_quality_selection_downloads_each_track_once() {
+        let downloads = Arc::new(Mutex::new(Vec::new()));
+        let seen = downloads.clone();
+        let addr = test_server(move |request| {
+            if request.starts_with("POST") {
+                return MockResponse::ok(r#"{"videoDetails":{"lengthSeconds":"10"}}"#);
+            }
+            let target = request.split_whitespace().nth(1).unwrap_or("");
+            let path = target.split('?').next().unwrap_or("").to_string();
+            seen.lock().unwrap().push(path);
+            MockResponse::ok(r#"<transcript><text start="1" dur="8">Hello</text></transcript>"#)
+        })
+        .await;
+
+        let options = YdlOptions::new()
+            .timeout(10)
+            .prefer_manual(false)
+            .select_by_quality(true);
+        let mut ydl = Ydl::new(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            options.clone(),
+        )
+        .unwrap();
+        ydl.extractor = Arc::new(
+            SubtitleExtractor::new(options)
+                .unwrap()
+                .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr)),
+        );
+        *ydl.tracks.lock().unwrap() = Some(vec![
+            SubtitleTrack::new("en".into(), "English".into(), SubtitleTrackType::Manual)
+                .with_url(format!("http://{}/manual", addr)),
+            SubtitleTrack::new(
+                "en".into(),
+                "English".into(),
+                SubtitleTrackType::AutoGenerated,
+            )
+            .with_url(format!("http://{}/auto", addr)),
+        ]);
+
+        let result = ydl.subtitle_detailed(SubtitleType::Txt).await.unwrap();
+        assert_eq!(result.content.trim(), "Hello");
+
+        // Scoring downloads both tracks; the winner's content is then reused
+        let mut downloads = downloads.lock().unwrap().clone();
+        downloads.sort();
+        assert_eq!(downloads, vec!["/auto", "/manual"]);
+    }
+
+    #[tokio::test]
+    async fn test_has_language_and_manual_subtitles_use_memoized_tracks() {
+        let ydl = Ydl::new(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            YdlOptions::new().language("fr"),
+        )
+        .unwrap();
+        *ydl.tracks.lock().unwrap() = Some(vec![SubtitleTrack::new(
+            "en-US".to_string(),
+            "English (United States)".to_string(),
+            SubtitleTrackType::AutoGenerated,
+        )]);
+
+        // Language preferences do not hide other tracks
+        assert!(ydl.has_language("en-us").await);
+        assert!(!ydl.has_language("en").await);
+        assert!(!ydl.has_manual_subtitles().await);
+
+        ydl.tracks
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .push(SubtitleTrack::new(
+                "de".to_string(),
+                "German".to_string(),
+                SubtitleTrackType::Manual,
+            ));
+        assert!(ydl.has_language("de").await);
+        assert!(ydl.has_manual_subtitles().await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_enforces_minimum_delay() {
+        let metrics = metrics::tests::RecordingMetrics::default();
+        let attempts = Mutex::new(Vec::new());
+
+        let result: YdlResult<()> = retry(2, &metrics, || {
+            attempts.lock().unwrap().push(tokio::time::Instant::now());
+            async { Err(YdlError::RateLimited { retry_after: 0 }) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(YdlError::RateLimited { .. })));
+        let attempts = attempts.into_inner().unwrap();
+        assert_eq!(attempts.len(), 3);
+        // The paused clock advances exactly by each sleep
+        for pair in attempts.windows(2) {
+            assert_eq!(pair[1] - pair[0], MIN_RETRY_DELAY);
+        }
+        assert_eq!(metrics.events(), vec!["retry 1", "retry 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_from_playlist_rejects_non_playlist_urls() {
+        let result = Ydl::from_playlist(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            YdlOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(YdlError::InvalidUrl { .. })));
+
+        let result = Ydl::from_playlist(
+            "https://www.youtube.com/playlist?list=XX1234567890",
+            YdlOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(YdlError::InvalidPlaylistId { .. })));
+    }
 
     #[tokio::test]
     async fn test_ydl_creation() {
@@ -263,6 +1020,320 @@ mod tests {
         assert_eq!(ydl.url(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
     }
 
+    /// Download SRT from a mock where four tracks are discovered but every caption
+    /// download except that of `working` is empty, and the player reports `status`
+    ///
+    /// Returns the error along with the number of caption requests made.
+    async fn download_with_empty_tracks(
+        status: &'static str,
+        working: Option<&'static str>,
+    ) -> (YdlError, usize) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let caption_requests = Arc::new(AtomicUsize::new(0));
+
+        let captions = caption_requests.clone();
+        let addr = test_server(move |request| {
+            if request.starts_with("GET /api/timedtext") {
+                captions.fetch_add(1, Ordering::SeqCst);
+                if working.is_some_and(|lang| request.contains(&format!("lang={lang}"))) {
+                    return MockResponse::ok(
+                        "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHola\n",
+                    );
+                }
+                return MockResponse::ok("");
+            }
+            let addr = request.server();
+            let tracks = ["en", "fr", "de", "es"]
+                .iter()
+                .map(|lang| {
+                    format!(
+                        r#"{{"baseUrl":"http://{addr}/api/timedtext?v=dQw4w9WgXcQ&lang={lang}","name":{{"simpleText":"{lang}"}},"vssId":".{lang}","languageCode":"{lang}"}}"#
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            MockResponse::ok(format!(
+                r#"{{"playabilityStatus":{{"status":"{status}"}},"captions":{{"playerCaptionsTracklistRenderer":{{"captionTracks":[{tracks}]}}}}}}"#
+            ))
+        })
+        .await;
+
+        let ydl = mock_ydl(addr);
+
+        let err = ydl.subtitle(SubtitleType::Srt).await.unwrap_err();
+        (err, caption_requests.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_all_empty_downloads_report_region_restricted_captions() {
+        let (err, caption_requests) = download_with_empty_tracks("OK", None).await;
+        assert!(
+            matches!(&err, YdlError::CaptionsUnavailableInRegion { video_id } if video_id == "dQw4w9WgXcQ"),
+            "{err:?}"
+        );
+        assert!(err.is_subtitle_unavailable());
+        // All four tracks were tried, each through the InnerTube, direct and fallback URLs
+        assert_eq!(caption_requests, 12);
+
+        // One working track, even the last, means only the selected one is broken
+        let (err, _) = download_with_empty_tracks("OK", Some("es")).await;
+        assert!(matches!(&err, YdlError::SubtitleParsing { .. }), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_empty_downloads_of_unplayable_video_report_restriction() {
+        let (err, _) = download_with_empty_tracks("UNPLAYABLE", None).await;
+        assert!(
+            matches!(&err, YdlError::VideoRestricted { video_id } if video_id == "dQw4w9WgXcQ"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracks_and_metadata_are_memoized() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let player_requests = Arc::new(AtomicUsize::new(0));
+        let page_requests = Arc::new(AtomicUsize::new(0));
+
+        let (players, pages) = (player_requests.clone(), page_requests.clone());
+        let addr = test_server(move |request| {
+            if request.starts_with("GET /watch") {
+                pages.fetch_add(1, Ordering::SeqCst);
+                MockResponse::ok(
+                    r#"<html><title>Memo - YouTube</title><script>var ytInitialPlayerResponse = {"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Memo","lengthSeconds":"60"}};</script></html>"#,
+                )
+            } else {
+                players.fetch_add(1, Ordering::SeqCst);
+                MockResponse::ok(
+                    r#"{"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en","name":{"simpleText":"English"},"vssId":".en","languageCode":"en"}]}}}"#,
+                )
+            }
+        })
+        .await;
+
+        let ydl = mock_ydl(addr);
+
+        let metadata = ydl.metadata().await.unwrap();
+        assert_eq!(metadata.title, "Memo");
+        assert_eq!(metadata.available_subtitles.len(), 1);
+        let discovery_requests = player_requests.load(Ordering::SeqCst);
+        assert!(discovery_requests > 0);
+
+        // Naming the file and then downloading reuses everything fetched so far
+        ydl.metadata().await.unwrap();
+        assert_eq!(ydl.available_subtitles().await.unwrap().len(), 1);
+        assert!(ydl.has_subtitles().await);
+        assert_eq!(player_requests.load(Ordering::SeqCst), discovery_requests);
+        assert_eq!(page_requests.load(Ordering::SeqCst), 1);
+
+        ydl.clear_cache();
+        ydl.metadata().await.unwrap();
+        assert_eq!(
+            player_requests.load(Ordering::SeqCst),
+            discovery_requests * 2
+        );
+        assert_eq!(page_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_subtitle_logs_stage_timings() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (content, logs) = processor::tests::capture_logs_at(tracing::Level::DEBUG, || {
+            runtime.block_on(async {
+                let addr = test_server(|request| {
+                    if request.starts_with("GET /api/timedtext") {
+                        MockResponse::ok("WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nTimed\n")
+                    } else {
+                        MockResponse::ok(english_track(request.server()))
+                    }
+                })
+                .await;
+
+                let ydl = mock_ydl(addr);
+                ydl.subtitle(SubtitleType::Srt).await
+            })
+        });
+
+        assert!(content.unwrap().contains("Timed"));
+        let timing = logs
+            .lines()
+            .find(|line| line.contains("Subtitle pipeline timing"))
+            .unwrap_or_else(|| panic!("no timing log in: {logs}"));
+        assert!(timing.contains("DEBUG"), "{timing}");
+        for field in ["discovery=", "download=", "processing="] {
+            assert!(timing.contains(field), "{field} missing from: {timing}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_converts_to_several_formats_without_requests() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        let addr = test_server(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if request.starts_with("GET /api/timedtext") {
+                MockResponse::ok(
+                    r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3"><body><p t="1000" d="2000">Hello there</p><p t="4000" d="1500">General Kenobi</p></body></timedtext>"#,
+                )
+            } else if request.starts_with("GET /watch") {
+                MockResponse::ok(
+                    r#"<html><title>Fetch - YouTube</title><script>var ytInitialPlayerResponse = {"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Fetch","lengthSeconds":"60"}};</script></html>"#,
+                )
+            } else {
+                MockResponse::ok(english_track(request.server()))
+            }
+        })
+        .await;
+
+        let ydl = mock_ydl(addr);
+
+        let cached = ydl.fetch().await.unwrap();
+        let requests_made = requests.load(Ordering::SeqCst);
+        assert_eq!(cached.track().language_code, "en");
+        assert_eq!(cached.parsed().entries.len(), 2);
+
+        let srt = cached.to(SubtitleType::Srt).await.unwrap();
+        assert!(srt.starts_with("1\n00:00:01,000 --> 00:00:03,000\nHello there"));
+        let vtt = cached.to(SubtitleType::Vtt).await.unwrap();
+        assert!(vtt.starts_with("WEBVTT"));
+        assert!(vtt.contains("00:00:04.000 --> 00:00:05.500\nGeneral Kenobi"));
+        let json: serde_json::Value =
+            serde_json::from_str(&cached.to(SubtitleType::Json).await.unwrap()).unwrap();
+        assert_eq!(json["entries"].as_array().unwrap().len(), 2);
+        assert_eq!(requests.load(Ordering::SeqCst), requests_made);
+
+        // LRC tags need the metadata, which is fetched once and then shared
+        let lrc = cached.to(SubtitleType::Lrc).await.unwrap();
+        assert!(lrc.starts_with("[ti:Fetch]"));
+        let requests_made = requests.load(Ordering::SeqCst);
+        cached.to(SubtitleType::Lrc).await.unwrap();
+        ydl.metadata().await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), requests_made);
+    }
+
+    #[tokio::test]
+    async fn test_process_track_content_reports_selected_track() {
+        let ydl = Ydl::new(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            YdlOptions::default(),
+        )
+        .unwrap();
+        let track = SubtitleTrack::new(
+            "es".to_string(),
+            "Spanish".to_string(),
+            SubtitleTrackType::AutoGenerated,
+        );
+        let raw = "1\n00:00:01,000 --> 00:00:03,000\nHola\n";
+
+        let result = ydl
+            .process_track_content(raw, SubtitleType::Txt, &track)
+            .await
+            .unwrap();
+        assert_eq!(result.language, track.language_code);
+        assert_eq!(result.track_type, SubtitleTrackType::AutoGenerated);
+        assert_eq!(result.format, SubtitleType::Txt);
+        assert_eq!(result.content.trim(), "Hola");
+
+        // LRC tags come from the memoized metadata, without a request
+        *ydl.details.lock().unwrap() = Some(
+            VideoMetadata::new("dQw4w9WgXcQ".to_string(), "Canción".to_string())
+                .with_duration(Duration::from_secs(200)),
+        );
+        let result = ydl
+            .process_track_content(raw, SubtitleType::Lrc, &track)
+            .await
+            .unwrap();
+        assert!(
+            result.content.starts_with("[ti:Canción]\n[length:03:20]\n"),
+            "{}",
+            result.content
+        );
+    }
+
+    #[tokio::test]
+    async fn test_playlist_stream_downloads_lazily() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let page_requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = page_requests.clone();
+        let addr = test_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            MockResponse::ok(
+                r#"{"contents":[
+                {"playlistVideoRenderer":{"videoId":"aaaaaaaaaaa","index":1}},
+                {"playlistVideoRenderer":{"videoId":"bbbbbbbbbbb","index":2}},
+                {"playlistVideoRenderer":{"videoId":"ccccccccccc","index":3}},
+                {"playlistVideoRenderer":{"videoId":"aaaaaaaaaaa","index":4}}]}"#,
+            )
+        })
+        .await;
+
+        let extractor = SubtitleExtractor::new(YdlOptions::new().timeout(10))
+            .unwrap()
+            .with_browse_endpoint(&format!("http://{}/youtubei/v1/browse", addr));
+        let ids = playlist_video_id_stream(extractor, "PLtest".to_string());
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let (current, max) = (in_flight.clone(), max_in_flight.clone());
+        let stream = subtitle_stream(ids, 2, move |video_id| {
+            let (current, max) = (current.clone(), max.clone());
+            async move {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                current.fetch_sub(1, Ordering::SeqCst);
+
+                if video_id == "bbbbbbbbbbb" {
+                    return Err(YdlError::NoSubtitlesAvailable { video_id });
+                }
+                Ok(SubtitleResult::new(
+                    format!("captions of {}", video_id),
+                    SubtitleType::Txt,
+                    "en".to_string(),
+                    SubtitleTrackType::Manual,
+                ))
+            }
+        });
+
+        // Building the stream does not touch the network
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(page_requests.load(Ordering::SeqCst), 0);
+
+        let mut stream = std::pin::pin!(stream);
+        let mut seen = Vec::new();
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok((video_id, result)) => {
+                    assert_eq!(result.content, format!("captions of {}", video_id));
+                    seen.push(video_id);
+                }
+                Err(e) => seen.push(format!("error: {}", e)),
+            }
+        }
+
+        assert_eq!(
+            seen,
+            vec![
+                "aaaaaaaaaaa".to_string(),
+                "error: No subtitles available for video: bbbbbbbbbbb".to_string(),
+                "ccccccccccc".to_string(),
+            ]
+        );
+        assert_eq!(page_requests.load(Ordering::SeqCst), 1);
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+
     #[test]
     fn test_ydl_invalid_url() {
         let options = YdlOptions::default();
@@ -0,0 +1,187 @@
+// Comparing and merging caption sets of the same content (e.g. re-uploads)
+use crate::types::{ParsedSubtitles, SubtitleEntry};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Minimum silence in a caption set that is treated as a gap worth filling
+pub const DEFAULT_MIN_GAP: Duration = Duration::from_secs(5);
+
+/// Total screen time covered by captions, with overlapping cues counted once
+pub fn coverage(subtitles: &ParsedSubtitles) -> Duration {
+    let mut entries: Vec<&SubtitleEntry> = subtitles.entries.iter().collect();
+    entries.sort_by_key(|e| e.start);
+
+    let mut total = Duration::ZERO;
+    let mut covered_until = Duration::ZERO;
+    for entry in entries {
+        let start = entry.start.max(covered_until);
+        if entry.end > start {
+            total += entry.end - start;
+            covered_until = entry.end;
+        }
+    }
+    total
+}
+
+/// Estimate how much later (in milliseconds) content appears in `other` than in `base`
+///
+/// Cues are aligned by their normalized text; the median start-time difference of
+/// matching cues is used so that a few accidental matches do not skew the result.
+/// Returns `None` when the two sets share no text.
+pub fn estimate_offset(base: &ParsedSubtitles, other: &ParsedSubtitles) -> Option<i64> {
+    let mut base_starts: HashMap<String, Duration> = HashMap::new();
+    for entry in &base.entries {
+        base_starts
+            .entry(normalize_text(&entry.text))
+            .or_insert(entry.start);
+    }
+
+    let mut offsets: Vec<i64> = other
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let base_start = base_starts.get(&normalize_text(&entry.text))?;
+            Some(entry.start.as_millis() as i64 - base_start.as_millis() as i64)
+        })
+        .collect();
+
+    if offsets.is_empty() {
+        return None;
+    }
+    offsets.sort_unstable();
+    Some(offsets[offsets.len() / 2])
+}
+
+/// Fill gaps in `base` with cues from `donor`, shifted onto `base`'s timeline
+///
+/// Only gaps of at least `min_gap` (including before the first and after the last
+/// cue of `base`) are filled, and only with donor cues that fit entirely inside one.
+/// If the two sets cannot be aligned, `base` is returned unchanged.
+pub fn fill_gaps(
+    base: &ParsedSubtitles,
+    donor: &ParsedSubtitles,
+    min_gap: Duration,
+) -> ParsedSubtitles {
+    let Some(offset) = estimate_offset(base, donor) else {
+        return base.clone();
+    };
+
+    let mut entries = base.entries.clone();
+    entries.sort_by_key(|e| e.start);
+
+    let gaps = find_gaps(&entries, min_gap);
+    let fillers = donor.entries.iter().filter_map(|entry| {
+        let start = shift(entry.start, offset)?;
+        let end = shift(entry.end, offset)?;
+        gaps.iter()
+            .any(|&(gap_start, gap_end)| start >= gap_start && end <= gap_end)
+            .then(|| SubtitleEntry::new(start, end, entry.text.clone()))
+    });
+
+    entries.extend(fillers.collect::<Vec<_>>());
+    entries.sort_by_key(|e| e.start);
+
+    ParsedSubtitles::new(entries, base.language.clone()).with_format(base.original_format)
+}
+
+/// Silent stretches of at least `min_gap` between sorted cues; the last one is open-ended
+fn find_gaps(entries: &[SubtitleEntry], min_gap: Duration) -> Vec<(Duration, Duration)> {
+    let mut gaps = Vec::new();
+    let mut covered_until = Duration::ZERO;
+
+    for entry in entries {
+        if entry.start.saturating_sub(covered_until) >= min_gap {
+            gaps.push((covered_until, entry.start));
+        }
+        covered_until = covered_until.max(entry.end);
+    }
+    gaps.push((covered_until, Duration::MAX));
+
+    gaps
+}
+
+/// Move a donor timestamp onto the base timeline
+fn shift(time: Duration, offset_ms: i64) -> Option<Duration> {
+    let shifted = time.as_millis() as i64 - offset_ms;
+    u64::try_from(shifted).ok().map(Duration::from_millis)
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(start: u64, end: u64, text: &str) -> SubtitleEntry {
+        SubtitleEntry::new(
+            Duration::from_secs(start),
+            Duration::from_secs(end),
+            text.to_string(),
+        )
+    }
+
+    fn subtitles(entries: Vec<SubtitleEntry>) -> ParsedSubtitles {
+        ParsedSubtitles::new(entries, "en".to_string())
+    }
+
+    #[test]
+    fn test_coverage_counts_overlaps_once() {
+        let subs = subtitles(vec![entry(0, 4, "a"), entry(2, 6, "b"), entry(10, 11, "c")]);
+        assert_eq!(coverage(&subs), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_fill_gaps_from_reupload() {
+        // The base is missing the middle of the talk
+        let base = subtitles(vec![
+            entry(0, 3, "Welcome everyone."),
+            entry(3, 6, "Today we talk about Rust."),
+            entry(30, 33, "Thanks for watching!"),
+        ]);
+        // The re-upload has a 10s intro, so everything is shifted later
+        let donor = subtitles(vec![
+            entry(10, 13, "Welcome everyone"),
+            entry(13, 16, "today we talk about rust."),
+            entry(17, 25, "Ownership is the key idea."),
+            entry(26, 39, "Borrowing builds on it."),
+            entry(40, 43, "Thanks for watching!"),
+        ]);
+
+        assert_eq!(estimate_offset(&base, &donor), Some(10_000));
+
+        let merged = fill_gaps(&base, &donor, DEFAULT_MIN_GAP);
+        let texts: Vec<&str> = merged.entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Welcome everyone.",
+                "Today we talk about Rust.",
+                "Ownership is the key idea.",
+                "Borrowing builds on it.",
+                "Thanks for watching!",
+            ]
+        );
+        assert_eq!(merged.entries[2].start, Duration::from_secs(7));
+        assert!(coverage(&merged) > coverage(&base));
+    }
+
+    #[test]
+    fn test_fill_gaps_without_common_text() {
+        let base = subtitles(vec![entry(0, 3, "hello"), entry(20, 23, "bye")]);
+        let donor = subtitles(vec![entry(5, 8, "something else")]);
+
+        let merged = fill_gaps(&base, &donor, DEFAULT_MIN_GAP);
+        assert_eq!(merged.entries.len(), 2);
+    }
+}
@@ -0,0 +1,95 @@
+// Instrumentation hooks, so users can export ydl activity to their own metrics system
+use crate::error::YdlError;
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// Callbacks invoked at key points of discovery and download
+///
+/// Every method has a no-op default, so implementations only override what they
+/// record. Implementations must be cheap and non-blocking: they run inline on the
+/// request path.
+pub trait Metrics: Send + Sync + Debug {
+    /// An HTTP request finished; `status` is `None` if no response was received
+    fn request(&self, _client: &str, _status: Option<u16>, _elapsed: Duration) {}
+
+    /// A response body of `bytes` bytes was read
+    fn bytes_downloaded(&self, _client: &str, _bytes: usize) {}
+
+    /// A cached result was used instead of making a request
+    fn cache_hit(&self, _key: &str) {}
+
+    /// An operation is being retried after `error`; `attempt` starts at 1
+    fn retry(&self, _attempt: u32, _error: &YdlError) {}
+
+    /// A discovery strategy or InnerTube client found subtitle tracks
+    fn client_succeeded(&self, _client: &str) {}
+
+    /// The InnerTube API found no tracks and fallback discovery `method` did
+    ///
+    /// `method` is one of `watch_page`, `embedded_player`, `mobile_page` or
+    /// `get_video_info`. A rising rate means the primary path is degrading.
+    fn fallback_used(&self, _method: &str) {}
+}
+
+/// Metrics implementation that records nothing; the default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records every event as a string, for asserting on in tests
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingMetrics {
+        pub events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingMetrics {
+        pub fn events(&self) -> Vec<String> {
+            self.events.lock().unwrap().clone()
+        }
+
+        fn push(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl Metrics for RecordingMetrics {
+        fn request(&self, client: &str, status: Option<u16>, _elapsed: Duration) {
+            self.push(format!("request {} {:?}", client, status));
+        }
+
+        fn bytes_downloaded(&self, client: &str, bytes: usize) {
+            self.push(format!("bytes {} {}", client, bytes));
+        }
+
+        fn cache_hit(&self, key: &str) {
+            self.push(format!("cache {}", key));
+        }
+
+        fn retry(&self, attempt: u32, _error: &YdlError) {
+            self.push(format!("retry {}", attempt));
+        }
+
+        fn client_succeeded(&self, client: &str) {
+            self.push(format!("success {}", client));
+        }
+
+        fn fallback_used(&self, method: &str) {
+            self.push(format!("fallback {}", method));
+        }
+    }
+
+    #[test]
+    fn test_noop_metrics_accepts_events() {
+        let metrics: &dyn Metrics = &NoopMetrics;
+        metrics.request("WEB", Some(200), Duration::from_millis(5));
+        metrics.cache_hit("dQw4w9WgXcQ");
+        metrics.client_succeeded("WEB");
+        metrics.fallback_used("watch_page");
+    }
+}
@@ -0,0 +1,999 @@
+// Request tracing for debugging discovery and download failures, and the connection
+// settings (timeouts, proxies, cookies) shared by every HTTP client
+use crate::error::{YdlError, YdlResult};
+use crate::metrics::Metrics;
+use crate::types::YdlOptions;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy, RequestBuilder, Response};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, trace};
+
+/// Tracing target for per-request network logs; enable with `ydl::network=trace`
+pub const TRACE_TARGET: &str = "ydl::network";
+
+/// Environment variable naming a cookies file, used when none is configured explicitly
+pub const COOKIES_ENV: &str = "YDL_COOKIES";
+
+/// Watch-page parameters that sometimes leak into caption URLs and make the timedtext
+/// endpoint answer with a consent or age interstitial page instead of captions
+///
+/// None of them select caption content, so they are safe to strip:
+/// - `bpctr`: bypasses the "content may be inappropriate" warning on the watch page
+/// - `has_verified`: records that the viewer confirmed their age
+/// - `ucbcb`, `cbrd`: record a cookie-consent decision
+/// - `persist_gl`, `persist_hl`: persist the region/language picked in the consent flow
+const INTERSTITIAL_PARAMS: &[&str] = &[
+    "bpctr",
+    "has_verified",
+    "ucbcb",
+    "cbrd",
+    "persist_gl",
+    "persist_hl",
+];
+
+/// Hosts, with their subdomains, that redirects are followed to
+const YOUTUBE_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "youtube-nocookie.com",
+    "googlevideo.com",
+    "ytimg.com",
+    "google.com",
+    "googleapis.com",
+];
+
+/// Sign-in hosts of YouTube and Google, refused even though they are YouTube hosts
+const LOGIN_HOSTS: &[&str] = &["accounts.google.com", "accounts.youtube.com"];
+
+/// Hosts of the cookie-consent interstitial shown to new EU visitors
+const CONSENT_HOSTS: &[&str] = &["consent.youtube.com", "consent.google.com"];
+
+/// Most redirects followed for one request
+pub const MAX_REDIRECTS: usize = 10;
+
+/// Query parameters whose values are credentials and must never be logged
+const SENSITIVE_PARAMS: &[&str] = &["key", "api_key", "token", "sig", "signature", "pot"];
+
+/// Send a request, logging its method, redacted URL, status, size and timing
+///
+/// `client` names the client making the request (e.g. an InnerTube client name).
+/// Headers and bodies are never logged, so cookies and payloads stay out of the logs.
+/// The outcome is also reported to `metrics`.
+pub(crate) async fn send(
+    request: RequestBuilder,
+    client: &str,
+    metrics: &dyn Metrics,
+) -> reqwest::Result<Response> {
+    let (http, request) = request.build_split();
+    let request = request?;
+    let method = request.method().clone();
+    let url = redact_url(request.url().as_str());
+
+    let started = Instant::now();
+    let result = http.execute(request).await;
+    let elapsed = started.elapsed();
+    let elapsed_ms = elapsed.as_millis();
+    metrics.request(
+        client,
+        result.as_ref().ok().map(|r| r.status().as_u16()),
+        elapsed,
+    );
+
+    match &result {
+        Ok(response) => trace!(
+            target: TRACE_TARGET,
+            %method,
+            %url,
+            client,
+            status = response.status().as_u16(),
+            content_length = ?response.content_length(),
+            elapsed_ms,
+            "request completed"
+        ),
+        Err(e) => trace!(
+            target: TRACE_TARGET,
+            %method,
+            %url,
+            client,
+            elapsed_ms,
+            error = %e,
+            "request failed"
+        ),
+    }
+
+    result
+}
+
+/// Proxies for outgoing requests, per URL scheme
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Comma-separated hosts to connect to directly, in `NO_PROXY` syntax
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Resolve proxies the way curl does: an explicit proxy is used for every request;
+    /// otherwise `HTTPS_PROXY`/`HTTP_PROXY` (falling back to `ALL_PROXY`) apply per scheme,
+    /// minus the hosts in `NO_PROXY`
+    ///
+    /// `env` looks up an environment variable; both upper- and lowercase names are tried.
+    pub fn resolve(explicit: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Self {
+        if let Some(proxy) = explicit {
+            return Self {
+                http: Some(proxy.to_string()),
+                https: Some(proxy.to_string()),
+                no_proxy: None,
+            };
+        }
+
+        let lookup = |name: &str| {
+            env(name)
+                .or_else(|| env(&name.to_lowercase()))
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            http: lookup("HTTP_PROXY").or_else(|| lookup("ALL_PROXY")),
+            https: lookup("HTTPS_PROXY").or_else(|| lookup("ALL_PROXY")),
+            no_proxy: lookup("NO_PROXY"),
+        }
+    }
+
+    fn apply(&self, builder: ClientBuilder) -> YdlResult<ClientBuilder> {
+        let invalid = |e: reqwest::Error| YdlError::Configuration {
+            message: format!("Invalid proxy URL: {}", e),
+        };
+        let no_proxy = || self.no_proxy.as_deref().and_then(NoProxy::from_string);
+
+        // Proxies are always configured explicitly, so reqwest's own environment lookup is off
+        let mut builder = builder.no_proxy();
+        if let Some(url) = &self.http {
+            builder = builder.proxy(Proxy::http(url).map_err(invalid)?.no_proxy(no_proxy()));
+        }
+        if let Some(url) = &self.https {
+            builder = builder.proxy(Proxy::https(url).map_err(invalid)?.no_proxy(no_proxy()));
+        }
+        Ok(builder)
+    }
+}
+
+/// Connection settings applied to every HTTP client ydl creates
+#[derive(Debug, Clone)]
+pub(crate) struct HttpConfig {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    /// Cap on a whole request; without one only stalled reads time out
+    timeout: Option<Duration>,
+    proxy: ProxyConfig,
+    cookie: Option<HeaderValue>,
+    /// Caller-supplied client used instead of building one (see [`YdlOptions::http_client`])
+    client: Option<Client>,
+}
+
+/// Default timeouts with no proxy or cookies; the environment is not consulted
+impl Default for HttpConfig {
+    fn default() -> Self {
+        let options = YdlOptions::default();
+        Self {
+            connect_timeout: Duration::from_secs(options.connect_timeout_seconds),
+            read_timeout: Duration::from_secs(options.read_timeout_seconds),
+            timeout: options.timeout_seconds.map(Duration::from_secs),
+            proxy: ProxyConfig::default(),
+            cookie: None,
+            client: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Settings from `options`, falling back to the environment for proxies and cookies
+    pub fn from_options(options: &YdlOptions) -> YdlResult<Self> {
+        let proxy = ProxyConfig::resolve(options.proxy.as_deref(), env_var);
+        let cookie = match (
+            &options.cookie_header,
+            cookies_path(options.cookies_file.as_deref(), env_var),
+        ) {
+            (Some(header), _) => {
+                Some(
+                    HeaderValue::from_str(header).map_err(|_| YdlError::Configuration {
+                        message: "Invalid cookie header".to_string(),
+                    })?,
+                )
+            }
+            (None, Some(path)) => load_cookie_header(&path)?,
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            connect_timeout: Duration::from_secs(options.connect_timeout_seconds),
+            read_timeout: Duration::from_secs(options.read_timeout_seconds),
+            timeout: options.timeout_seconds.map(Duration::from_secs),
+            proxy,
+            cookie,
+            client: options.http_client.clone(),
+        })
+    }
+
+    /// Whether requests carry cookies, i.e. may belong to a signed-in session
+    pub fn has_cookies(&self) -> bool {
+        self.cookie.is_some()
+    }
+
+    /// A client sending `headers` (plus any cookies) with these settings applied
+    ///
+    /// `customize` adjusts the builder, e.g. to set a redirect policy. When a client was
+    /// supplied in the options it is used instead, with `headers` added to each request.
+    pub fn client(
+        &self,
+        mut headers: HeaderMap,
+        customize: impl FnOnce(ClientBuilder) -> ClientBuilder,
+    ) -> YdlResult<HttpClient> {
+        if let Some(client) = &self.client {
+            if let Some(cookie) = &self.cookie {
+                headers.insert(reqwest::header::COOKIE, cookie.clone());
+            }
+            return Ok(HttpClient {
+                client: client.clone(),
+                headers,
+            });
+        }
+
+        let client = customize(self.client_builder(headers)?)
+            .build()
+            .map_err(|e| YdlError::Configuration {
+                message: format!("Failed to create HTTP client: {}", e),
+            })?;
+        Ok(HttpClient {
+            client,
+            headers: HeaderMap::new(),
+        })
+    }
+
+    /// A client builder sending `headers` (plus any cookies) with these settings applied
+    fn client_builder(&self, mut headers: HeaderMap) -> YdlResult<ClientBuilder> {
+        if let Some(cookie) = &self.cookie {
+            headers.insert(reqwest::header::COOKIE, cookie.clone());
+        }
+
+        let mut builder = ClientBuilder::new()
+            .default_headers(headers)
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        self.proxy.apply(builder)
+    }
+}
+
+/// An HTTP client, plus headers added to each of its requests
+///
+/// Clients ydl builds carry their headers as defaults, so `headers` is empty for them.
+/// A caller-supplied client is shared as is, so the headers are sent per request instead.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    client: Client,
+    headers: HeaderMap,
+}
+
+impl HttpClient {
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url).headers(self.headers.clone())
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url).headers(self.headers.clone())
+    }
+}
+
+/// Cookies file to use: the explicitly configured one, else the one named by `YDL_COOKIES`
+pub fn cookies_path(
+    explicit: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    explicit.map(Path::to_path_buf).or_else(|| {
+        env(COOKIES_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Unexpired YouTube cookies from a Netscape-format cookies file, as `(name, value)` pairs
+///
+/// This is the format written by browser "cookies.txt" exporters and by curl. Cookies for
+/// other domains are ignored. `now` is the current Unix time in seconds.
+pub fn parse_cookies_file(content: &str, now: u64) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches('\r');
+            let line = match line.strip_prefix("#HttpOnly_") {
+                Some(rest) => rest,
+                None if line.starts_with('#') => return None,
+                None => line,
+            };
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [domain, _, _, _, expires, name, value] = fields[..] else {
+                return None;
+            };
+            let domain = domain.trim_start_matches('.');
+            if domain != "youtube.com" && !domain.ends_with(".youtube.com") {
+                return None;
+            }
+            // Session cookies have an expiry of 0
+            let expires: u64 = expires.parse().unwrap_or(0);
+            if expires != 0 && expires < now {
+                return None;
+            }
+
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn load_cookie_header(path: &Path) -> YdlResult<Option<HeaderValue>> {
+    let content = std::fs::read_to_string(path).map_err(|e| YdlError::Configuration {
+        message: format!("Cannot read cookies file {}: {}", path.display(), e),
+    })?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let cookies = parse_cookies_file(&content, now);
+    debug!(
+        "Loaded {} YouTube cookies from {}",
+        cookies.len(),
+        path.display()
+    );
+    if cookies.is_empty() {
+        return Ok(None);
+    }
+
+    let header = cookies
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ");
+    HeaderValue::from_str(&header)
+        .map(Some)
+        .map_err(|_| YdlError::Configuration {
+            message: format!(
+                "Cookies file {} contains invalid characters",
+                path.display()
+            ),
+        })
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Why YouTube will throttle a caption URL, if it carries protection ydl cannot satisfy
+///
+/// ydl does not run YouTube's player JavaScript, so it cannot decrypt a
+/// `signatureCipher` signature, transform the `n` parameter or mint a proof-of-origin
+/// token. Downloads from such URLs come back slow or empty.
+pub fn caption_url_protection(url: &str) -> Option<&'static str> {
+    let parsed = url::Url::parse(url).ok()?;
+    let has = |name: &str| parsed.query_pairs().any(|(k, _)| k == name);
+
+    if has("s") && !(has("sig") || has("signature") || has("lsig")) {
+        Some("its signature is still encrypted (signatureCipher)")
+    } else if has("n") {
+        Some("its `n` parameter must be transformed by the player JavaScript")
+    } else if parsed
+        .query_pairs()
+        .any(|(k, v)| k == "exp" && v.split(',').any(|exp| exp == "xpe"))
+        && !has("pot")
+    {
+        Some("it requires a proof-of-origin token (pot)")
+    } else {
+        None
+    }
+}
+
+/// Remove consent/age interstitial parameters (see [`INTERSTITIAL_PARAMS`]) from a caption URL
+///
+/// Parameters listed in the URL's `sparams` are covered by its signature and always kept,
+/// as is everything else; URLs without interstitial parameters are returned unchanged.
+pub fn strip_interstitial_params(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let signed: Vec<String> = parsed
+        .query_pairs()
+        .filter(|(k, _)| k == "sparams")
+        .flat_map(|(_, v)| {
+            v.split(',')
+                .map(|param| param.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let strip = |key: &str| INTERSTITIAL_PARAMS.contains(&key) && !signed.iter().any(|s| s == key);
+    if !parsed.query_pairs().any(|(k, _)| strip(&k)) {
+        return url.to_string();
+    }
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !strip(k))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+
+    parsed.to_string()
+}
+
+/// Redirect policy following up to [`MAX_REDIRECTS`] redirects within YouTube and Google
+///
+/// Redirects to the consent interstitial, to Google's sign-in pages, or to any
+/// other host (a login portal, a proxy's captive portal), are refused so the request fails with
+/// [`YdlError::ConsentRequired`] or [`YdlError::VideoRestricted`] instead of
+/// returning the wrong page. Other redirects that stay on the same host are followed.
+pub fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match refuse_redirect(&attempt) {
+            Some(refusal) => attempt.error(refusal),
+            None => attempt.follow(),
+        }
+    })
+}
+
+/// A redirect [`redirect_policy`] refused to follow
+#[derive(Debug)]
+struct RefusedRedirect {
+    consent: bool,
+    /// Video ID of the original request, or its URL if it names none
+    video_id: String,
+    location: String,
+}
+
+impl std::fmt::Display for RefusedRedirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "refused redirect to {}", self.location)
+    }
+}
+
+impl std::error::Error for RefusedRedirect {}
+
+fn refuse_redirect(attempt: &Attempt) -> Option<RefusedRedirect> {
+    let host = attempt
+        .url()
+        .host_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let on = |hosts: &[&str]| {
+        hosts
+            .iter()
+            .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+    };
+    let consent = on(CONSENT_HOSTS);
+    let refused = consent || on(LOGIN_HOSTS);
+    let same_host = attempt
+        .previous()
+        .last()
+        .and_then(url::Url::host_str)
+        .is_some_and(|previous| previous.eq_ignore_ascii_case(&host));
+    if !refused && (same_host || on(YOUTUBE_HOSTS)) {
+        return None;
+    }
+
+    let original = attempt.previous().first()?;
+    let video_id = original
+        .query_pairs()
+        .find(|(key, _)| key == "v")
+        .map(|(_, id)| id.into_owned())
+        .unwrap_or_else(|| redact_url(original.as_str()));
+    debug!(
+        "Refusing redirect from {} to {}",
+        redact_url(original.as_str()),
+        redact_url(attempt.url().as_str())
+    );
+    Some(RefusedRedirect {
+        consent,
+        video_id,
+        location: redact_url(attempt.url().as_str()),
+    })
+}
+
+/// The error for a request that failed because [`redirect_policy`] refused a redirect
+pub(crate) fn refused_redirect_error(error: &reqwest::Error) -> Option<YdlError> {
+    if !error.is_redirect() {
+        return None;
+    }
+
+    let mut cause = std::error::Error::source(error);
+    while let Some(err) = cause {
+        if let Some(refusal) = err.downcast_ref::<RefusedRedirect>() {
+            return Some(if refusal.consent {
+                YdlError::ConsentRequired {
+                    url: refusal.location.clone(),
+                }
+            } else {
+                YdlError::VideoRestricted {
+                    video_id: refusal.video_id.clone(),
+                }
+            });
+        }
+        cause = err.source();
+    }
+    None
+}
+
+/// Replace the values of credential-bearing query parameters with `REDACTED`
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(k, v)| {
+            let value = if SENSITIVE_PARAMS.contains(&k.as_ref()) {
+                "REDACTED".to_string()
+            } else {
+                v.into_owned()
+            };
+            (k.into_owned(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+
+    parsed.to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// A request received by [`test_server`]: its head and body as text
+    ///
+    /// Derefs to the text, so tests can match on `request.starts_with("GET /watch")`.
+    pub(crate) struct MockRequest {
+        text: String,
+        server: SocketAddr,
+    }
+
+    impl MockRequest {
+        /// Address the server listens on, for bodies that link back to it
+        pub(crate) fn server(&self) -> SocketAddr {
+            self.server
+        }
+
+        pub(crate) fn body(&self) -> &str {
+            self.text
+                .split_once("\r\n\r\n")
+                .map_or("", |(_, body)| body)
+        }
+
+        /// Value of the header `name` (case-insensitive), if sent
+        pub(crate) fn header(&self, name: &str) -> Option<&str> {
+            self.text.lines().skip(1).find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name).then(|| value.trim())
+            })
+        }
+    }
+
+    impl std::ops::Deref for MockRequest {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            &self.text
+        }
+    }
+
+    /// A response for [`test_server`] to send; every response closes its connection
+    pub(crate) struct MockResponse {
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: Vec<String>,
+        /// Pause before writing each part of the body
+        pause: Duration,
+        /// Bytes of the body sent before the connection is dropped, if not all of them
+        truncate_at: Option<usize>,
+    }
+
+    impl MockResponse {
+        pub(crate) fn ok(body: impl Into<String>) -> Self {
+            Self::status(200, body)
+        }
+
+        pub(crate) fn status(status: u16, body: impl Into<String>) -> Self {
+            Self {
+                status,
+                headers: Vec::new(),
+                body: vec![body.into()],
+                pause: Duration::ZERO,
+                truncate_at: None,
+            }
+        }
+
+        /// A 200 whose body is written in `parts`, waiting `pause` before each
+        pub(crate) fn trickle(parts: &[&str], pause: Duration) -> Self {
+            Self {
+                body: parts.iter().map(|part| part.to_string()).collect(),
+                pause,
+                ..Self::ok("")
+            }
+        }
+
+        pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+            self.headers.push((name, value.into()));
+            self
+        }
+
+        /// Announce the whole body but drop the connection after `bytes` of it
+        pub(crate) fn truncated(mut self, bytes: usize) -> Self {
+            self.truncate_at = Some(bytes);
+            self
+        }
+
+        async fn write_to(self, socket: &mut TcpStream) {
+            let length: usize = self.body.iter().map(String::len).sum();
+            let reason = reqwest::StatusCode::from_u16(self.status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or("");
+            let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason);
+            for (name, value) in &self.headers {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            head.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                length
+            ));
+            let _ = socket.write_all(head.as_bytes()).await;
+
+            let mut budget = self.truncate_at.unwrap_or(length);
+            for part in &self.body {
+                tokio::time::sleep(self.pause).await;
+                let sent = &part.as_bytes()[..part.len().min(budget)];
+                let _ = socket.write_all(sent).await;
+                budget -= sent.len();
+            }
+            let _ = socket.shutdown().await;
+        }
+    }
+
+    /// Serve HTTP on a local port, answering every request with `handler`
+    pub(crate) async fn test_server(
+        handler: impl Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    ) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = listener.local_addr().unwrap();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let text = read_request(&mut socket).await;
+                    let response = handler(&MockRequest { text, server });
+                    response.write_to(&mut socket).await;
+                });
+            }
+        });
+        server
+    }
+
+    /// Read a request's head and, going by its `Content-Length`, its body
+    async fn read_request(socket: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                if n == 0 {
+                    break;
+                }
+                continue;
+            };
+            let length = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if n == 0 || body.len() >= length {
+                break;
+            }
+        }
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_spares_slow_but_steady_body() {
+        let addr = test_server(|request| {
+            // Either trickle the body for longer than the read timeout in total,
+            // or go silent for longer than it once
+            let pause = if request.starts_with("GET /stalled") {
+                600
+            } else {
+                100
+            };
+            MockResponse::trickle(
+                &["WEBVTT\n\n", "00:00:01.000 --> 00:00:02.000\n", "Hello\n"],
+                Duration::from_millis(pause),
+            )
+        })
+        .await;
+
+        let fetch = |config: HttpConfig, path: &'static str| async move {
+            let client = config.client(HeaderMap::new(), |builder| builder)?;
+            let request = client.get(&format!("http://{}{}", addr, path));
+            let response = send(request, "test", &crate::metrics::NoopMetrics).await?;
+            response.text().await.map_err(YdlError::from)
+        };
+
+        // No overall cap by default, so only the stalled download fails
+        let config = HttpConfig {
+            read_timeout: Duration::from_millis(250),
+            ..HttpConfig::default()
+        };
+        assert_eq!(config.timeout, None);
+        let content = fetch(config.clone(), "/steady.vtt").await.unwrap();
+        assert!(content.contains("Hello"));
+        assert!(fetch(config.clone(), "/stalled.vtt").await.is_err());
+
+        // The same holds for options left at their defaults
+        let defaults = HttpConfig::from_options(&YdlOptions::default()).unwrap();
+        assert_eq!(defaults.timeout, None);
+        let config = HttpConfig {
+            read_timeout: Duration::from_millis(250),
+            ..defaults
+        };
+        let content = fetch(config, "/steady.vtt").await.unwrap();
+        assert!(content.contains("Hello"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_off_youtube_are_refused() {
+        let addr = test_server(|request| {
+            let location = if request.starts_with("GET /watch") {
+                "https://login.example.com/signin?continue=watch"
+            } else if request.starts_with("GET /signin") {
+                "https://accounts.google.com/ServiceLogin?continue=watch"
+            } else if request.starts_with("GET /consent") {
+                "https://consent.youtube.com/ml?continue=watch"
+            } else if request.starts_with("GET /moved") {
+                "/page"
+            } else {
+                return MockResponse::ok("ok");
+            };
+            MockResponse::status(302, "").header("Location", location)
+        })
+        .await;
+
+        let client = HttpConfig::from_options(&YdlOptions::default())
+            .unwrap()
+            .client(HeaderMap::new(), |builder| {
+                builder.redirect(redirect_policy())
+            })
+            .unwrap();
+        let get = |path: &str| {
+            let request = client.get(&format!("http://{}{}", addr, path));
+            async move { request.send().await.map_err(YdlError::from) }
+        };
+
+        let error = get("/watch?v=dQw4w9WgXcQ").await.unwrap_err();
+        assert!(
+            matches!(&error, YdlError::VideoRestricted { video_id } if video_id == "dQw4w9WgXcQ"),
+            "{error:?}"
+        );
+        assert!(!error.is_retryable());
+
+        // Google's sign-in wall is refused although google.com is followed otherwise
+        let error = get("/signin?v=dQw4w9WgXcQ").await.unwrap_err();
+        assert!(
+            matches!(&error, YdlError::VideoRestricted { video_id } if video_id == "dQw4w9WgXcQ"),
+            "{error:?}"
+        );
+
+        let error = get("/consent").await.unwrap_err();
+        assert!(
+            matches!(&error, YdlError::ConsentRequired { url } if url.starts_with("https://consent.youtube.com/")),
+            "{error:?}"
+        );
+
+        // Redirects within the same host are still followed
+        let response = get("/moved").await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url(
+                "https://www.youtube.com/youtubei/v1/player?key=AIzaSecret&prettyPrint=false"
+            ),
+            "https://www.youtube.com/youtubei/v1/player?key=REDACTED&prettyPrint=false"
+        );
+        assert_eq!(
+            redact_url("https://www.youtube.com/api/timedtext?v=abc&signature=xyz&lang=en"),
+            "https://www.youtube.com/api/timedtext?v=abc&signature=REDACTED&lang=en"
+        );
+        assert_eq!(
+            redact_url("https://www.youtube.com/watch"),
+            "https://www.youtube.com/watch"
+        );
+    }
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_proxy_from_env() {
+        let vars = [
+            ("https_proxy", "http://secure.proxy:3128"),
+            ("ALL_PROXY", "http://all.proxy:8080"),
+            ("NO_PROXY", "localhost,.internal"),
+        ];
+        assert_eq!(
+            ProxyConfig::resolve(None, env(&vars)),
+            ProxyConfig {
+                http: Some("http://all.proxy:8080".to_string()),
+                https: Some("http://secure.proxy:3128".to_string()),
+                no_proxy: Some("localhost,.internal".to_string()),
+            }
+        );
+
+        assert_eq!(
+            ProxyConfig::resolve(None, env(&[("HTTP_PROXY", "")])),
+            ProxyConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_explicit_proxy_overrides_env() {
+        let vars = [
+            ("HTTPS_PROXY", "http://env.proxy:3128"),
+            ("NO_PROXY", "youtube.com"),
+        ];
+        let explicit = "socks5://127.0.0.1:1080";
+        assert_eq!(
+            ProxyConfig::resolve(Some(explicit), env(&vars)),
+            ProxyConfig {
+                http: Some(explicit.to_string()),
+                https: Some(explicit.to_string()),
+                no_proxy: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_cookies_path_precedence() {
+        let vars = [(COOKIES_ENV, "/env/cookies.txt")];
+        assert_eq!(
+            cookies_path(None, env(&vars)),
+            Some(PathBuf::from("/env/cookies.txt"))
+        );
+        assert_eq!(
+            cookies_path(Some(Path::new("/flag/cookies.txt")), env(&vars)),
+            Some(PathBuf::from("/flag/cookies.txt"))
+        );
+        assert_eq!(cookies_path(None, env(&[])), None);
+    }
+
+    #[test]
+    fn test_cookies_option_accepts_file_or_header() {
+        let options = YdlOptions::new().cookies("Cookie: SID=secret; HSID=other");
+        assert_eq!(
+            options.cookie_header.as_deref(),
+            Some("SID=secret; HSID=other")
+        );
+        assert!(options.cookies_file.is_none());
+        let config = HttpConfig::from_options(&options).unwrap();
+        assert!(config.has_cookies());
+        assert_eq!(
+            config.cookie.unwrap().to_str().unwrap(),
+            "SID=secret; HSID=other"
+        );
+
+        let options = YdlOptions::new().cookies("/home/me/cookies.txt");
+        assert_eq!(
+            options.cookies_file,
+            Some(PathBuf::from("/home/me/cookies.txt"))
+        );
+        assert!(options.cookie_header.is_none());
+
+        // A file is read as one even when its name looks like a header
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("session=main.txt");
+        std::fs::write(&file, "").unwrap();
+        let options = YdlOptions::new().cookies(file.to_str().unwrap());
+        assert_eq!(options.cookies_file, Some(file));
+        assert!(options.cookie_header.is_none());
+
+        let options = YdlOptions::new().cookie_header("SID=secret");
+        assert_eq!(options.cookie_header.as_deref(), Some("SID=secret"));
+
+        let invalid = YdlOptions::new().cookies("SID=bad\nvalue");
+        assert!(matches!(
+            HttpConfig::from_options(&invalid),
+            Err(YdlError::Configuration { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_cookies_file() {
+        let content = "# Netscape HTTP Cookie File\n\
+            .youtube.com\tTRUE\t/\tTRUE\t0\tPREF\tf6=40000000\n\
+            #HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t2000000000\tSID\tsecret\r\n\
+            .youtube.com\tTRUE\t/\tTRUE\t1000\tEXPIRED\tgone\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tOTHER\tvalue\n\
+            malformed line\n";
+
+        assert_eq!(
+            parse_cookies_file(content, 1_700_000_000),
+            vec![
+                ("PREF".to_string(), "f6=40000000".to_string()),
+                ("SID".to_string(), "secret".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_interstitial_params() {
+        let base = "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en";
+        assert_eq!(strip_interstitial_params(base), base);
+        assert_eq!(
+            strip_interstitial_params(&format!(
+                "{}&bpctr=9999999999&has_verified=1&fmt=srv3",
+                base
+            )),
+            format!("{}&fmt=srv3", base)
+        );
+
+        // A parameter covered by the signature stays
+        let signed = format!("{}&bpctr=9999999999&sparams=ip,bpctr&sig=AOq0", base);
+        assert_eq!(strip_interstitial_params(&signed), signed);
+    }
+
+    #[test]
+    fn test_caption_url_protection() {
+        let base = "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en";
+        assert_eq!(caption_url_protection(base), None);
+        assert_eq!(
+            caption_url_protection(&format!("{}&s=AOq0QJ8wRQIh&sp=sig", base)),
+            Some("its signature is still encrypted (signatureCipher)")
+        );
+        assert_eq!(
+            caption_url_protection(&format!("{}&s=AOq0QJ8wRQIh&sig=AIh", base)),
+            None
+        );
+        assert_eq!(
+            caption_url_protection(&format!("{}&n=kB5gTpHzLrXhYw", base)),
+            Some("its `n` parameter must be transformed by the player JavaScript")
+        );
+        assert_eq!(
+            caption_url_protection(&format!("{}&exp=xpe&sparams=ip,exp", base)),
+            Some("it requires a proof-of-origin token (pot)")
+        );
+        assert_eq!(
+            caption_url_protection(&format!("{}&exp=xpe&pot=MlQ", base)),
+            None
+        );
+    }
+}
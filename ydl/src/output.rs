@@ -0,0 +1,86 @@
+// Output path resolution and directory creation, shared by library users and the CLI
+use crate::error::{YdlError, YdlResult};
+use std::path::{Path, PathBuf};
+
+/// Path of `file_name` inside `output_dir`, or in the current directory if there is none
+pub fn output_path(output_dir: Option<&Path>, file_name: &str) -> PathBuf {
+    match output_dir {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Make sure the directory that will hold `path` exists
+///
+/// A missing directory (and its missing parents) is created when `create_dirs` is set;
+/// otherwise it is reported as a `FileSystem` error with `NotFound`.
+pub async fn ensure_output_dir(path: &Path, create_dirs: bool) -> YdlResult<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    ensure_dir(dir, create_dirs).await
+}
+
+/// Make sure the directory `dir` exists, creating it under the same policy as
+/// [`ensure_output_dir`]
+pub async fn ensure_dir(dir: &Path, create_dirs: bool) -> YdlResult<()> {
+    if dir.exists() {
+        return Ok(());
+    }
+
+    if !create_dirs {
+        return Err(YdlError::FileSystem {
+            source: std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Output directory does not exist: {}", dir.display()),
+            ),
+        });
+    }
+
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| YdlError::FileSystem {
+            source: std::io::Error::new(
+                e.kind(),
+                format!("Cannot create output directory {}: {}", dir.display(), e),
+            ),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        assert_eq!(
+            output_path(Some(Path::new("subs/en")), "talk.srt"),
+            PathBuf::from("subs/en/talk.srt")
+        );
+        assert_eq!(output_path(None, "talk.srt"), PathBuf::from("talk.srt"));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_output_dir_policy() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("a").join("b").join("talk.srt");
+
+        let err = ensure_output_dir(&nested, false).await.unwrap_err();
+        assert!(matches!(
+            &err,
+            YdlError::FileSystem { source } if source.kind() == std::io::ErrorKind::NotFound
+        ));
+        assert!(!nested.parent().unwrap().exists());
+
+        ensure_output_dir(&nested, true).await.unwrap();
+        assert!(nested.parent().unwrap().is_dir());
+        assert!(!nested.exists());
+
+        // Existing directories, including the current one, need no creation
+        ensure_output_dir(&nested, false).await.unwrap();
+        ensure_output_dir(Path::new("talk.srt"), false)
+            .await
+            .unwrap();
+    }
+}
@@ -1,10 +1,101 @@
 use crate::error::{YdlError, YdlResult};
 use regex::Regex;
+use std::time::Duration;
 use url::Url;
 
+/// What a YouTube URL points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YouTubeRef {
+    /// A single video, by ID
+    Video(String),
+    /// A playlist
+    Playlist(PlaylistRef),
+    /// A channel
+    Channel(ChannelRef),
+    /// An ad-hoc `watch_videos` list of video IDs
+    WatchVideos(Vec<String>),
+}
+
+/// A video URL with the parameters [`YouTubeParser::parse_url`] discards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedUrl {
+    pub video_id: String,
+    /// Where playback starts (`t=` or `start=`)
+    pub start: Option<Duration>,
+    /// The playlist the video was opened from (`list=`)
+    pub playlist: Option<String>,
+}
+
+/// Reference to a playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistRef {
+    pub id: String,
+}
+
+/// A playlist along with the entry a URL points at, e.g. `watch?v=ID&list=PL...&index=7`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistPosition {
+    pub playlist: PlaylistRef,
+    /// The video the URL is playing (`v=`)
+    pub video_id: Option<String>,
+    /// 1-based position in the playlist (`index=`)
+    pub index: Option<usize>,
+}
+
+impl PlaylistPosition {
+    /// The entries of `video_ids` from this position onward
+    ///
+    /// The video ID is used when it appears in the list, since `index=` goes stale
+    /// when the playlist is edited; otherwise the index is used. Without either,
+    /// the whole list is returned.
+    pub fn remaining(&self, video_ids: Vec<String>) -> Vec<String> {
+        let start = self
+            .video_id
+            .as_ref()
+            .and_then(|id| video_ids.iter().position(|v| v == id))
+            .or_else(|| self.index.map(|index| index.saturating_sub(1)))
+            .unwrap_or(0)
+            .min(video_ids.len());
+
+        video_ids.into_iter().skip(start).collect()
+    }
+}
+
+/// Reference to a channel, in whichever form the URL used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelRef {
+    /// `/@handle` (stored without the `@`)
+    Handle(String),
+    /// `/channel/UC...`
+    Id(String),
+    /// Legacy `/c/name` custom URL
+    Custom(String),
+    /// Legacy `/user/name` URL
+    User(String),
+}
+
+impl ChannelRef {
+    /// URL of the channel's home page
+    pub fn page_url(&self) -> String {
+        match self {
+            ChannelRef::Handle(handle) => format!("https://www.youtube.com/@{}", handle),
+            ChannelRef::Id(id) => format!("https://www.youtube.com/channel/{}", id),
+            ChannelRef::Custom(name) => format!("https://www.youtube.com/c/{}", name),
+            ChannelRef::User(name) => format!("https://www.youtube.com/user/{}", name),
+        }
+    }
+}
+
+/// Type prefixes of the playlist IDs accepted by [`YouTubeParser::parse_playlist_url`]:
+/// user playlists, channel uploads, liked videos, favorites and mixes
+const PLAYLIST_ID_PREFIXES: [&str; 5] = ["PL", "UU", "LL", "FL", "RD"];
+
 /// YouTube URL parser for extracting video IDs from various URL formats
 pub struct YouTubeParser {
     video_id_regex: Regex,
+    playlist_id_regex: Regex,
+    channel_id_regex: Regex,
+    channel_name_regex: Regex,
     youtube_domains: Vec<&'static str>,
 }
 
@@ -19,6 +110,14 @@ impl YouTubeParser {
     pub fn new() -> Self {
         // YouTube video ID pattern: 11 characters, alphanumeric plus - and _
         let video_id_regex = Regex::new(r"^[a-zA-Z0-9_-]{11}$").expect("Valid video ID regex");
+        // Playlist IDs carry a type prefix (PL, UU, LL, FL or RD) followed by an ID
+        let playlist_id_regex =
+            Regex::new(r"^[a-zA-Z0-9_-]{10,64}$").expect("Valid playlist ID regex");
+        // Channel IDs are "UC" followed by 22 characters
+        let channel_id_regex =
+            Regex::new(r"^UC[a-zA-Z0-9_-]{22}$").expect("Valid channel ID regex");
+        let channel_name_regex =
+            Regex::new(r"^[a-zA-Z0-9._-]{1,100}$").expect("Valid channel name regex");
 
         let youtube_domains = vec![
             "youtube.com",
@@ -31,6 +130,9 @@ impl YouTubeParser {
 
         Self {
             video_id_regex,
+            playlist_id_regex,
+            channel_id_regex,
+            channel_name_regex,
             youtube_domains,
         }
     }
@@ -49,6 +151,239 @@ impl YouTubeParser {
         self.extract_video_id(&url)
     }
 
+    /// Parse a YouTube URL into its video ID, start time and playlist
+    ///
+    /// The start time comes from `t=` (or the embed player's `start=`), in the
+    /// `90`, `90s`, `1m30s` and `1h2m3s` forms; a `#t=` fragment works too.
+    pub fn parse_url_full(&self, url_str: &str) -> YdlResult<ParsedUrl> {
+        let url = Url::parse(url_str).map_err(|_| YdlError::InvalidUrl {
+            url: url_str.to_string(),
+        })?;
+
+        self.validate_domain(&url)?;
+        let video_id = self.extract_video_id(&url)?;
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        let fragment_start = url
+            .fragment()
+            .and_then(|fragment| fragment.strip_prefix("t="))
+            .map(str::to_string);
+
+        let start = param("t")
+            .or_else(|| param("start"))
+            .or(fragment_start)
+            .and_then(|value| parse_start_time(&value));
+        let playlist = param("list").filter(|id| self.is_valid_playlist_id(id));
+
+        Ok(ParsedUrl {
+            video_id,
+            start,
+            playlist,
+        })
+    }
+
+    /// Parse a `watch_videos?video_ids=ID1,ID2,...` URL into its video IDs
+    ///
+    /// When `skip_invalid` is set, malformed IDs are dropped instead of failing the
+    /// whole URL. An error is still returned if no valid IDs remain.
+    pub fn parse_watch_videos_url(
+        &self,
+        url_str: &str,
+        skip_invalid: bool,
+    ) -> YdlResult<Vec<String>> {
+        let url = Url::parse(url_str).map_err(|_| YdlError::InvalidUrl {
+            url: url_str.to_string(),
+        })?;
+
+        self.validate_domain(&url)?;
+
+        if url.path() != "/watch_videos" {
+            return Err(YdlError::InvalidUrl {
+                url: url.to_string(),
+            });
+        }
+
+        let ids_param = url
+            .query_pairs()
+            .find(|(key, _)| key == "video_ids")
+            .map(|(_, value)| value.to_string())
+            .ok_or_else(|| YdlError::InvalidUrl {
+                url: url.to_string(),
+            })?;
+
+        let mut video_ids = Vec::new();
+        for id in ids_param
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+        {
+            if self.is_valid_video_id(id) {
+                video_ids.push(id.to_string());
+            } else if !skip_invalid {
+                return Err(YdlError::InvalidVideoId {
+                    video_id: id.to_string(),
+                });
+            }
+        }
+
+        if video_ids.is_empty() {
+            return Err(YdlError::InvalidUrl {
+                url: url.to_string(),
+            });
+        }
+
+        Ok(video_ids)
+    }
+
+    /// Classify a YouTube URL as a video, playlist, channel or `watch_videos` list
+    pub fn classify_url(&self, url_str: &str) -> YdlResult<YouTubeRef> {
+        if self.is_watch_videos_url(url_str) {
+            return self
+                .parse_watch_videos_url(url_str, false)
+                .map(YouTubeRef::WatchVideos);
+        }
+
+        if let Ok(video_id) = self.parse_url(url_str) {
+            return Ok(YouTubeRef::Video(video_id));
+        }
+
+        let url = Url::parse(url_str).map_err(|_| YdlError::InvalidUrl {
+            url: url_str.to_string(),
+        })?;
+        self.validate_domain(&url)?;
+
+        if let Some(playlist) = self.extract_playlist(&url) {
+            return Ok(YouTubeRef::Playlist(playlist));
+        }
+        if let Some(channel) = self.extract_channel(&url) {
+            return Ok(YouTubeRef::Channel(channel));
+        }
+
+        Err(YdlError::InvalidUrl {
+            url: url_str.to_string(),
+        })
+    }
+
+    /// Parse a playlist URL and extract the playlist ID from its `list=` parameter
+    ///
+    /// Works for `/playlist?list=...` as well as `watch?v=...&list=...` URLs, where
+    /// [`parse_url`](Self::parse_url) keeps returning the single video. IDs without a
+    /// `PL`, `UU`, `LL`, `FL` or `RD` prefix are rejected.
+    pub fn parse_playlist_url(&self, url_str: &str) -> YdlResult<String> {
+        let url = Url::parse(url_str).map_err(|_| YdlError::InvalidUrl {
+            url: url_str.to_string(),
+        })?;
+
+        self.validate_domain(&url)?;
+
+        let playlist_id = url
+            .query_pairs()
+            .find(|(key, _)| key == "list")
+            .map(|(_, value)| value.to_string())
+            .ok_or_else(|| YdlError::InvalidUrl {
+                url: url.to_string(),
+            })?;
+
+        if !self.is_valid_playlist_id(&playlist_id) {
+            return Err(YdlError::InvalidPlaylistId { playlist_id });
+        }
+
+        Ok(playlist_id)
+    }
+
+    /// Check whether a playlist ID has a known type prefix and valid characters
+    pub fn is_valid_playlist_id(&self, playlist_id: &str) -> bool {
+        PLAYLIST_ID_PREFIXES
+            .iter()
+            .any(|prefix| playlist_id.starts_with(prefix))
+            && self.playlist_id_regex.is_match(playlist_id)
+    }
+
+    /// Parse the `list=`, `v=` and `index=` parameters of a playlist or watch URL
+    ///
+    /// Returns `None` if the URL has no valid `list=` parameter.
+    pub fn parse_playlist_position(&self, url_str: &str) -> Option<PlaylistPosition> {
+        let url = Url::parse(url_str).ok()?;
+        self.validate_domain(&url).ok()?;
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+        };
+
+        let id = param("list").filter(|id| self.is_valid_playlist_id(id))?;
+        let index = param("index")
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|&index| index > 0);
+
+        Some(PlaylistPosition {
+            playlist: PlaylistRef { id },
+            video_id: self.parse_url(url_str).ok(),
+            index,
+        })
+    }
+
+    /// Extract a playlist from `/playlist?list=ID` URLs
+    fn extract_playlist(&self, url: &Url) -> Option<PlaylistRef> {
+        if url.path() != "/playlist" {
+            return None;
+        }
+
+        url.query_pairs()
+            .find(|(key, _)| key == "list")
+            .map(|(_, value)| value.to_string())
+            .filter(|id| self.is_valid_playlist_id(id))
+            .map(|id| PlaylistRef { id })
+    }
+
+    /// Extract a channel from `/@handle`, `/channel/ID`, `/c/name` and `/user/name` URLs
+    fn extract_channel(&self, url: &Url) -> Option<ChannelRef> {
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+        match segments.as_slice() {
+            [handle, ..] if handle.starts_with('@') => {
+                let handle = &handle[1..];
+                self.channel_name_regex
+                    .is_match(handle)
+                    .then(|| ChannelRef::Handle(handle.to_string()))
+            }
+            ["channel", id, ..] => self
+                .channel_id_regex
+                .is_match(id)
+                .then(|| ChannelRef::Id(id.to_string())),
+            ["c", name, ..] => self
+                .channel_name_regex
+                .is_match(name)
+                .then(|| ChannelRef::Custom(name.to_string())),
+            ["user", name, ..] => self
+                .channel_name_regex
+                .is_match(name)
+                .then(|| ChannelRef::User(name.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Check whether a URL is a `/shorts/VIDEO_ID` link
+    pub fn is_shorts_url(&self, url_str: &str) -> bool {
+        Url::parse(url_str)
+            .map(|url| {
+                self.validate_domain(&url).is_ok() && self.extract_from_shorts_url(&url).is_ok()
+            })
+            .unwrap_or(false)
+    }
+
+    /// Check whether a URL is a `watch_videos` multi-video link
+    pub fn is_watch_videos_url(&self, url_str: &str) -> bool {
+        Url::parse(url_str)
+            .map(|url| self.validate_domain(&url).is_ok() && url.path() == "/watch_videos")
+            .unwrap_or(false)
+    }
+
     /// Validate that the URL is from a YouTube domain
     fn validate_domain(&self, url: &Url) -> YdlResult<()> {
         let domain = url.domain().ok_or_else(|| YdlError::InvalidUrl {
@@ -185,11 +520,59 @@ impl YouTubeParser {
     }
 }
 
+/// Parse a `t=` start time: bare seconds (`90`) or `h`/`m`/`s` units (`90s`, `1m30s`)
+fn parse_start_time(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut last_unit = u64::MAX;
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        // Units must each appear once, largest first
+        if number.is_empty() || unit >= last_unit {
+            return None;
+        }
+        seconds += number.parse::<u64>().ok()? * unit;
+        number.clear();
+        last_unit = unit;
+    }
+
+    number.is_empty().then(|| Duration::from_secs(seconds))
+}
+
 /// Convenience function to parse a YouTube URL
 pub fn parse_youtube_url(url: &str) -> YdlResult<String> {
     YouTubeParser::new().parse_url(url)
 }
 
+/// Convenience function to parse a `watch_videos` URL into its video IDs
+pub fn parse_watch_videos_url(url: &str) -> YdlResult<Vec<String>> {
+    YouTubeParser::new().parse_watch_videos_url(url, false)
+}
+
+/// Convenience function to extract the playlist ID from a playlist or watch URL
+pub fn parse_playlist_url(url: &str) -> YdlResult<String> {
+    YouTubeParser::new().parse_playlist_url(url)
+}
+
+/// Convenience function to classify a YouTube URL
+pub fn classify_url(url: &str) -> YdlResult<YouTubeRef> {
+    YouTubeParser::new().classify_url(url)
+}
+
 /// Convenience function to validate a video ID
 pub fn is_valid_video_id(video_id: &str) -> bool {
     YouTubeParser::new().is_valid_video_id(video_id)
@@ -238,15 +621,15 @@ mod tests {
         ];
 
         for url in urls {
-            let result = parser.parse_url(url);
-            if result.is_err() {
-                // Handle the case where scheme is missing
-                let full_url = format!("https://{}", url);
-                let result = parser.parse_url(&full_url);
-                assert!(result.is_ok(), "Failed to parse: {}", url);
-                assert_eq!(result.unwrap(), "dQw4w9WgXcQ");
-            } else {
-                assert_eq!(result.unwrap(), "dQw4w9WgXcQ");
+            match parser.parse_url(url) {
+                Ok(id) => assert_eq!(id, "dQw4w9WgXcQ"),
+                Err(_) => {
+                    // Handle the case where scheme is missing
+                    let full_url = format!("https://{}", url);
+                    let result = parser.parse_url(&full_url);
+                    assert!(result.is_ok(), "Failed to parse: {}", url);
+                    assert_eq!(result.unwrap(), "dQw4w9WgXcQ");
+                }
             }
         }
     }
@@ -275,6 +658,11 @@ mod tests {
         let result = parser.parse_url(url);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "dQw4w9WgXcQ");
+
+        assert!(parser.is_shorts_url(url));
+        assert!(parser.is_shorts_url("https://m.youtube.com/shorts/dQw4w9WgXcQ?feature=share"));
+        assert!(!parser.is_shorts_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert!(!parser.is_shorts_url("https://example.com/shorts/dQw4w9WgXcQ"));
     }
 
     #[test]
@@ -295,6 +683,264 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_url_full() {
+        let parser = parser();
+
+        let parsed = parser
+            .parse_url_full("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1m30s&list=PLrCZdFsaG")
+            .unwrap();
+        assert_eq!(
+            parsed,
+            ParsedUrl {
+                video_id: "dQw4w9WgXcQ".to_string(),
+                start: Some(Duration::from_secs(90)),
+                playlist: Some("PLrCZdFsaG".to_string()),
+            }
+        );
+
+        let start = |url: &str| parser.parse_url_full(url).unwrap().start;
+        assert_eq!(
+            start("https://youtu.be/dQw4w9WgXcQ?t=90s"),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            start("https://youtu.be/dQw4w9WgXcQ?t=90"),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            start("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1h2m3s"),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            start("https://www.youtube.com/embed/dQw4w9WgXcQ?start=45"),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(
+            start("https://www.youtube.com/watch?v=dQw4w9WgXcQ#t=30"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(start("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), None);
+        // Malformed start times are ignored rather than failing the URL
+        assert_eq!(
+            start("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=30s1m"),
+            None
+        );
+        assert_eq!(start("https://youtu.be/dQw4w9WgXcQ?t=abc"), None);
+
+        assert!(
+            parser
+                .parse_url_full("https://example.com/watch?v=dQw4w9WgXcQ&t=90")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_watch_videos_url() {
+        let parser = parser();
+
+        let url =
+            "https://www.youtube.com/watch_videos?video_ids=dQw4w9WgXcQ,aBc_123-XyZ,0123456789a";
+        assert!(parser.is_watch_videos_url(url));
+
+        let ids = parser.parse_watch_videos_url(url, false).unwrap();
+        assert_eq!(ids, vec!["dQw4w9WgXcQ", "aBc_123-XyZ", "0123456789a"]);
+
+        // A single-video watch URL is not a multi-video link
+        assert!(!parser.is_watch_videos_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert!(
+            parser
+                .parse_watch_videos_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ", false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_watch_videos_url_with_invalid_id() {
+        let parser = parser();
+        let url = "https://www.youtube.com/watch_videos?video_ids=dQw4w9WgXcQ,bad!,0123456789a";
+
+        let result = parser.parse_watch_videos_url(url, false);
+        assert!(matches!(result, Err(YdlError::InvalidVideoId { video_id }) if video_id == "bad!"));
+
+        let ids = parser.parse_watch_videos_url(url, true).unwrap();
+        assert_eq!(ids, vec!["dQw4w9WgXcQ", "0123456789a"]);
+
+        // Skipping every ID leaves nothing to download
+        let url = "https://www.youtube.com/watch_videos?video_ids=bad!,short";
+        assert!(parser.parse_watch_videos_url(url, true).is_err());
+    }
+
+    #[test]
+    fn test_parse_playlist_position() {
+        let parser = parser();
+
+        let position = parser
+            .parse_playlist_position(
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf&index=3",
+            )
+            .unwrap();
+        assert_eq!(position.playlist.id, "PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf");
+        assert_eq!(position.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(position.index, Some(3));
+
+        let position = parser
+            .parse_playlist_position(
+                "https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf&index=abc",
+            )
+            .unwrap();
+        assert_eq!(position.video_id, None);
+        assert_eq!(position.index, None);
+
+        assert!(
+            parser
+                .parse_playlist_position("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+                .is_none()
+        );
+        assert!(
+            parser
+                .parse_playlist_position("https://example.com/playlist?list=PLrAXtmErZgOeiKm4sg")
+                .is_none()
+        );
+
+        // IDs that parse_playlist_url rejects are no playlist anywhere else either
+        let unknown = "https://www.youtube.com/playlist?list=XXrAXtmErZgOeiKm4sgNOknGvNjby9efdf";
+        assert!(parser.parse_playlist_url(unknown).is_err());
+        assert!(parser.parse_playlist_position(unknown).is_none());
+        assert!(parser.classify_url(unknown).is_err());
+    }
+
+    #[test]
+    fn test_parse_playlist_url() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .parse_playlist_url(
+                    "https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+                )
+                .unwrap(),
+            "PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+        );
+        for prefix in ["UU", "LL", "FL", "RD"] {
+            let url = format!(
+                "https://m.youtube.com/playlist?list={}uAXFkgsw1L7xaCfnd5JJOw",
+                prefix
+            );
+            assert!(parser.parse_playlist_url(&url).is_ok(), "{}", url);
+        }
+
+        // A watch URL with both parameters: one video for parse_url, the list here
+        let mixed = "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf&index=2";
+        assert_eq!(parser.parse_url(mixed).unwrap(), "dQw4w9WgXcQ");
+        assert_eq!(
+            parser.parse_playlist_url(mixed).unwrap(),
+            "PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+        );
+
+        assert!(matches!(
+            parser
+                .parse_playlist_url("https://www.youtube.com/playlist?list=OLAK5uy_kXyZ1234567890"),
+            Err(YdlError::InvalidPlaylistId { .. })
+        ));
+        assert!(matches!(
+            parser.parse_playlist_url("https://www.youtube.com/playlist?list=PL!bad"),
+            Err(YdlError::InvalidPlaylistId { .. })
+        ));
+        assert!(matches!(
+            parser.parse_playlist_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            Err(YdlError::InvalidUrl { .. })
+        ));
+        assert!(
+            parser
+                .parse_playlist_url(
+                    "https://example.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_playlist_position_remaining() {
+        let ids: Vec<String> = ["aaaaaaaaaaa", "bbbbbbbbbbb", "ccccccccccc", "ddddddddddd"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let position = |video_id: Option<&str>, index: Option<usize>| PlaylistPosition {
+            playlist: PlaylistRef {
+                id: "PLtest12345".to_string(),
+            },
+            video_id: video_id.map(str::to_string),
+            index,
+        };
+
+        // The current video wins over a stale index
+        assert_eq!(
+            position(Some("ccccccccccc"), Some(2)).remaining(ids.clone()),
+            vec!["ccccccccccc", "ddddddddddd"]
+        );
+        assert_eq!(
+            position(Some("zzzzzzzzzzz"), Some(2)).remaining(ids.clone()),
+            vec!["bbbbbbbbbbb", "ccccccccccc", "ddddddddddd"]
+        );
+        assert_eq!(position(None, None).remaining(ids.clone()).len(), 4);
+        assert!(position(None, Some(10)).remaining(ids).is_empty());
+    }
+
+    #[test]
+    fn test_classify_url() {
+        let parser = parser();
+
+        assert_eq!(
+            parser
+                .classify_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123")
+                .unwrap(),
+            YouTubeRef::Video("dQw4w9WgXcQ".to_string())
+        );
+        assert_eq!(
+            parser
+                .classify_url(
+                    "https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+                )
+                .unwrap(),
+            YouTubeRef::Playlist(PlaylistRef {
+                id: "PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf".to_string()
+            })
+        );
+        assert_eq!(
+            parser
+                .classify_url("https://www.youtube.com/@rustlang/videos")
+                .unwrap(),
+            YouTubeRef::Channel(ChannelRef::Handle("rustlang".to_string()))
+        );
+        assert_eq!(
+            parser
+                .classify_url("https://www.youtube.com/channel/UCaYhcUwRBNscFNUKTjgPFiA")
+                .unwrap(),
+            YouTubeRef::Channel(ChannelRef::Id("UCaYhcUwRBNscFNUKTjgPFiA".to_string()))
+        );
+        assert_eq!(
+            parser
+                .classify_url(
+                    "https://www.youtube.com/watch_videos?video_ids=dQw4w9WgXcQ,jNQXAC9IVRw"
+                )
+                .unwrap(),
+            YouTubeRef::WatchVideos(vec!["dQw4w9WgXcQ".to_string(), "jNQXAC9IVRw".to_string()])
+        );
+
+        assert!(
+            parser
+                .classify_url("https://www.google.com/@rustlang")
+                .is_err()
+        );
+        assert!(
+            parser
+                .classify_url("https://www.youtube.com/feed/trending")
+                .is_err()
+        );
+        assert!(parser.classify_url("not a url").is_err());
+    }
+
     #[test]
     fn test_invalid_urls() {
         let parser = parser();
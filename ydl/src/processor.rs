@@ -1,11 +1,35 @@
+use crate::LazyDetails;
 use crate::error::{YdlError, YdlResult};
-use crate::types::{ParsedSubtitles, SubtitleEntry, SubtitleType};
+use crate::types::{
+    ParsedSubtitles, Segmentation, SubtitleEntry, SubtitleTrack, SubtitleTrackType, SubtitleType,
+    YdlOptions, format_duration_as_ass, format_duration_as_lrc, format_duration_as_smpte,
+    format_duration_as_vtt,
+};
 use encoding_rs::UTF_8;
 use regex::Regex;
 use std::time::Duration;
 use tracing::{debug, warn};
 
+/// Default pause between cues that starts a new paragraph
+pub const DEFAULT_PARAGRAPH_GAP: Duration = Duration::from_secs(2);
+
+/// XML namespace identifying TTML documents
+const TTML_NAMESPACE: &str = "http://www.w3.org/ns/ttml";
+
+/// Shortest cue written with `strict_output`
+pub const STRICT_MIN_DURATION: Duration = Duration::from_millis(500);
+
+/// Longest cue written with `strict_output`
+pub const STRICT_MAX_DURATION: Duration = Duration::from_secs(10);
+
+/// Shortest cue written with `normalize_timing`, room permitting
+pub const NORMALIZE_MIN_DURATION: Duration = Duration::from_millis(500);
+
+/// Gaps between cues shorter than this are closed with `normalize_timing`
+pub const NORMALIZE_MAX_GAP: Duration = Duration::from_millis(250);
+
 /// Content processor for parsing and converting subtitle formats
+#[derive(Clone)]
 pub struct ContentProcessor {
     /// Regex for parsing SRT timestamps
     srt_time_regex: Regex,
@@ -13,6 +37,27 @@ pub struct ContentProcessor {
     vtt_time_regex: Regex,
     /// Regex for cleaning HTML tags
     html_tag_regex: Regex,
+    /// Regex for `<br>` tags and literal `\n` escapes inside cue text
+    line_break_regex: Regex,
+    /// Regex for the opening `<p t=... d=...>` tag of srv3 cues
+    srv3_cue_regex: Regex,
+    /// Regex for the per-word `<s>` segments inside srv3 cues, capturing the
+    /// optional `t=` offset and the word
+    srv3_word_regex: Regex,
+    /// Regex for legacy timed-text `<text start=... dur=...>` cues
+    xml_text_regex: Regex,
+    /// Regex for TTML `<p>` cues
+    ttml_cue_regex: Regex,
+    /// Regex for `name="value"` attributes in TTML tags
+    xml_attr_regex: Regex,
+    /// Output options
+    options: YdlOptions,
+    /// Video the content belongs to, used for timestamp links
+    video_id: Option<String>,
+    /// Video title, written as an LRC `[ti:]` tag
+    title: Option<String>,
+    /// Video length, written as an LRC `[length:]` tag
+    duration: Option<Duration>,
 }
 
 impl Default for ContentProcessor {
@@ -28,20 +73,69 @@ impl ContentProcessor {
             Regex::new(r"(\d{2}):(\d{2}):(\d{2}),(\d{3}) --> (\d{2}):(\d{2}):(\d{2}),(\d{3})")
                 .expect("Valid SRT time regex");
 
-        let vtt_time_regex =
-            Regex::new(r"(\d{2}):(\d{2}):(\d{2})\.(\d{3}) --> (\d{2}):(\d{2}):(\d{2})\.(\d{3})")
-                .expect("Valid VTT time regex");
+        // The hour component is optional in WebVTT (MM:SS.mmm is valid)
+        let vtt_time_regex = Regex::new(
+            r"(?:(\d{1,9}):)?(\d{2}):(\d{2})\.(\d{3})\s+-->\s+(?:(\d{1,9}):)?(\d{2}):(\d{2})\.(\d{3})",
+        )
+        .expect("Valid VTT time regex");
 
         let html_tag_regex = Regex::new(r"<[^>]*>").expect("Valid HTML tag regex");
+        let line_break_regex = Regex::new(r"(?i)<br\s*/?>|\\n").expect("Valid line break regex");
+
+        let srv3_cue_regex =
+            Regex::new(r#"<p\s+t="(\d+)"(?:\s+d="(\d+)")?[^>]*>"#).expect("Valid srv3 cue regex");
+        let srv3_word_regex = Regex::new(r#"<s(?:\s[^>]*?\bt="(\d+)")?[^>]*>([^<]*)</s>"#)
+            .expect("Valid srv3 word regex");
+        let xml_text_regex =
+            Regex::new(r#"<text start="([^"]+)"(?:\s+dur="([^"]+)")?>([^<]*)</text>"#)
+                .expect("Valid XML text regex");
+        let ttml_cue_regex =
+            Regex::new(r"(?s)<p\b([^>]*)>(.*?)</p>").expect("Valid TTML cue regex");
+        let xml_attr_regex =
+            Regex::new(r#"(?:^|\s)([\w:.-]+)\s*=\s*"([^"]*)""#).expect("Valid XML attribute regex");
 
         Self {
             srt_time_regex,
             vtt_time_regex,
             html_tag_regex,
+            line_break_regex,
+            srv3_cue_regex,
+            srv3_word_regex,
+            xml_text_regex,
+            ttml_cue_regex,
+            xml_attr_regex,
+            options: YdlOptions::default(),
+            video_id: None,
+            title: None,
+            duration: None,
+        }
+    }
+
+    /// Create a content processor that honors the output settings in `options`
+    pub fn with_options(options: YdlOptions) -> Self {
+        Self {
+            options,
+            ..Self::new()
         }
     }
 
-    /// Process raw subtitle content and convert to the desired format
+    /// Set the video ID used when rendering timestamp links
+    pub fn with_video_id(mut self, video_id: &str) -> Self {
+        self.video_id = Some(video_id.to_string());
+        self
+    }
+
+    /// Set the video title and length written as LRC metadata tags
+    pub fn with_metadata(mut self, title: &str, duration: Option<Duration>) -> Self {
+        self.title = Some(title.to_string()).filter(|title| !title.is_empty());
+        self.duration = duration;
+        self
+    }
+
+    /// Process raw subtitle content of a `track_type` track and convert to the desired format
+    ///
+    /// The track type picks the segmentation (see [`YdlOptions::segmentation_for`]);
+    /// auto-generated tracks also have their rolling lines collapsed.
     pub fn process_content(
         &self,
         raw_content: &str,
@@ -49,6 +143,7 @@ impl ContentProcessor {
         language: &str,
         clean_content: bool,
         validate_timing: bool,
+        track_type: &SubtitleTrackType,
     ) -> YdlResult<String> {
         debug!(
             "Processing subtitle content, target format: {:?}",
@@ -59,22 +154,297 @@ impl ContentProcessor {
         let content = self.ensure_utf8(raw_content)?;
 
         // Parse the content to determine the source format and extract entries
-        let parsed = self.parse_subtitle_content(&content, language)?;
+        let parsed = self.parse_timed_content(&content, language)?;
 
         // Validate timing if requested
         if validate_timing {
             self.validate_timing(&parsed.entries)?;
         }
 
+        self.render_parsed(&content, &parsed, target_format, clean_content, track_type)
+    }
+
+    /// Convert entries parsed from `content` (see [`Self::parse_timed_content`]) to `target_format`
+    ///
+    /// The second half of [`Self::process_content`]: `content` is returned unchanged,
+    /// bar trailing blank lines, when it already is in the requested format and
+    /// nothing would alter its cues (see [`YdlOptions::alters_cues`]).
+    fn render_parsed(
+        &self,
+        content: &str,
+        parsed: &ParsedSubtitles,
+        target_format: SubtitleType,
+        clean_content: bool,
+        track_type: &SubtitleTrackType,
+    ) -> YdlResult<String> {
+        let segmentation = self.options.segmentation_for(track_type);
+
+        // Raw means "whatever the source was", so render in the source's own format
+        let source_format = parsed.original_format;
+        let target_format = resolve_target_format(target_format, source_format);
+
+        // Nothing to convert: the source is already in the requested format, and
+        // neither the options nor the track's own cues call for rewriting it
+        let has_empty_cue = || parsed.entries.iter().any(|e| e.text.trim().is_empty());
+        if target_format == source_format
+            && !clean_content
+            && segmentation == Segmentation::Original
+            && *track_type != SubtitleTrackType::AutoGenerated
+            && !self.options.alters_cues()
+            && !(self.options.drop_empty_cues && has_empty_cue())
+        {
+            debug!("Source already in {:?}, skipping conversion", source_format);
+            return Ok(end_with_single_newline(content.to_string()));
+        }
+
         // Clean content if requested
         let entries = if clean_content {
-            self.clean_subtitle_entries(parsed.entries)
+            self.clean_subtitle_entries(parsed.entries.clone())
+        } else {
+            parsed.entries.clone()
+        };
+
+        let entries = if *track_type == SubtitleTrackType::AutoGenerated {
+            self.dedupe_rolling(entries)
+        } else {
+            entries
+        };
+
+        // Only JSON and ASS have a place for the speaker; elsewhere the label stays
+        // in the text, so it is not lost
+        let entries = if !self.options.detect_speakers {
+            entries
+        } else if matches!(target_format, SubtitleType::Json | SubtitleType::Ass) {
+            self.detect_speakers(entries)
+        } else {
+            label_speakers(self.detect_speakers(entries))
+        };
+
+        let entries = self.apply_text_transform(entries);
+        let entries = self.drop_empty_entries(entries);
+        let entries = self.segment_entries(entries, segmentation);
+        let entries = if self.options.strict_output {
+            strict_entries(entries)
         } else {
-            parsed.entries
+            entries
+        };
+        let entries = if self.options.normalize_timing {
+            self.normalize_durations(entries, NORMALIZE_MIN_DURATION, NORMALIZE_MAX_GAP)
+        } else {
+            entries
+        };
+        let entries = match self.options.max_line_length {
+            Some(max_length) if matches!(target_format, SubtitleType::Srt | SubtitleType::Vtt) => {
+                entries
+                    .into_iter()
+                    .map(|mut entry| {
+                        entry.text = wrap_text(&entry.text, max_length, self.options.max_lines);
+                        entry
+                    })
+                    .collect()
+            }
+            _ => entries,
         };
 
         // Convert to target format
-        self.convert_to_format(&entries, target_format, language)
+        self.convert_to_format(&entries, target_format, &parsed.language)
+    }
+
+    /// Collapse the rolling lines of auto-generated captions
+    ///
+    /// As words stream in, YouTube repeats the line so far in each new cue ("the
+    /// quick", "the quick brown", ...). When an entry's text is a whole-word prefix of
+    /// the next one, the two become a single entry with the longer text, spanning
+    /// both.
+    pub fn dedupe_rolling(&self, entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+        let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut result: Vec<SubtitleEntry> = Vec::with_capacity(entries.len());
+
+        for mut entry in entries {
+            if let Some(prev) = result.last() {
+                let prev_text = normalize(&prev.text);
+                let text = normalize(&entry.text);
+                let continues = text
+                    .strip_prefix(prev_text.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '));
+                if !prev_text.is_empty() && continues {
+                    let prev = result.pop().unwrap();
+                    entry.start = prev.start;
+                    entry.end = entry.end.max(prev.end);
+                }
+            }
+            result.push(entry);
+        }
+
+        result
+    }
+
+    /// Re-segment cues according to `mode`
+    pub fn segment_entries(
+        &self,
+        entries: Vec<SubtitleEntry>,
+        mode: Segmentation,
+    ) -> Vec<SubtitleEntry> {
+        match mode {
+            Segmentation::Original => entries,
+            Segmentation::NonOverlapping => clamp_overlaps(entries),
+            Segmentation::Sentences => clamp_overlaps(merge_sentences(entries)),
+        }
+    }
+
+    /// Lengthen entries shorter than `min_dur` and close gaps shorter than `max_gap`
+    ///
+    /// Only entry ends move, and never past the start of the following entry, so no
+    /// overlap is created and the order is kept. An entry that cannot reach `min_dur`
+    /// before the next one starts ends where the next begins.
+    pub fn normalize_durations(
+        &self,
+        mut entries: Vec<SubtitleEntry>,
+        min_dur: Duration,
+        max_gap: Duration,
+    ) -> Vec<SubtitleEntry> {
+        for i in 0..entries.len() {
+            let next_start = entries.get(i + 1).map(|next| next.start);
+            let entry = &mut entries[i];
+
+            let mut end = entry.end.max(entry.start + min_dur);
+            if let Some(next_start) = next_start {
+                if next_start >= entry.end && next_start - entry.end < max_gap {
+                    end = end.max(next_start);
+                }
+                end = end.min(next_start.max(entry.end));
+            }
+            entry.end = end;
+        }
+        entries
+    }
+
+    /// Move every entry `offset` milliseconds later (earlier if negative)
+    ///
+    /// Times saturate at zero. An entry pushed entirely below zero keeps a 1ms
+    /// duration at the start of the track, so timing never becomes invalid.
+    pub fn shift_timing(&self, entries: Vec<SubtitleEntry>, offset: i64) -> Vec<SubtitleEntry> {
+        let shift = |time: Duration| {
+            let millis = time.as_millis() as i64 + offset;
+            Duration::from_millis(millis.max(0) as u64)
+        };
+        let min_duration = Duration::from_millis(1);
+
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                entry.end = shift(entry.end).max(min_duration);
+                entry.start = shift(entry.start).min(entry.end - min_duration);
+                if let Some(words) = &mut entry.word_timings {
+                    for (start, _) in words.iter_mut() {
+                        *start = shift(*start);
+                    }
+                }
+                entry
+            })
+            .collect()
+    }
+
+    /// Parse UTF-8 `content` and apply the timing options (trimming, shifting, start and rebase)
+    fn parse_timed_content(&self, content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
+        let mut parsed = self.parse_subtitle_content(content, language)?;
+        if let Some(gap) = self.options.trim_silence {
+            parsed.trim_silence(true, true, gap);
+        }
+        if self.options.shift_ms != 0 {
+            parsed.entries = self.shift_timing(parsed.entries, self.options.shift_ms);
+        }
+        if let Some(start) = self.options.start_at {
+            parsed.entries.retain(|entry| entry.start >= start);
+        }
+        if let Some(start) = self.options.rebase {
+            parsed.rebase(start);
+        }
+        Ok(parsed)
+    }
+
+    /// Parse raw subtitle content into entries without converting it
+    pub fn parse_content(&self, raw_content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
+        let content = self.ensure_utf8(raw_content)?;
+        let parsed = self.parse_timed_content(&content, language)?;
+
+        let format = parsed.original_format;
+        let mut entries = parsed.entries;
+        if self.options.clean_content {
+            entries = self.clean_subtitle_entries(entries);
+        }
+        if self.options.detect_speakers {
+            entries = self.detect_speakers(entries);
+        }
+        entries = self.apply_text_transform(entries);
+        entries = self.drop_empty_entries(entries);
+
+        Ok(ParsedSubtitles::new(entries, parsed.language).with_format(format))
+    }
+
+    /// Run the user's `text_transform`, if any, over every cue
+    fn apply_text_transform(&self, entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+        let Some(transform) = &self.options.text_transform else {
+            return entries;
+        };
+
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                entry.text = transform.apply(&entry.text);
+                entry
+            })
+            .collect()
+    }
+
+    /// Remove entries with blank text, if `drop_empty_cues` is set
+    ///
+    /// When anything is removed and the remaining identifiers are all numeric, they
+    /// are renumbered from 1 so they stay contiguous.
+    fn drop_empty_entries(&self, entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+        if !self.options.drop_empty_cues {
+            return entries;
+        }
+
+        let total = entries.len();
+        let mut kept: Vec<SubtitleEntry> = entries
+            .into_iter()
+            .filter(|entry| !entry.text.trim().is_empty())
+            .collect();
+        if kept.len() == total {
+            return kept;
+        }
+        debug!("Dropped {} empty cue(s)", total - kept.len());
+
+        let numeric_ids = kept.iter().all(|entry| {
+            entry
+                .id
+                .as_deref()
+                .is_some_and(|id| id.parse::<u64>().is_ok())
+        });
+        if numeric_ids {
+            for (i, entry) in kept.iter_mut().enumerate() {
+                entry.id = Some((i + 1).to_string());
+            }
+        }
+        kept
+    }
+
+    /// Split leading speaker labels off cue text into `SubtitleEntry::speaker`
+    ///
+    /// Recognizes `>> NAME: text`, `- Name: text` and bare upper-case `NAME: text`.
+    /// A lone `>>` marks an unnamed change of speaker; it is stripped from the text
+    /// and leaves the speaker unset.
+    pub fn detect_speakers(&self, entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                let (speaker, text) = split_speaker(&entry.text);
+                entry.text = text;
+                entry.speaker = speaker.or(entry.speaker);
+                entry
+            })
+            .collect()
     }
 
     /// Ensure content is valid UTF-8
@@ -112,8 +482,13 @@ impl ContentProcessor {
         debug!("Parsing subtitle content, {} bytes", content.len());
 
         // Try different parsers based on content characteristics
-        if content.contains("WEBVTT") {
+        if content.trim_start().starts_with('{') && content.contains("\"events\"") {
+            self.parse_json3_content(content, language)
+        } else if content.contains("WEBVTT") {
             self.parse_vtt_content(content, language)
+        } else if content.contains(TTML_NAMESPACE) {
+            // TTML is XML too, so it must be recognized before YouTube's timed-text XML
+            self.parse_ttml_content(content, language)
         } else if content.contains("<?xml") || content.contains("<transcript") {
             self.parse_youtube_xml_content(content, language)
         } else if self.srt_time_regex.is_match(content) {
@@ -130,94 +505,223 @@ impl ContentProcessor {
     /// Parse SRT format content
     fn parse_srt_content(&self, content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
         let mut entries = Vec::new();
-        let blocks = content.split("\n\n");
-
-        for block in blocks {
-            let block = block.trim();
-            if block.is_empty() {
-                continue;
+        let mut skipped = 0;
+
+        for (line, block) in Self::srt_blocks(content) {
+            match self.parse_srt_block(block) {
+                Some(Ok(entry)) => entries.push(entry),
+                Some(Err(index)) => {
+                    let bad_line = block.lines().nth(index).unwrap_or(block);
+                    self.skip_malformed_block("SRT", line + index, bad_line, &mut skipped)?
+                }
+                None => {}
             }
+        }
 
-            let lines: Vec<&str> = block.lines().collect();
-            if lines.len() < 3 {
-                continue;
-            }
+        if skipped > 0 {
+            warn!("Skipped {} malformed SRT block(s)", skipped);
+        }
 
-            // Skip sequence number (first line)
-            let timing_line = lines[1];
-            let text_lines = &lines[2..];
+        if entries.is_empty() {
+            return Err(YdlError::subtitle_parsing("No valid SRT entries found"));
+        }
 
-            if let Some(captures) = self.srt_time_regex.captures(timing_line) {
-                let start = self.parse_srt_time(&captures, 1)?;
-                let end = self.parse_srt_time(&captures, 5)?;
-                let text = text_lines.join("\n");
+        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Srt))
+    }
 
-                entries.push(SubtitleEntry::new(start, end, text));
+    /// Parse SRT content lazily, yielding each entry as its block is scanned
+    ///
+    /// Memory stays bounded by one block however long the transcript is, so entries
+    /// can be converted and written as they arrive (see [`write_srt`](Self::write_srt)).
+    /// Malformed blocks yield an error, or are skipped with `lenient_blocks`. Unlike
+    /// full parsing, entries come in file order rather than sorted by start time.
+    pub fn parse_srt_iter<'a>(
+        &'a self,
+        content: &'a str,
+    ) -> impl Iterator<Item = YdlResult<SubtitleEntry>> + 'a {
+        Self::srt_blocks(content).filter_map(move |(line, block)| {
+            let index = match self.parse_srt_block(block)? {
+                Ok(entry) => return Some(Ok(entry)),
+                Err(index) => index,
+            };
+            if self.options.lenient_blocks {
+                debug!("Skipping malformed SRT block at line {}: {}", line, block);
+                return None;
             }
+            let bad_line = block.lines().nth(index).unwrap_or(block);
+            Some(Err(Self::malformed_block_error(
+                "SRT",
+                line + index,
+                bad_line,
+            )))
+        })
+    }
+
+    /// Split SRT content on blank lines into trimmed blocks, each with its 1-based starting line
+    fn srt_blocks(content: &str) -> impl Iterator<Item = (usize, &str)> {
+        let mut line = 1;
+        content.split("\n\n").map(move |raw| {
+            let leading = &raw[..raw.len() - raw.trim_start().len()];
+            let start = line + leading.matches('\n').count();
+            line += raw.matches('\n').count() + 2;
+            (start, raw.trim())
+        })
+    }
+
+    /// Parse one trimmed SRT block: `None` if it is empty, `Some(Err(index))` if
+    /// malformed, where `index` is the offending line within the block
+    fn parse_srt_block(&self, block: &str) -> Option<Result<SubtitleEntry, usize>> {
+        if block.is_empty() {
+            return None;
         }
 
-        if entries.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "No valid SRT entries found".to_string(),
-            });
+        let lines: Vec<&str> = block.lines().collect();
+        if lines.len() < 3 {
+            return Some(Err(if lines.len() < 2 { 0 } else { 1 }));
         }
 
-        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Srt))
+        // Skip sequence number (first line)
+        let Some(captures) = self.srt_time_regex.captures(lines[1]) else {
+            return Some(Err(1));
+        };
+        let (Ok(start), Ok(end)) = (
+            self.parse_srt_time(&captures, 1),
+            self.parse_srt_time(&captures, 5),
+        ) else {
+            return Some(Err(1));
+        };
+
+        Some(Ok(SubtitleEntry::new(start, end, lines[2..].join("\n"))))
     }
 
     /// Parse VTT format content
+    ///
+    /// Works block by block: the `WEBVTT` header and `NOTE`, `STYLE` and `REGION`
+    /// blocks are skipped in full, an optional cue identifier may precede the
+    /// timing line, and cues whose start is not before their end are dropped.
     fn parse_vtt_content(&self, content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
         let mut entries = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
-        let mut i = 0;
-
-        // Skip WEBVTT header and metadata
-        while i < lines.len() {
-            let line = lines[i].trim();
-            if line.is_empty() || line.starts_with("WEBVTT") || line.starts_with("NOTE") {
-                i += 1;
+        let mut skipped = 0;
+
+        for (line, block) in Self::split_blocks(content) {
+            let first = block[0];
+            if first.starts_with("NOTE") || first == "STYLE" || first == "REGION" {
                 continue;
             }
-            break;
-        }
 
-        // Parse cue blocks
-        while i < lines.len() {
-            let line = lines[i].trim();
+            // A cue's timing line may be preceded by an identifier (or, for a
+            // header without a blank line after it, by header lines)
+            let Some(timing_index) = block
+                .iter()
+                .position(|line| self.vtt_time_regex.is_match(line))
+            else {
+                // The header is the only block expected to have no timing line
+                if !first.starts_with("WEBVTT") {
+                    self.skip_malformed_block("VTT", line, first, &mut skipped)?;
+                }
+                continue;
+            };
+
+            let captures = self
+                .vtt_time_regex
+                .captures(block[timing_index])
+                .expect("timing line matched above");
+            let (Ok(start), Ok(end)) = (
+                self.parse_vtt_time(&captures, 1),
+                self.parse_vtt_time(&captures, 5),
+            ) else {
+                let timing = block[timing_index];
+                self.skip_malformed_block("VTT", line + timing_index, timing, &mut skipped)?;
+                continue;
+            };
 
-            if line.is_empty() {
-                i += 1;
+            if start >= end {
+                warn!(
+                    "Skipping VTT cue with invalid timing: {}",
+                    block[timing_index]
+                );
                 continue;
             }
 
-            // Check if this line contains timing
-            if let Some(captures) = self.vtt_time_regex.captures(line) {
-                let start = self.parse_vtt_time(&captures, 1)?;
-                let end = self.parse_vtt_time(&captures, 5)?;
+            let text = block[timing_index + 1..].join("\n");
+            let mut entry = SubtitleEntry::new(start, end, text);
+            if timing_index == 1 && !first.starts_with("WEBVTT") {
+                entry = entry.with_id(first.trim().to_string());
+            }
+            entries.push(entry);
+        }
 
-                // Collect text lines
-                i += 1;
-                let mut text_lines = Vec::new();
-                while i < lines.len() && !lines[i].trim().is_empty() {
-                    text_lines.push(lines[i]);
-                    i += 1;
-                }
+        if skipped > 0 {
+            warn!("Skipped {} malformed VTT block(s)", skipped);
+        }
 
-                let text = text_lines.join("\n");
-                entries.push(SubtitleEntry::new(start, end, text));
+        if entries.is_empty() {
+            return Err(YdlError::subtitle_parsing("No valid VTT entries found"));
+        }
+
+        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Vtt))
+    }
+
+    /// Count a malformed block when lenient, or fail the parse with it when strict
+    fn skip_malformed_block(
+        &self,
+        format: &str,
+        line: usize,
+        context: &str,
+        skipped: &mut usize,
+    ) -> YdlResult<()> {
+        if !self.options.lenient_blocks {
+            return Err(Self::malformed_block_error(format, line, context));
+        }
+
+        debug!(
+            "Skipping malformed {} block at line {}: {}",
+            format, line, context
+        );
+        *skipped += 1;
+        Ok(())
+    }
+
+    /// Parse error for a malformed block, pointing at `line` and quoting (part of) it
+    fn malformed_block_error(format: &str, line: usize, context: &str) -> YdlError {
+        const MAX_CONTEXT_CHARS: usize = 60;
+
+        let context = match context.char_indices().nth(MAX_CONTEXT_CHARS) {
+            Some((end, _)) => format!("{}...", &context[..end]),
+            None => context.to_string(),
+        };
+        YdlError::SubtitleParsing {
+            message: format!("Malformed {} block", format),
+            line: Some(line),
+            context: Some(context),
+        }
+    }
+
+    /// Split content into blocks of non-blank lines with trailing whitespace removed,
+    /// each with the 1-based line number it starts on
+    fn split_blocks(content: &str) -> Vec<(usize, Vec<&str>)> {
+        let mut blocks = Vec::new();
+        let mut current = Vec::new();
+        let mut start = 1;
+
+        for (index, line) in content.lines().map(str::trim_end).enumerate() {
+            if line.trim().is_empty() {
+                if !current.is_empty() {
+                    blocks.push((start, std::mem::take(&mut current)));
+                }
             } else {
-                // Skip cue identifier line
-                i += 1;
+                if current.is_empty() {
+                    start = index + 1;
+                }
+                current.push(line);
             }
         }
 
-        if entries.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "No valid VTT entries found".to_string(),
-            });
+        if !current.is_empty() {
+            blocks.push((start, current));
         }
 
-        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Vtt))
+        blocks
     }
 
     /// Parse YouTube XML transcript format
@@ -228,23 +732,22 @@ impl ContentProcessor {
     ) -> YdlResult<ParsedSubtitles> {
         let mut entries = Vec::new();
 
-        // Try the newer srv3 format first (uses <p> tags)
-        let p_regex =
-            Regex::new(r#"<p\s+t="(\d+)"(?:\s+d="(\d+)")?[^>]*>(.*?)</p>"#).map_err(|e| {
-                YdlError::SubtitleParsing {
-                    message: format!("Invalid XML regex: {}", e),
-                }
-            })?;
-
-        let s_regex =
-            Regex::new(r"<s[^>]*>([^<]*)</s>").map_err(|e| YdlError::SubtitleParsing {
-                message: format!("Invalid s tag regex: {}", e),
-            })?;
+        // Try the newer srv3 format first (uses <p> tags). Only the opening tag goes
+        // through the regex: capturing the cue body as well is several times slower
+        // on long tracks. A cue must close on the line it opened on.
+        let mut position = 0;
+        while let Some(captures) = self.srv3_cue_regex.captures_at(content, position) {
+            let tag = captures.get(0).unwrap();
+            let body = &content[tag.end()..];
+            let Some(close) = body.find("</p>").filter(|&i| !body[..i].contains('\n')) else {
+                position = tag.start() + 1;
+                continue;
+            };
+            position = tag.end() + close + "</p>".len();
 
-        for captures in p_regex.captures_iter(content) {
             let start_str = captures.get(1).unwrap().as_str();
             let duration_str = captures.get(2).map(|m| m.as_str()).unwrap_or("1000");
-            let inner_content = captures.get(3).unwrap().as_str();
+            let inner_content = &body[..close];
 
             // Parse start time (in milliseconds for srv3 format)
             let start_ms: u64 = start_str.parse().unwrap_or(0);
@@ -253,12 +756,26 @@ impl ContentProcessor {
             let start = Duration::from_millis(start_ms);
             let end = Duration::from_millis(start_ms + duration_ms);
 
-            // Extract text from <s> tags or use the inner content directly
+            // Extract text from <s> tags or use the inner content directly. Words are
+            // offset from the cue start; the first one usually has no `t=` of its own.
+            let mut word_timings = Vec::new();
+            let mut timed = false;
             let text = if inner_content.contains("<s") {
                 let mut words = Vec::new();
-                for s_capture in s_regex.captures_iter(inner_content) {
-                    if let Some(word) = s_capture.get(1) {
+                for s_capture in self.srv3_word_regex.captures_iter(inner_content) {
+                    let offset_ms = s_capture
+                        .get(1)
+                        .and_then(|t| t.as_str().parse::<u64>().ok());
+                    timed |= offset_ms.is_some();
+                    if let Some(word) = s_capture.get(2) {
                         words.push(word.as_str());
+                        let word = html_escape::decode_html_entities(word.as_str());
+                        if !word.trim().is_empty() {
+                            word_timings.push((
+                                start + Duration::from_millis(offset_ms.unwrap_or(0)),
+                                word.trim().to_string(),
+                            ));
+                        }
                     }
                 }
                 words.join("")
@@ -274,19 +791,18 @@ impl ContentProcessor {
 
             // Skip empty entries
             if !decoded_text.is_empty() {
-                entries.push(SubtitleEntry::new(start, end, decoded_text));
+                let entry = SubtitleEntry::new(start, end, decoded_text);
+                entries.push(if timed {
+                    entry.with_word_timings(word_timings)
+                } else {
+                    entry
+                });
             }
         }
 
         // If no <p> tags found, try the older <text> format
         if entries.is_empty() {
-            let text_regex =
-                Regex::new(r#"<text start="([^"]+)"(?:\s+dur="([^"]+)")?>([^<]*)</text>"#)
-                    .map_err(|e| YdlError::SubtitleParsing {
-                        message: format!("Invalid XML regex: {}", e),
-                    })?;
-
-            for captures in text_regex.captures_iter(content) {
+            for captures in self.xml_text_regex.captures_iter(content) {
                 let start_str = captures.get(1).unwrap().as_str();
                 let duration_str = captures.get(2).map(|m| m.as_str()).unwrap_or("1");
                 let text = captures.get(3).unwrap().as_str();
@@ -306,9 +822,114 @@ impl ContentProcessor {
         }
 
         if entries.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "No valid XML transcript entries found".to_string(),
-            });
+            return Err(YdlError::subtitle_parsing(
+                "No valid XML transcript entries found",
+            ));
+        }
+
+        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Raw))
+    }
+
+    /// Parse YouTube's timed-text JSON (`fmt=json3`)
+    ///
+    /// Each event with `segs` is a cue starting at `tStartMs` and lasting `dDurationMs`
+    /// (one second if missing), its text the segments' `utf8` strings joined. Events
+    /// that carry only window styling or a line break are skipped.
+    fn parse_json3_content(&self, content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
+        let document: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| YdlError::subtitle_parsing(format!("Invalid json3 content: {}", e)))?;
+
+        let mut entries = Vec::new();
+        for event in document["events"].as_array().into_iter().flatten() {
+            let Some(segs) = event["segs"].as_array() else {
+                continue;
+            };
+            let text: String = segs.iter().filter_map(|seg| seg["utf8"].as_str()).collect();
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            let start = Duration::from_millis(event["tStartMs"].as_u64().unwrap_or(0));
+            let duration = event["dDurationMs"]
+                .as_u64()
+                .map_or(Duration::from_secs(1), Duration::from_millis);
+            entries.push(SubtitleEntry::new(
+                start,
+                start + duration,
+                text.to_string(),
+            ));
+        }
+
+        if entries.is_empty() {
+            return Err(YdlError::subtitle_parsing("No valid json3 events found"));
+        }
+
+        Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Raw))
+    }
+
+    /// Parse W3C TTML (`<tt xmlns="http://www.w3.org/ns/ttml">`) content
+    ///
+    /// Cues come from `<p begin=... end=...>` (or `dur=`) elements. Times may be clock
+    /// times (`HH:MM:SS.mmm`, `HH:MM:SS:frames`) or offsets (`1.5s`, `1500ms`, `90f`,
+    /// `10000t`), using the document's `ttp:frameRate` and `ttp:tickRate`.
+    fn parse_ttml_content(&self, content: &str, language: &str) -> YdlResult<ParsedSubtitles> {
+        let attr = |attrs: &str, name: &str| -> Option<String> {
+            self.xml_attr_regex
+                .captures_iter(attrs)
+                .find(|c| &c[1] == name)
+                .map(|c| c[2].trim().to_string())
+        };
+
+        let root = content
+            .find("<tt")
+            .and_then(|start| {
+                content[start..]
+                    .find('>')
+                    .map(|end| &content[start..start + end])
+            })
+            .unwrap_or("");
+        let frame_rate = attr(root, "ttp:frameRate")
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .unwrap_or(30.0);
+        let tick_rate = attr(root, "ttp:tickRate")
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .unwrap_or(1.0);
+
+        let mut entries = Vec::new();
+        for captures in self.ttml_cue_regex.captures_iter(content) {
+            let attrs = &captures[1];
+            let Some(start) = attr(attrs, "begin")
+                .and_then(|begin| parse_ttml_time(&begin, frame_rate, tick_rate))
+            else {
+                continue;
+            };
+            let end = attr(attrs, "end")
+                .and_then(|end| parse_ttml_time(&end, frame_rate, tick_rate))
+                .or_else(|| {
+                    attr(attrs, "dur")
+                        .and_then(|dur| parse_ttml_time(&dur, frame_rate, tick_rate))
+                        .map(|dur| start + dur)
+                })
+                .unwrap_or(start + Duration::from_secs(1));
+
+            // <br/> separates lines; any other markup (<span> styling) is dropped
+            let text = self.line_break_regex.replace_all(&captures[2], "\n");
+            let text = self.html_tag_regex.replace_all(&text, "");
+            let text = html_escape::decode_html_entities(&text)
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            if !text.is_empty() {
+                entries.push(SubtitleEntry::new(start, end, text));
+            }
+        }
+
+        if entries.is_empty() {
+            return Err(YdlError::subtitle_parsing("No valid TTML cues found"));
         }
 
         Ok(ParsedSubtitles::new(entries, language.to_string()).with_format(SubtitleType::Raw))
@@ -324,9 +945,7 @@ impl ContentProcessor {
         let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
 
         if lines.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "No content found in plain text".to_string(),
-            });
+            return Err(YdlError::subtitle_parsing("No content found in plain text"));
         }
 
         let mut entries = Vec::new();
@@ -353,33 +972,25 @@ impl ContentProcessor {
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid SRT hour format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid SRT hour format"))?;
         let minutes: u64 = captures
             .get(start_group + 1)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid SRT minute format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid SRT minute format"))?;
         let seconds: u64 = captures
             .get(start_group + 2)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid SRT second format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid SRT second format"))?;
         let millis: u64 = captures
             .get(start_group + 3)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid SRT millisecond format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid SRT millisecond format"))?;
 
         Ok(Duration::from_millis(
             hours * 3_600_000 + minutes * 60_000 + seconds * 1000 + millis,
@@ -394,36 +1005,27 @@ impl ContentProcessor {
     ) -> YdlResult<Duration> {
         let hours: u64 = captures
             .get(start_group)
-            .unwrap()
-            .as_str()
+            .map_or("0", |m| m.as_str())
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid VTT hour format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid VTT hour format"))?;
         let minutes: u64 = captures
             .get(start_group + 1)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid VTT minute format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid VTT minute format"))?;
         let seconds: u64 = captures
             .get(start_group + 2)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid VTT second format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid VTT second format"))?;
         let millis: u64 = captures
             .get(start_group + 3)
             .unwrap()
             .as_str()
             .parse()
-            .map_err(|_| YdlError::SubtitleParsing {
-                message: "Invalid VTT millisecond format".to_string(),
-            })?;
+            .map_err(|_| YdlError::subtitle_parsing("Invalid VTT millisecond format"))?;
 
         Ok(Duration::from_millis(
             hours * 3_600_000 + minutes * 60_000 + seconds * 1000 + millis,
@@ -435,11 +1037,27 @@ impl ContentProcessor {
         entries
             .into_iter()
             .map(|mut entry| {
+                // Line breaks encoded as <br> or literal "\n" escapes must survive tag removal
+                entry.text = self
+                    .line_break_regex
+                    .replace_all(&entry.text, "\n")
+                    .to_string();
+
                 // Remove HTML tags
                 entry.text = self.html_tag_regex.replace_all(&entry.text, "").to_string();
 
-                // Normalize whitespace
-                entry.text = entry.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                // Normalize whitespace, keeping line structure if requested
+                entry.text = if self.options.preserve_line_breaks {
+                    entry
+                        .text
+                        .lines()
+                        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                        .filter(|line| !line.is_empty())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                } else {
+                    entry.text.split_whitespace().collect::<Vec<_>>().join(" ")
+                };
 
                 // Remove common subtitle formatting
                 entry.text = entry
@@ -466,9 +1084,10 @@ impl ContentProcessor {
         for (i, entry) in entries.iter().enumerate() {
             // Check that start < end
             if entry.start >= entry.end {
-                return Err(YdlError::SubtitleParsing {
-                    message: format!("Invalid timing at entry {}: start >= end", i + 1),
-                });
+                return Err(YdlError::subtitle_parsing(format!(
+                    "Invalid timing at entry {}: start >= end",
+                    i + 1
+                )));
             }
 
             // Check for reasonable duration (not too short or too long)
@@ -499,7 +1118,7 @@ impl ContentProcessor {
     }
 
     /// Convert subtitle entries to target format
-    fn convert_to_format(
+    pub fn convert_to_format(
         &self,
         entries: &[SubtitleEntry],
         format: SubtitleType,
@@ -508,8 +1127,20 @@ impl ContentProcessor {
         match format {
             SubtitleType::Srt => self.to_srt_format(entries),
             SubtitleType::Vtt => self.to_vtt_format(entries),
-            SubtitleType::Txt => self.to_txt_format(entries),
+            SubtitleType::Txt => match &self.video_id {
+                Some(video_id) if self.options.timestamp_links => {
+                    self.to_txt_with_timestamp_links(entries, video_id)
+                }
+                _ => match self.options.txt_paragraphs {
+                    Some(gap) => self.to_txt_paragraphs(entries, gap),
+                    None => self.to_txt_format(entries),
+                },
+            },
             SubtitleType::Json => self.to_json_format(entries, language),
+            SubtitleType::Scc => self.to_scc_format(entries),
+            SubtitleType::Ass => self.to_ass_format(entries, language),
+            SubtitleType::Lrc => self.to_lrc_format(entries),
+            SubtitleType::Ttml => self.to_ttml_format(entries, language),
             SubtitleType::Raw => {
                 // For raw format, return as is if we have entries
                 if entries.is_empty() {
@@ -525,189 +1156,2191 @@ impl ContentProcessor {
     fn to_srt_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
         let mut result = String::new();
 
-        for (i, entry) in entries.iter().enumerate() {
-            result.push_str(&format!("{}\n", i + 1));
-            result.push_str(&format!(
-                "{} --> {}\n",
-                entry.start_as_srt(),
-                entry.end_as_srt()
-            ));
-            result.push_str(&entry.text);
-            result.push_str("\n\n");
+        let mut numbering = SrtNumbering::default();
+        for entry in entries {
+            result.push_str(&self.srt_cue(numbering.next(entry), entry));
         }
 
-        Ok(result)
+        Ok(end_with_single_newline(result))
     }
 
-    /// Convert to VTT format
-    fn to_vtt_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
-        let mut result = String::from("WEBVTT\n\n");
+    /// One SRT cue, with a trailing blank line
+    fn srt_cue(&self, number: u64, entry: &SubtitleEntry) -> String {
+        let (timecode, fps) = (self.options.timecode, self.options.frame_rate);
+        format!(
+            "{}\n{} --> {}\n{}\n\n",
+            number,
+            timecode.format(entry.start, fps),
+            timecode.format(entry.end, fps),
+            entry.text
+        )
+    }
 
+    /// Write `entries` to `out` as SRT as they arrive, numbered like
+    /// [`convert_to_format`](Self::convert_to_format) numbers them
+    ///
+    /// Only one entry is held at a time. Returns the number of cues written.
+    pub fn write_srt<E: std::borrow::Borrow<SubtitleEntry>>(
+        &self,
+        entries: impl IntoIterator<Item = E>,
+        out: &mut impl std::io::Write,
+    ) -> YdlResult<usize> {
+        let mut count = 0;
+        let mut numbering = SrtNumbering::default();
         for entry in entries {
-            result.push_str(&format!(
-                "{} --> {}\n",
-                entry.start_as_vtt(),
-                entry.end_as_vtt()
-            ));
-            result.push_str(&entry.text);
-            result.push_str("\n\n");
+            let entry = entry.borrow();
+            // Blank lines go between cues, so the output ends with a single newline
+            let cue = self.srt_cue(numbering.next(entry), entry);
+            if count > 0 {
+                out.write_all(b"\n")?;
+            }
+            out.write_all(cue.trim_end_matches('\n').as_bytes())?;
+            out.write_all(b"\n")?;
+            count += 1;
         }
+        Ok(count)
+    }
 
-        Ok(result)
+    /// Write `entries` to `out` as WebVTT as they arrive
+    ///
+    /// Only one entry is held at a time. Returns the number of cues written.
+    pub fn write_vtt<E: std::borrow::Borrow<SubtitleEntry>>(
+        &self,
+        entries: impl IntoIterator<Item = E>,
+        out: &mut impl std::io::Write,
+    ) -> YdlResult<usize> {
+        out.write_all(b"WEBVTT\n")?;
+        if let Some(mpegts) = self.options.vtt_timestamp_map {
+            // HLS maps the segment's local cue times onto the MPEG-TS presentation clock
+            writeln!(out, "X-TIMESTAMP-MAP=MPEGTS:{},LOCAL:00:00:00.000", mpegts)?;
+        }
+
+        let mut count = 0;
+        for entry in entries {
+            let entry = entry.borrow();
+            out.write_all(b"\n")?;
+            if let Some(id) = &entry.id {
+                writeln!(out, "{}", id)?;
+            }
+            writeln!(out, "{} --> {}", entry.start_as_vtt(), entry.end_as_vtt())?;
+            writeln!(out, "{}", entry.text.trim_end())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Convert to VTT format
+    fn to_vtt_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
+        let mut result = Vec::new();
+        self.write_vtt(entries, &mut result)?;
+        let result = String::from_utf8(result).expect("VTT output is built from strings");
+        Ok(end_with_single_newline(result))
     }
 
     /// Convert to plain text format
     fn to_txt_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
-        let texts: Vec<String> = entries.iter().map(|e| e.text.clone()).collect();
+        // Each cue is one line of text, so multi-line cues are joined with spaces
+        let texts: Vec<String> = entries.iter().map(|e| e.text.replace('\n', " ")).collect();
         Ok(texts.join("\n"))
     }
 
-    /// Convert to JSON format
-    fn to_json_format(&self, entries: &[SubtitleEntry], language: &str) -> YdlResult<String> {
-        let json_entries: Vec<serde_json::Value> = entries
-            .iter()
-            .map(|entry| {
-                serde_json::json!({
-                    "start": entry.start.as_secs_f64(),
-                    "end": entry.end.as_secs_f64(),
-                    "text": entry.text
-                })
+    /// Convert to plain text with a `https://youtu.be/ID?t=SECONDS` link before each paragraph
+    pub fn to_txt_with_timestamp_links(
+        &self,
+        entries: &[SubtitleEntry],
+        video_id: &str,
+    ) -> YdlResult<String> {
+        let paragraphs: Vec<String> = self
+            .group_paragraphs(entries, DEFAULT_PARAGRAPH_GAP)
+            .into_iter()
+            .map(|paragraph| {
+                format!(
+                    "https://youtu.be/{}?t={} {}",
+                    video_id,
+                    paragraph[0].start.as_secs(),
+                    paragraph_text(paragraph)
+                )
             })
             .collect();
 
-        let result = serde_json::json!({
-            "language": language,
-            "entries": json_entries
+        Ok(paragraphs.join("\n\n"))
+    }
+
+    /// Convert to plain text with consecutive cues merged into paragraphs
+    ///
+    /// Paragraphs are separated by blank lines; see [`Self::group_paragraphs`] for
+    /// where one ends. [`DEFAULT_PARAGRAPH_GAP`] suits most videos.
+    pub fn to_txt_paragraphs(
+        &self,
+        entries: &[SubtitleEntry],
+        gap_threshold: Duration,
+    ) -> YdlResult<String> {
+        let paragraphs: Vec<String> = self
+            .group_paragraphs(entries, gap_threshold)
+            .into_iter()
+            .map(paragraph_text)
+            .filter(|text| !text.is_empty())
+            .collect();
+
+        Ok(paragraphs.join("\n\n"))
+    }
+
+    /// Group consecutive entries into paragraphs
+    ///
+    /// A new paragraph starts when the pause before an entry exceeds `gap_threshold`
+    /// or the previous entry ends with sentence-ending punctuation.
+    fn group_paragraphs<'a>(
+        &self,
+        entries: &'a [SubtitleEntry],
+        gap_threshold: Duration,
+    ) -> Vec<&'a [SubtitleEntry]> {
+        let mut paragraphs = Vec::new();
+        let mut start = 0;
+
+        for i in 1..entries.len() {
+            let prev = &entries[i - 1];
+            let gap = entries[i].start.saturating_sub(prev.end);
+            let sentence_end = prev.text.trim_end().ends_with(['.', '!', '?']);
+
+            if gap > gap_threshold || sentence_end {
+                paragraphs.push(&entries[start..i]);
+                start = i;
+            }
+        }
+
+        if start < entries.len() {
+            paragraphs.push(&entries[start..]);
+        }
+
+        paragraphs
+    }
+
+    /// Convert to Scenarist Closed Caption (SCC) format
+    ///
+    /// Emits basic CEA-608 pop-on captions on channel 1 with SMPTE timecodes at
+    /// `YdlOptions::frame_rate` (29.97 drop-frame by default). Limitations: each cue
+    /// is wrapped to at most two 32-column rows at the bottom of the screen, only
+    /// the 608 basic character set is encoded (other characters are dropped), and
+    /// no roll-up, paint-on, positioning, or styling is produced. Captions are
+    /// loaded at the cue start, so display lags the timecode by the few frames it
+    /// takes to transmit the codes.
+    pub fn to_scc_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
+        let frame_rate = self.options.frame_rate;
+        let mut result = String::from("Scenarist_SCC V1.0\n");
+
+        for (i, entry) in entries.iter().enumerate() {
+            let rows = cea608::wrap_rows(&entry.text);
+            if rows.is_empty() {
+                continue;
+            }
+
+            let mut codes = vec![
+                cea608::ERASE_NON_DISPLAYED,
+                cea608::ERASE_NON_DISPLAYED,
+                cea608::RESUME_CAPTION_LOADING,
+                cea608::RESUME_CAPTION_LOADING,
+            ];
+            let first_row = cea608::BOTTOM_ROW + 1 - rows.len();
+            for (offset, row) in rows.iter().enumerate() {
+                let pac = cea608::preamble(first_row + offset);
+                codes.push(pac);
+                codes.push(pac);
+                codes.extend(cea608::encode_text(row));
+            }
+            codes.push(cea608::END_OF_CAPTION);
+            codes.push(cea608::END_OF_CAPTION);
+
+            result.push_str(&format!(
+                "\n{}\t{}\n",
+                format_duration_as_smpte(entry.start, frame_rate),
+                cea608::join_words(&codes)
+            ));
+
+            // Clear the screen at the end of the cue unless the next one replaces it
+            let replaced = entries
+                .get(i + 1)
+                .is_some_and(|next| next.start <= entry.end);
+            if !replaced {
+                result.push_str(&format!(
+                    "\n{}\t{}\n",
+                    format_duration_as_smpte(entry.end, frame_rate),
+                    cea608::join_words(&[cea608::ERASE_DISPLAYED, cea608::ERASE_DISPLAYED])
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Convert to Advanced SubStation Alpha (ASS) format
+    ///
+    /// Every cue uses a single bottom-centered `Default` style on a 1920x1080 canvas.
+    /// Line breaks become `\N`, and braces are replaced with parentheses so that
+    /// leftover text is never read as an override block. Detected speakers go into
+    /// the `Name` field.
+    pub fn to_ass_format(&self, entries: &[SubtitleEntry], language: &str) -> YdlResult<String> {
+        let mut result = String::from("[Script Info]\n");
+        result.push_str("ScriptType: v4.00+\n");
+        result.push_str(&format!("Language: {}\n", language));
+        result.push_str("PlayResX: 1920\nPlayResY: 1080\n");
+        result.push_str("WrapStyle: 0\nScaledBorderAndShadow: yes\n\n");
+
+        result.push_str("[V4+ Styles]\n");
+        result.push_str("Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n");
+        result.push_str("Style: Default,Arial,64,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,2,60,60,50,1\n\n");
+
+        result.push_str("[Events]\n");
+        result.push_str(
+            "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        );
+        for entry in entries {
+            let text = entry
+                .text
+                .lines()
+                .map(|line| line.replace('{', "(").replace('}', ")"))
+                .collect::<Vec<_>>()
+                .join("\\N");
+            // Fields are comma-separated; only the final Text field may contain commas
+            let name = entry.speaker.as_deref().unwrap_or("").replace(',', " ");
+            result.push_str(&format!(
+                "Dialogue: 0,{},{},Default,{},0,0,0,,{}\n",
+                format_duration_as_ass(entry.start),
+                format_duration_as_ass(entry.end),
+                name,
+                text
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Convert to LRC lyrics format
+    ///
+    /// Each cue becomes one `[mm:ss.xx]` line, with its lines joined by spaces. LRC has
+    /// no end times, so cues are written by start time and overlaps are not kept apart.
+    /// `[ti:]` and `[length:]` tags come first when the title and length are known.
+    pub fn to_lrc_format(&self, entries: &[SubtitleEntry]) -> YdlResult<String> {
+        let mut result = String::new();
+        if let Some(title) = &self.title {
+            result.push_str(&format!("[ti:{}]\n", title.replace(['\n', '\r'], " ")));
+        }
+        if let Some(duration) = self.duration {
+            let secs = duration.as_secs();
+            result.push_str(&format!("[length:{:02}:{:02}]\n", secs / 60, secs % 60));
+        }
+
+        let mut entries: Vec<&SubtitleEntry> = entries.iter().collect();
+        entries.sort_by_key(|entry| entry.start);
+        for entry in entries {
+            let text = entry.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            result.push_str(&format!(
+                "[{}]{}\n",
+                format_duration_as_lrc(entry.start),
+                text
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Convert to a TTML (DFXP) document, one `<p begin end>` per cue
+    ///
+    /// Times are `HH:MM:SS.mmm` clock times and `xml:lang` is the track language;
+    /// lines within a cue are separated by `<br/>`.
+    pub fn to_ttml_format(&self, entries: &[SubtitleEntry], language: &str) -> YdlResult<String> {
+        let mut result = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        result.push_str(&format!(
+            "<tt xmlns=\"{}\" xml:lang=\"{}\">\n  <body>\n    <div>\n",
+            TTML_NAMESPACE,
+            html_escape::encode_xml(language)
+        ));
+
+        for entry in entries {
+            let text = entry
+                .text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(html_escape::encode_xml)
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            result.push_str(&format!(
+                "      <p begin=\"{}\" end=\"{}\">{}</p>\n",
+                format_duration_as_vtt(entry.start),
+                format_duration_as_vtt(entry.end),
+                text
+            ));
+        }
+
+        result.push_str("    </div>\n  </body>\n</tt>\n");
+        Ok(result)
+    }
+
+    /// Convert to JSON format
+    fn to_json_format(&self, entries: &[SubtitleEntry], language: &str) -> YdlResult<String> {
+        let json_entries: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|entry| {
+                let mut value = serde_json::json!({
+                    "start": entry.start.as_secs_f64(),
+                    "end": entry.end.as_secs_f64(),
+                    "text": entry.text
+                });
+                if let Some(speaker) = &entry.speaker {
+                    value["speaker"] = serde_json::json!(speaker);
+                }
+                if let Some(words) = &entry.word_timings {
+                    value["words"] = words
+                        .iter()
+                        .map(|(start, text)| {
+                            serde_json::json!({ "start": start.as_secs_f64(), "text": text })
+                        })
+                        .collect();
+                }
+                value
+            })
+            .collect();
+
+        let result = serde_json::json!({
+            "language": language,
+            "entries": json_entries
         });
 
-        serde_json::to_string_pretty(&result).map_err(YdlError::from)
+        serde_json::to_string_pretty(&result).map_err(YdlError::from)
+    }
+}
+
+/// One downloaded track, parsed once and rendered to any number of formats
+///
+/// Returned by [`Ydl::fetch`](crate::Ydl::fetch). [`to`](Self::to) neither downloads
+/// nor re-parses the captions; it only converts the cached cues. The video's metadata
+/// is fetched the first time LRC output needs it for its tags.
+pub struct CachedSubtitles {
+    processor: ContentProcessor,
+    /// The download as UTF-8, returned as is when it already is in the requested format
+    content: String,
+    parsed: ParsedSubtitles,
+    track: SubtitleTrack,
+    details: LazyDetails,
+}
+
+impl CachedSubtitles {
+    /// Parse `raw_content` downloaded for `track` with the options of `processor`
+    pub(crate) fn new(
+        processor: ContentProcessor,
+        raw_content: &str,
+        track: SubtitleTrack,
+        details: LazyDetails,
+    ) -> YdlResult<Self> {
+        let content = processor.ensure_utf8(raw_content)?;
+        let parsed = processor.parse_timed_content(&content, &track.language_code)?;
+        if processor.options.validate_timing {
+            processor.validate_timing(&parsed.entries)?;
+        }
+
+        Ok(Self {
+            processor,
+            content,
+            parsed,
+            track,
+            details,
+        })
+    }
+
+    /// Render the subtitles in `format`, as [`Ydl::subtitle`](crate::Ydl::subtitle) would
+    pub async fn to(&self, format: SubtitleType) -> YdlResult<String> {
+        // The metadata only adds the LRC title and length tags, so it is optional
+        let lrc_processor = match format {
+            SubtitleType::Lrc => self.details.get().await.ok().map(|details| {
+                self.processor
+                    .clone()
+                    .with_metadata(&details.title, details.duration)
+            }),
+            _ => None,
+        };
+        lrc_processor
+            .as_ref()
+            .unwrap_or(&self.processor)
+            .render_parsed(
+                &self.content,
+                &self.parsed,
+                format,
+                self.processor.options.clean_content,
+                &self.track.track_type,
+            )
+    }
+
+    /// Cues as parsed, after the timing options but before cleaning and segmentation
+    pub fn parsed(&self) -> &ParsedSubtitles {
+        &self.parsed
+    }
+
+    /// The track the subtitles were downloaded from
+    pub fn track(&self) -> &SubtitleTrack {
+        &self.track
+    }
+}
+
+/// Parse a TTML time expression: a clock time (`01:02:03.500`, `01:02:03:12`) or an
+/// offset with a metric (`3.5s`, `3500ms`, `1.5m`, `2h`, `90f`, `10000t`)
+fn parse_ttml_time(expr: &str, frame_rate: f64, tick_rate: f64) -> Option<Duration> {
+    let expr = expr.trim();
+
+    let seconds = if expr.contains(':') {
+        let parts: Vec<&str> = expr.split(':').collect();
+        let (hours, minutes, seconds, frames) = match parts.as_slice() {
+            [h, m, s] => (h, m, s, None),
+            [h, m, s, f] => (h, m, s, Some(f)),
+            _ => return None,
+        };
+        let frames = match frames {
+            Some(f) => f.parse::<f64>().ok()? / frame_rate,
+            None => 0.0,
+        };
+        hours.parse::<f64>().ok()? * 3600.0
+            + minutes.parse::<f64>().ok()? * 60.0
+            + seconds.parse::<f64>().ok()?
+            + frames
+    } else {
+        let split = expr.find(|c: char| c.is_ascii_alphabetic())?;
+        let value = expr[..split].parse::<f64>().ok()?;
+        match &expr[split..] {
+            "h" => value * 3600.0,
+            "m" => value * 60.0,
+            "s" => value,
+            "ms" => value / 1000.0,
+            "f" => value / frame_rate,
+            "t" => value / tick_rate,
+            _ => return None,
+        }
+    };
+
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// SRT sequence numbers for cues written one at a time
+///
+/// Numeric cue identifiers (as carried over from WebVTT) are kept as long as they
+/// count up; from the first one that does not, cues are numbered on from the last.
+#[derive(Default)]
+struct SrtNumbering {
+    last: u64,
+    counting: bool,
+}
+
+impl SrtNumbering {
+    fn next(&mut self, entry: &SubtitleEntry) -> u64 {
+        let id = entry.id.as_deref().and_then(|id| id.parse::<u64>().ok());
+        self.last = match id {
+            Some(id) if !self.counting && id > self.last => id,
+            _ => {
+                self.counting = true;
+                self.last + 1
+            }
+        };
+        self.last
+    }
+}
+
+/// Drop the blank line after the final cue, which strict parsers reject
+fn end_with_single_newline(mut output: String) -> String {
+    output.truncate(output.trim_end().len());
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Text of a paragraph's cues on one line, their line breaks and padding collapsed
+fn paragraph_text(paragraph: &[SubtitleEntry]) -> String {
+    paragraph
+        .iter()
+        .flat_map(|e| e.text.lines())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split a leading speaker label off `text`, returning the speaker and remaining text
+fn split_speaker(text: &str) -> (Option<String>, String) {
+    let trimmed = text.trim_start();
+    let (marked, rest) = if let Some(rest) = trimmed.strip_prefix(">>") {
+        (true, rest.trim_start())
+    } else if let Some(rest) = trimmed.strip_prefix("- ") {
+        (true, rest.trim_start())
+    } else {
+        (false, trimmed)
+    };
+
+    if let Some((label, remainder)) = rest.split_once(':') {
+        let label = label.trim();
+        if is_speaker_label(label, marked) {
+            return (Some(label.to_string()), remainder.trim_start().to_string());
+        }
+    }
+
+    if marked {
+        (None, rest.to_string())
+    } else {
+        (None, text.to_string())
+    }
+}
+
+/// Put detected speakers back in front of the cue text as `NAME: text`
+///
+/// The `>>`/`-` markers are not restored, so labels read the same however the
+/// captions wrote them.
+fn label_speakers(entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            if let Some(speaker) = &entry.speaker {
+                entry.text = format!("{}: {}", speaker, entry.text);
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Whether `label` looks like a speaker name rather than the start of a sentence
+///
+/// Names after a `>>`/`-` marker only need capitalized words; bare labels must be
+/// upper-case (`JOHN:`) to avoid catching phrases like `Note: ...`.
+fn is_speaker_label(label: &str, marked: bool) -> bool {
+    let words: Vec<&str> = label.split_whitespace().collect();
+    if words.is_empty() || words.len() > 3 || label.len() > 32 {
+        return false;
+    }
+
+    words.iter().all(|word| {
+        let valid_chars = word
+            .chars()
+            .all(|c| c.is_alphabetic() || matches!(c, '.' | '\'' | '-'));
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        let upper_case = !word.chars().any(char::is_lowercase);
+        valid_chars && capitalized && (marked || upper_case)
+    })
+}
+
+/// Resolve the format to render, mapping `Raw` to the source format's natural rendering
+///
+/// Sources without a text rendering of their own (YouTube XML) fall back to SRT.
+fn resolve_target_format(target: SubtitleType, source: SubtitleType) -> SubtitleType {
+    match (target, source) {
+        (SubtitleType::Raw, SubtitleType::Raw) => SubtitleType::Srt,
+        (SubtitleType::Raw, source) => source,
+        (target, _) => target,
+    }
+}
+
+/// Clamp each cue's end to the start of the cue that follows it
+///
+/// Cues sharing a start time with their successor are left alone, since clamping
+/// them would leave nothing to show.
+fn clamp_overlaps(mut entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+    entries.sort_by_key(|e| e.start);
+
+    for i in 1..entries.len() {
+        let next_start = entries[i].start;
+        let entry = &mut entries[i - 1];
+        if next_start > entry.start && entry.end > next_start {
+            entry.end = next_start;
+        }
+    }
+
+    entries
+}
+
+/// Word-wrap `text` into at most `max_lines` lines of up to `max_length` characters
+///
+/// Text whose lines all fit is returned unchanged. Otherwise the words are refilled
+/// greedily, breaking only at whitespace; a word longer than `max_length` gets a line
+/// of its own, and the last line takes every word that does not fit before it.
+fn wrap_text(text: &str, max_length: usize, max_lines: usize) -> String {
+    let fits = |line: &str| line.chars().count() <= max_length;
+    if text.lines().count() <= max_lines && text.lines().all(fits) {
+        return text.to_string();
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let full = lines.len() == max_lines;
+        match lines.last_mut() {
+            Some(line) if full || line.chars().count() + 1 + word.chars().count() <= max_length => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Sort, de-duplicate and de-overlap cues; see [`YdlOptions::strict_output`]
+fn strict_entries(mut entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+    entries.retain(|e| !e.text.trim().is_empty());
+    entries.sort_by_key(|e| e.start);
+
+    let mut strict: Vec<SubtitleEntry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if let Some(last) = strict.last_mut() {
+            if last.text == entry.text && entry.start <= last.end {
+                last.end = last.end.max(entry.end);
+                continue;
+            }
+            if last.start == entry.start {
+                if !last.text.lines().any(|line| line == entry.text) {
+                    last.text = format!("{}\n{}", last.text, entry.text);
+                }
+                last.end = last.end.max(entry.end);
+                continue;
+            }
+        }
+        strict.push(entry);
+    }
+
+    // Starts are now strictly increasing, so clamping to the next start keeps every cue non-empty
+    for i in 0..strict.len() {
+        let next_start = strict.get(i + 1).map(|e| e.start);
+        let entry = &mut strict[i];
+        let duration = entry
+            .end
+            .saturating_sub(entry.start)
+            .clamp(STRICT_MIN_DURATION, STRICT_MAX_DURATION);
+        entry.end = entry.start + duration;
+        if let Some(next_start) = next_start {
+            entry.end = entry.end.min(next_start);
+        }
+    }
+
+    strict
+}
+
+/// Merge consecutive cues until one ends with sentence-ending punctuation
+fn merge_sentences(entries: Vec<SubtitleEntry>) -> Vec<SubtitleEntry> {
+    let mut merged: Vec<SubtitleEntry> = Vec::new();
+    let mut current: Option<SubtitleEntry> = None;
+
+    for entry in entries {
+        let sentence = match current.take() {
+            Some(mut sentence) => {
+                sentence.end = sentence.end.max(entry.end);
+                sentence.text = format!("{} {}", sentence.text.trim_end(), entry.text.trim());
+                sentence
+            }
+            None => entry,
+        };
+
+        if sentence.text.trim_end().ends_with(['.', '!', '?']) {
+            merged.push(sentence);
+        } else {
+            current = Some(sentence);
+        }
+    }
+
+    merged.extend(current);
+    merged
+}
+
+// Minimal CEA-608 encoder for pop-on captions on channel 1
+mod cea608 {
+    /// Resume caption loading (pop-on mode)
+    pub const RESUME_CAPTION_LOADING: u16 = 0x1420;
+    /// Erase non-displayed memory
+    pub const ERASE_NON_DISPLAYED: u16 = 0x142e;
+    /// Erase displayed memory
+    pub const ERASE_DISPLAYED: u16 = 0x142c;
+    /// End of caption (swap memories)
+    pub const END_OF_CAPTION: u16 = 0x142f;
+    /// Lowest caption row
+    pub const BOTTOM_ROW: usize = 15;
+    /// Characters per caption row
+    const ROW_WIDTH: usize = 32;
+    /// Maximum rows rendered per caption
+    const MAX_ROWS: usize = 2;
+
+    /// Preamble address code for column 0 of `row` (1-15), white text
+    pub fn preamble(row: usize) -> u16 {
+        let (first, second) = match row {
+            1 => (0x11, 0x50),
+            2 => (0x11, 0x70),
+            3 => (0x12, 0x50),
+            4 => (0x12, 0x70),
+            5 => (0x15, 0x50),
+            6 => (0x15, 0x70),
+            7 => (0x16, 0x50),
+            8 => (0x16, 0x70),
+            9 => (0x17, 0x50),
+            10 => (0x17, 0x70),
+            11 => (0x10, 0x50),
+            12 => (0x13, 0x50),
+            13 => (0x13, 0x70),
+            14 => (0x14, 0x50),
+            _ => (0x14, 0x70),
+        };
+        (first << 8) | second
+    }
+
+    /// Wrap text into at most two rows of 32 columns, dropping the remainder
+    pub fn wrap_rows(text: &str) -> Vec<String> {
+        let mut rows = Vec::new();
+        let mut current = String::new();
+
+        for word in text.split_whitespace() {
+            let word: String = word.chars().filter(|&c| encode_char(c).is_some()).collect();
+            if word.is_empty() {
+                continue;
+            }
+            let word: String = word.chars().take(ROW_WIDTH).collect();
+
+            let needed = if current.is_empty() {
+                word.chars().count()
+            } else {
+                current.chars().count() + 1 + word.chars().count()
+            };
+            if needed > ROW_WIDTH && !current.is_empty() {
+                rows.push(std::mem::take(&mut current));
+                if rows.len() == MAX_ROWS {
+                    return rows;
+                }
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(&word);
+        }
+
+        if !current.is_empty() && rows.len() < MAX_ROWS {
+            rows.push(current);
+        }
+        rows
+    }
+
+    /// Encode a row of text into byte pairs, padding odd lengths with a null
+    pub fn encode_text(text: &str) -> Vec<u16> {
+        let bytes: Vec<u8> = text.chars().filter_map(encode_char).collect();
+        bytes
+            .chunks(2)
+            .map(|pair| ((pair[0] as u16) << 8) | *pair.get(1).unwrap_or(&0) as u16)
+            .collect()
+    }
+
+    /// Map a character to the CEA-608 basic character set
+    fn encode_char(c: char) -> Option<u8> {
+        match c {
+            'á' => Some(0x2a),
+            'é' => Some(0x5c),
+            'í' => Some(0x5e),
+            'ó' => Some(0x5f),
+            'ú' => Some(0x60),
+            'ç' => Some(0x7b),
+            '÷' => Some(0x7c),
+            'Ñ' => Some(0x7d),
+            'ñ' => Some(0x7e),
+            // These ASCII positions hold accented letters in CEA-608
+            '*' | '\\' | '^' | '_' | '`' | '{' | '|' | '}' | '~' => None,
+            ' '..='\x7f' => Some(c as u8),
+            _ => None,
+        }
+    }
+
+    /// Apply odd parity to a 7-bit value
+    fn with_parity(byte: u8) -> u8 {
+        let byte = byte & 0x7f;
+        if byte.count_ones().is_multiple_of(2) {
+            byte | 0x80
+        } else {
+            byte
+        }
+    }
+
+    /// Render byte pairs as space-separated hex words with parity applied
+    pub fn join_words(words: &[u16]) -> String {
+        words
+            .iter()
+            .map(|&word| {
+                format!(
+                    "{:02x}{:02x}",
+                    with_parity((word >> 8) as u8),
+                    with_parity((word & 0xff) as u8)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// Simple HTML entity decoder (subset of common entities)
+mod html_escape {
+    pub fn decode_html_entities(text: &str) -> std::borrow::Cow<'_, str> {
+        let mut result = text.to_string();
+
+        result = result.replace("&amp;", "&");
+        result = result.replace("&lt;", "<");
+        result = result.replace("&gt;", ">");
+        result = result.replace("&quot;", "\"");
+        result = result.replace("&#39;", "'");
+        result = result.replace("&#x27;", "'");
+        result = result.replace("&apos;", "'");
+
+        std::borrow::Cow::Owned(result)
+    }
+
+    /// Escape text for XML content or a double-quoted attribute
+    pub fn encode_xml(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::Timecode;
+
+    fn test_processor() -> ContentProcessor {
+        ContentProcessor::new()
+    }
+
+    #[test]
+    fn test_parse_srt_content() {
+        let processor = test_processor();
+        let srt_content = r"1
+00:00:01,000 --> 00:00:03,000
+Hello, world!
+
+2
+00:00:04,000 --> 00:00:06,000
+This is a test.
+";
+
+        let result = processor.parse_srt_content(srt_content, "en");
+        assert!(result.is_ok());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].text, "Hello, world!");
+        assert_eq!(parsed.entries[1].text, "This is a test.");
+    }
+
+    #[test]
+    fn test_parse_vtt_content() {
+        let processor = test_processor();
+        let vtt_content = r"WEBVTT
+
+00:00:01.000 --> 00:00:03.000
+Hello, world!
+
+00:00:04.000 --> 00:00:06.000
+This is a test.
+";
+
+        let result = processor.parse_vtt_content(vtt_content, "en");
+        assert!(result.is_ok());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].text, "Hello, world!");
+        assert_eq!(parsed.entries[1].text, "This is a test.");
+    }
+
+    #[test]
+    fn test_vtt_cue_identifiers_round_trip() {
+        let vtt_content = "WEBVTT
+
+intro
+00:00:01.000 --> 00:00:03.000
+Hello, world!
+
+00:00:04.000 --> 00:00:06.000
+No identifier here.
+
+chapter-2 title
+00:00:07.000 --> 00:00:09.000
+This is a test.
+";
+
+        let processor = test_processor();
+        let vtt = processor
+            .process_content(
+                vtt_content,
+                SubtitleType::Vtt,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(vtt, vtt_content);
+
+        let parsed = processor.parse_vtt_content(vtt_content, "en").unwrap();
+        let ids: Vec<Option<&str>> = parsed.entries.iter().map(|e| e.id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("intro"), None, Some("chapter-2 title")]);
+
+        // Numeric identifiers carry over as SRT sequence numbers
+        let numbered = "WEBVTT\n\n10\n00:00:01.000 --> 00:00:02.000\nA\n\n20\n00:00:03.000 --> 00:00:04.000\nB\n";
+        let srt = processor
+            .process_content(
+                numbered,
+                SubtitleType::Srt,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.starts_with("10\n00:00:01,000"));
+        assert!(srt.contains("\n20\n00:00:03,000"));
+
+        // Named identifiers do not, so the cues are numbered from 1
+        let srt = processor
+            .process_content(
+                vtt_content,
+                SubtitleType::Srt,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.starts_with("1\n00:00:01,000"));
+    }
+
+    #[test]
+    fn test_parse_vtt_skips_metadata_blocks() {
+        let processor = test_processor();
+        let vtt_content = "WEBVTT\nKind: captions\nLanguage: en\n\n\
+STYLE\n::cue {\n  color: yellow;\n}\n\n\
+REGION\nid:fred\nwidth:40%\n\n\
+NOTE this comment\nspans 00:00:09.000 --> 00:00:10.000 lines\n\n\
+intro   \n00:00:01.000 --> 00:00:03.000 align:start position:0%   \nHello, world!   \n\n\
+NOTE\nanother comment\n\n\
+02:04.500 --> 02:06.000\nNo hours here\n";
+
+        let parsed = processor.parse_vtt_content(vtt_content, "en").unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].text, "Hello, world!");
+        assert_eq!(parsed.entries[0].start, Duration::from_secs(1));
+        assert_eq!(parsed.entries[1].text, "No hours here");
+        assert_eq!(parsed.entries[1].start, Duration::from_millis(124_500));
+    }
+
+    #[test]
+    fn test_parse_vtt_drops_inverted_cues() {
+        let processor = test_processor();
+        let vtt_content = "WEBVTT\n\n00:00:05.000 --> 00:00:01.000\nBackwards\n\n\
+00:00:06.000 --> 00:00:07.000\nForwards\n";
+
+        let parsed = processor.parse_vtt_content(vtt_content, "en").unwrap();
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].text, "Forwards");
+    }
+
+    mod vtt_proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn vtt_line() -> impl Strategy<Value = String> {
+            prop_oneof![
+                Just(String::new()),
+                Just("WEBVTT".to_string()),
+                Just("NOTE".to_string()),
+                Just("STYLE".to_string()),
+                Just("REGION".to_string()),
+                "[0-9]{1,3}",
+                "[0-9]{2}:[0-9]{2}:[0-9]{2}\\.[0-9]{3} --> [0-9]{2}:[0-9]{2}:[0-9]{2}\\.[0-9]{3}",
+                "[0-9]{2}:[0-9]{2}\\.[0-9]{3} --> [0-9]{2}:[0-9]{2}\\.[0-9]{3}[ a-z:%0-9]{0,12}",
+                "[^\\n]{0,40}",
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn vtt_parser_never_panics(content in "\\PC{0,400}") {
+                let _ = ContentProcessor::new().parse_vtt_content(&content, "en");
+            }
+
+            #[test]
+            fn vtt_parser_emits_only_forward_cues(lines in prop::collection::vec(vtt_line(), 0..40)) {
+                let content = lines.join("\n");
+                if let Ok(parsed) = ContentProcessor::new().parse_vtt_content(&content, "en") {
+                    for entry in &parsed.entries {
+                        prop_assert!(entry.start < entry.end);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_to_srt() {
+        let processor = test_processor();
+        let entries = vec![SubtitleEntry::new(
+            Duration::from_secs(1),
+            Duration::from_secs(3),
+            "Hello, world!".to_string(),
+        )];
+
+        let result = processor.to_srt_format(&entries);
+        assert!(result.is_ok());
+
+        let srt = result.unwrap();
+        assert!(srt.contains("1\n"));
+        assert!(srt.contains("00:00:01,000 --> 00:00:03,000"));
+        assert!(srt.contains("Hello, world!"));
+    }
+
+    #[test]
+    fn test_srt_frame_timecodes() {
+        let processor = ContentProcessor::with_options(
+            YdlOptions::new()
+                .timecode(Timecode::Frames)
+                .frame_rate(25.0),
+        );
+        let entries = vec![SubtitleEntry::new(
+            Duration::from_millis(1_040),
+            Duration::from_millis(2_500),
+            "Hello".to_string(),
+        )];
+
+        let srt = processor
+            .convert_to_format(&entries, SubtitleType::Srt, "en")
+            .unwrap();
+        assert_eq!(srt, "1\n00:00:01:01 --> 00:00:02:13\nHello\n");
+    }
+
+    #[test]
+    fn test_parse_srt_iter_is_lazy() {
+        let processor = ContentProcessor::with_options(YdlOptions::new().lenient_blocks(false));
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n\
+            2\n00:00:02,000 --> 00:00:03,000\nSecond\n\n\
+            not a block\n";
+
+        // Stopping early never reaches the malformed block
+        let first: Vec<_> = processor.parse_srt_iter(srt).take(2).collect();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].as_ref().unwrap().text, "Second");
+
+        let results: Vec<_> = processor.parse_srt_iter(srt).collect();
+        assert!(matches!(results[2], Err(YdlError::SubtitleParsing { .. })));
+
+        // Lenient parsing (the default) skips it instead
+        assert_eq!(test_processor().parse_srt_iter(srt).count(), 2);
+    }
+
+    #[test]
+    fn test_streaming_writers_match_converters() {
+        let processor =
+            ContentProcessor::with_options(YdlOptions::new().preserve_line_breaks(true));
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nFirst\nline\n\n\
+            2\n00:00:02,500 --> 00:00:03,000\nSecond\n";
+
+        let mut out = Vec::new();
+        let entries = processor.parse_srt_iter(srt).map(Result::unwrap);
+        assert_eq!(processor.write_srt(entries, &mut out).unwrap(), 2);
+
+        let parsed = processor.parse_content(srt, "en").unwrap().entries;
+        let expected = processor
+            .convert_to_format(&parsed, SubtitleType::Srt, "en")
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut out = Vec::new();
+        processor.write_vtt(&parsed, &mut out).unwrap();
+        let expected = processor
+            .convert_to_format(&parsed, SubtitleType::Vtt, "en")
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_srt_numbers_like_converter() {
+        let processor = test_processor();
+        let cue = |id: Option<&str>, second: u64| {
+            let entry = SubtitleEntry::new(
+                Duration::from_secs(second),
+                Duration::from_secs(second + 1),
+                format!("Cue {}", second),
+            );
+            match id {
+                Some(id) => entry.with_id(id.to_string()),
+                None => entry,
+            }
+        };
+
+        let cases = [
+            // Counting-up VTT ids are kept, cues without ids are numbered from 1
+            (
+                vec![cue(Some("5"), 1), cue(Some("6"), 2), cue(Some("9"), 3)],
+                [5, 6, 9],
+            ),
+            (vec![cue(None, 1), cue(None, 2), cue(None, 3)], [1, 2, 3]),
+            // Once an id is missing or goes back, numbering carries on from there
+            (
+                vec![cue(Some("5"), 1), cue(Some("intro"), 2), cue(Some("7"), 3)],
+                [5, 6, 7],
+            ),
+            (
+                vec![cue(Some("4"), 1), cue(Some("2"), 2), cue(Some("9"), 3)],
+                [4, 5, 6],
+            ),
+        ];
+        for (entries, numbers) in cases {
+            let expected = processor
+                .convert_to_format(&entries, SubtitleType::Srt, "en")
+                .unwrap();
+            let mut out = Vec::new();
+            processor.write_srt(&entries, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+            let written: Vec<&str> = expected
+                .split("\n\n")
+                .filter_map(|block| block.lines().next())
+                .collect();
+            assert_eq!(written, numbers.map(|n| n.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_convert_to_vtt() {
+        let processor = test_processor();
+        let entries = vec![SubtitleEntry::new(
+            Duration::from_secs(1),
+            Duration::from_secs(3),
+            "Hello, world!".to_string(),
+        )];
+
+        let result = processor.to_vtt_format(&entries);
+        assert!(result.is_ok());
+
+        let vtt = result.unwrap();
+        assert!(vtt.starts_with("WEBVTT"));
+        assert!(vtt.contains("00:00:01.000 --> 00:00:03.000"));
+        assert!(vtt.contains("Hello, world!"));
+    }
+
+    #[test]
+    fn test_output_ends_with_single_newline() {
+        let processor = test_processor();
+        let entries = vec![
+            SubtitleEntry::new(Duration::from_secs(1), Duration::from_secs(3), "One".into()),
+            SubtitleEntry::new(Duration::from_secs(3), Duration::from_secs(5), "Two".into()),
+        ];
+
+        let srt = processor.to_srt_format(&entries).unwrap();
+        assert!(srt.ends_with("Two\n"));
+        assert!(!srt.ends_with("\n\n"));
+        assert!(srt.contains("One\n\n2\n"));
+
+        let vtt = processor.to_vtt_format(&entries).unwrap();
+        assert!(vtt.ends_with("Two\n"));
+        assert!(!vtt.ends_with("\n\n"));
+        assert_eq!(processor.to_vtt_format(&[]).unwrap(), "WEBVTT\n");
+    }
+
+    #[test]
+    fn test_convert_to_txt() {
+        let processor = test_processor();
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_secs(1),
+                Duration::from_secs(3),
+                "Hello, world!".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(4),
+                Duration::from_secs(6),
+                "This is a test.".to_string(),
+            ),
+        ];
+
+        let result = processor.to_txt_format(&entries);
+        assert!(result.is_ok());
+
+        let txt = result.unwrap();
+        assert_eq!(txt, "Hello, world!\nThis is a test.");
+    }
+
+    #[test]
+    fn test_shift_timing() {
+        let processor = test_processor();
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_millis(500),
+                Duration::from_millis(1500),
+                "a".into(),
+            ),
+            SubtitleEntry::new(Duration::from_secs(5), Duration::from_secs(7), "b".into()),
+        ];
+
+        let later = processor.shift_timing(entries.clone(), 250);
+        assert_eq!(later[0].start, Duration::from_millis(750));
+        assert_eq!(later[1].end, Duration::from_millis(7250));
+
+        // Shifting past zero saturates; the first cue keeps the end it can
+        let earlier = processor.shift_timing(entries.clone(), -1000);
+        assert_eq!(earlier[0].start, Duration::ZERO);
+        assert_eq!(earlier[0].end, Duration::from_millis(500));
+        assert_eq!(earlier[1].start, Duration::from_secs(4));
+
+        // A cue pushed entirely below zero still has start < end
+        let gone = processor.shift_timing(entries, -2000);
+        assert_eq!(gone[0].start, Duration::ZERO);
+        assert_eq!(gone[0].end, Duration::from_millis(1));
+        assert!(processor.validate_timing(&gone).is_ok());
+    }
+
+    #[test]
+    fn test_start_at_drops_earlier_cues() {
+        let processor =
+            ContentProcessor::with_options(YdlOptions::new().start_at(Duration::from_secs(2)));
+        let srt = "1\n00:00:01,000 --> 00:00:02,500\nBefore\n\n\
+            2\n00:00:02,000 --> 00:00:03,000\nAt\n\n\
+            3\n00:00:04,000 --> 00:00:05,000\nAfter\n";
+
+        let txt = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(txt, "At\nAfter");
+
+        // Pass-through is skipped, so the same format is still filtered
+        let out = processor
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                false,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(!out.contains("Before"));
+    }
+
+    #[test]
+    fn test_dedupe_rolling() {
+        let processor = test_processor();
+        let srt = "1\n00:00:00,000 --> 00:00:01,000\nthe quick\n\n\
+            2\n00:00:01,000 --> 00:00:02,000\nthe quick brown\n\n\
+            3\n00:00:02,000 --> 00:00:03,000\nthe quick brown fox\n\n\
+            4\n00:00:03,000 --> 00:00:04,000\nthe quickest way\n\n\
+            5\n00:00:04,000 --> 00:00:05,000\njumps over\n";
+
+        let txt = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::AutoGenerated,
+            )
+            .unwrap();
+        assert_eq!(txt, "the quick brown fox\nthe quickest way\njumps over");
+
+        let entries = processor.parse_content(srt, "en").unwrap().entries;
+        let deduped = processor.dedupe_rolling(entries.clone());
+        assert_eq!(deduped.len(), 3);
+        assert_eq!(deduped[0].start, Duration::ZERO);
+        assert_eq!(deduped[0].end, Duration::from_secs(3));
+
+        // Manual tracks are left alone
+        let txt = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(txt.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_txt_paragraphs() {
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_millis(500),
+                Duration::from_secs(2),
+                "so today we're".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                "looking at\nlifetimes.".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_millis(4200),
+                Duration::from_secs(6),
+                "They can be".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(6),
+                Duration::from_secs(8),
+                "confusing at first".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(12),
+                Duration::from_secs(14),
+                "but stick with it".to_string(),
+            ),
+        ];
+
+        assert_eq!(
+            test_processor()
+                .to_txt_paragraphs(&entries, DEFAULT_PARAGRAPH_GAP)
+                .unwrap(),
+            "so today we're looking at lifetimes.\n\n\
+             They can be confusing at first\n\n\
+             but stick with it"
+        );
+
+        let processor = ContentProcessor::with_options(
+            YdlOptions::new().txt_paragraphs(Duration::from_secs(5)),
+        );
+        assert_eq!(
+            processor
+                .convert_to_format(&entries, SubtitleType::Txt, "en")
+                .unwrap(),
+            "so today we're looking at lifetimes.\n\n\
+             They can be confusing at first but stick with it"
+        );
+
+        // Without the option, TXT stays one cue per line
+        assert_eq!(
+            test_processor()
+                .convert_to_format(&entries, SubtitleType::Txt, "en")
+                .unwrap(),
+            "so today we're\nlooking at lifetimes.\nThey can be\nconfusing at first\nbut stick with it"
+        );
+    }
+
+    #[test]
+    fn test_txt_with_timestamp_links() {
+        let processor = test_processor();
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_millis(500),
+                Duration::from_secs(2),
+                "Welcome to the".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                "show.".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_millis(4500),
+                Duration::from_secs(6),
+                "Today we talk".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_millis(65_700),
+                Duration::from_secs(68),
+                "about Rust".to_string(),
+            ),
+        ];
+
+        let txt = processor
+            .to_txt_with_timestamp_links(&entries, "dQw4w9WgXcQ")
+            .unwrap();
+        let paragraphs: Vec<&str> = txt.split("\n\n").collect();
+
+        assert_eq!(
+            paragraphs,
+            vec![
+                "https://youtu.be/dQw4w9WgXcQ?t=0 Welcome to the show.",
+                "https://youtu.be/dQw4w9WgXcQ?t=4 Today we talk",
+                "https://youtu.be/dQw4w9WgXcQ?t=65 about Rust",
+            ]
+        );
     }
-}
-
-// Simple HTML entity decoder (subset of common entities)
-mod html_escape {
-    pub fn decode_html_entities(text: &str) -> std::borrow::Cow<'_, str> {
-        let mut result = text.to_string();
-
-        result = result.replace("&amp;", "&");
-        result = result.replace("&lt;", "<");
-        result = result.replace("&gt;", ">");
-        result = result.replace("&quot;", "\"");
-        result = result.replace("&#39;", "'");
-        result = result.replace("&#x27;", "'");
-        result = result.replace("&apos;", "'");
 
-        std::borrow::Cow::Owned(result)
+    #[test]
+    fn test_timestamp_links_option() {
+        let srt = "1\n00:00:03,000 --> 00:00:04,000\nHello.\n\n";
+
+        let processor = ContentProcessor::with_options(YdlOptions::new().timestamp_links(true))
+            .with_video_id("dQw4w9WgXcQ");
+        let txt = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(txt, "https://youtu.be/dQw4w9WgXcQ?t=3 Hello.");
+
+        // Without the option the plain text is unchanged
+        let txt = test_processor()
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(txt, "Hello.");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn overlapping_entries() -> Vec<SubtitleEntry> {
+        vec![
+            SubtitleEntry::new(
+                Duration::from_secs(0),
+                Duration::from_secs(4),
+                "so today we".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(2),
+                Duration::from_secs(6),
+                "talk about rust.".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(5),
+                Duration::from_secs(8),
+                "Let's go!".to_string(),
+            ),
+        ]
+    }
 
-    fn test_processor() -> ContentProcessor {
-        ContentProcessor::new()
+    #[test]
+    fn test_segmentation_original() {
+        let entries =
+            test_processor().segment_entries(overlapping_entries(), Segmentation::Original);
+        let ends: Vec<u64> = entries.iter().map(|e| e.end.as_secs()).collect();
+        assert_eq!(ends, vec![4, 6, 8]);
     }
 
     #[test]
-    fn test_parse_srt_content() {
-        let processor = test_processor();
-        let srt_content = r"1
-00:00:01,000 --> 00:00:03,000
-Hello, world!
+    fn test_segmentation_non_overlapping() {
+        let entries =
+            test_processor().segment_entries(overlapping_entries(), Segmentation::NonOverlapping);
+        let ends: Vec<u64> = entries.iter().map(|e| e.end.as_secs()).collect();
+        assert_eq!(ends, vec![2, 5, 8]);
+        assert!(entries.windows(2).all(|w| w[0].end <= w[1].start));
+    }
 
-2
-00:00:04,000 --> 00:00:06,000
-This is a test.
-";
+    #[test]
+    fn test_segmentation_sentences() {
+        let entries =
+            test_processor().segment_entries(overlapping_entries(), Segmentation::Sentences);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].text, "so today we talk about rust.");
+        assert_eq!(entries[0].start, Duration::from_secs(0));
+        assert_eq!(entries[0].end, Duration::from_secs(5));
+        assert_eq!(entries[1].text, "Let's go!");
+        assert_eq!(entries[1].end, Duration::from_secs(8));
+    }
 
-        let result = processor.parse_srt_content(srt_content, "en");
-        assert!(result.is_ok());
+    #[test]
+    fn test_line_breaks_in_cues() {
+        let processor =
+            ContentProcessor::with_options(YdlOptions::new().preserve_line_breaks(true));
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:03.000\nFirst line<br/>second  line\n\n00:00:03.000 --> 00:00:05.000\nEscaped\\nbreak\n";
+
+        let srt = processor
+            .process_content(
+                vtt,
+                SubtitleType::Srt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.contains("00:00:03,000\nFirst line\nsecond line\n\n"));
+        assert!(srt.ends_with("00:00:05,000\nEscaped\nbreak\n"));
+
+        let txt = processor
+            .process_content(
+                vtt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(txt, "First line second line\nEscaped break");
 
-        let parsed = result.unwrap();
-        assert_eq!(parsed.entries.len(), 2);
-        assert_eq!(parsed.entries[0].text, "Hello, world!");
-        assert_eq!(parsed.entries[1].text, "This is a test.");
+        let processor = test_processor();
+        let srt = processor
+            .process_content(
+                vtt,
+                SubtitleType::Srt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.contains("00:00:03,000\nFirst line second line\n\n"));
     }
 
     #[test]
-    fn test_parse_vtt_content() {
+    fn test_detect_speakers() {
         let processor = test_processor();
-        let vtt_content = r"WEBVTT
+        let entries = processor.detect_speakers(vec![
+            SubtitleEntry::new(
+                Duration::ZERO,
+                Duration::from_secs(1),
+                ">> BOB: Hi there".into(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                "- Alice: Hello, Bob".into(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(2),
+                Duration::from_secs(3),
+                ">> And who are you?".into(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(3),
+                Duration::from_secs(4),
+                "Note: nobody answered".into(),
+            ),
+        ]);
+
+        assert_eq!(entries[0].speaker.as_deref(), Some("BOB"));
+        assert_eq!(entries[0].text, "Hi there");
+        assert_eq!(entries[1].speaker.as_deref(), Some("Alice"));
+        assert_eq!(entries[1].text, "Hello, Bob");
+        assert_eq!(entries[2].speaker, None);
+        assert_eq!(entries[2].text, "And who are you?");
+        assert_eq!(entries[3].speaker, None);
+        assert_eq!(entries[3].text, "Note: nobody answered");
+    }
 
-00:00:01.000 --> 00:00:03.000
-Hello, world!
+    #[test]
+    fn test_empty_cues_are_dropped_and_renumbered() {
+        let strip_music: std::sync::Arc<dyn Fn(&str) -> String + Send + Sync> =
+            std::sync::Arc::new(|t| t.replace("[Music]", ""));
+        let options = YdlOptions::new().text_transform(strip_music);
+        let vtt = "WEBVTT\n\n\
+            1\n00:00:01.000 --> 00:00:02.000\nHello\n\n\
+            2\n00:00:02.000 --> 00:00:04.000\n<i>[Music]</i>\n\n\
+            3\n00:00:04.000 --> 00:00:05.000\nWorld\n";
+        let to_srt = |processor: &ContentProcessor| {
+            processor
+                .process_content(
+                    vtt,
+                    SubtitleType::Srt,
+                    "en",
+                    true,
+                    true,
+                    &SubtitleTrackType::Manual,
+                )
+                .unwrap()
+        };
 
-00:00:04.000 --> 00:00:06.000
-This is a test.
-";
+        let srt = to_srt(&ContentProcessor::with_options(options.clone()));
+        assert_eq!(
+            srt,
+            "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n\
+             2\n00:00:04,000 --> 00:00:05,000\nWorld\n"
+        );
 
-        let result = processor.parse_vtt_content(vtt_content, "en");
-        assert!(result.is_ok());
+        // Opting out keeps the blank cue and its original numbering
+        let srt = to_srt(&ContentProcessor::with_options(
+            options.drop_empty_cues(false),
+        ));
+        assert!(srt.contains("2\n00:00:02,000 --> 00:00:04,000\n\n\n3\n"));
+    }
 
-        let parsed = result.unwrap();
-        assert_eq!(parsed.entries.len(), 2);
-        assert_eq!(parsed.entries[0].text, "Hello, world!");
-        assert_eq!(parsed.entries[1].text, "This is a test.");
+    #[test]
+    fn test_text_transform() {
+        let transform: std::sync::Arc<dyn Fn(&str) -> String + Send + Sync> =
+            std::sync::Arc::new(|t| t.to_uppercase());
+        let processor = ContentProcessor::with_options(YdlOptions::new().text_transform(transform));
+        let srt = "1\n00:00:01,000 --> 00:00:03,000\n<i>hello</i>   world\n";
+
+        // Runs after cleaning, so it sees the text without markup
+        let content = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(content.trim(), "HELLO WORLD");
+
+        // Also applies when the source is already in the requested format
+        let content = processor
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                false,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(content.contains("<I>HELLO</I>   WORLD"));
     }
 
     #[test]
-    fn test_convert_to_srt() {
-        let processor = test_processor();
+    fn test_vtt_timestamp_map() {
         let entries = vec![SubtitleEntry::new(
             Duration::from_secs(1),
-            Duration::from_secs(3),
-            "Hello, world!".to_string(),
+            Duration::from_secs(2),
+            "Hello".to_string(),
         )];
 
-        let result = processor.to_srt_format(&entries);
-        assert!(result.is_ok());
+        let processor = ContentProcessor::with_options(YdlOptions::new().vtt_timestamp_map(900000));
+        let vtt = processor
+            .convert_to_format(&entries, SubtitleType::Vtt, "en")
+            .unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\nX-TIMESTAMP-MAP=MPEGTS:900000,LOCAL:00:00:00.000\n\n00:00:01.000 --> 00:00:02.000\nHello\n"
+        );
 
-        let srt = result.unwrap();
-        assert!(srt.contains("1\n"));
-        assert!(srt.contains("00:00:01,000 --> 00:00:03,000"));
-        assert!(srt.contains("Hello, world!"));
+        // Off by default
+        let vtt = test_processor()
+            .convert_to_format(&entries, SubtitleType::Vtt, "en")
+            .unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:01.000"));
     }
 
     #[test]
-    fn test_convert_to_vtt() {
+    fn test_json_includes_speaker() {
+        let processor = ContentProcessor::with_options(YdlOptions::new().detect_speakers(true));
+        let srt = "1\n00:00:01,000 --> 00:00:03,000\n&gt;&gt; JOHN: Welcome\n\n2\n00:00:03,000 --> 00:00:05,000\nThanks\n";
+
+        let json = processor
+            .process_content(
+                srt,
+                SubtitleType::Json,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["entries"][0]["speaker"], "JOHN");
+        assert_eq!(value["entries"][0]["text"], "Welcome");
+        assert!(value["entries"][1].get("speaker").is_none());
+
+        // Formats without a speaker field keep the label in the text
+        let srt_out = processor
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt_out.contains("\nJOHN: Welcome\n"), "{srt_out}");
+        let txt = processor
+            .process_content(
+                srt,
+                SubtitleType::Txt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(txt.starts_with("JOHN: Welcome"), "{txt}");
+    }
+
+    #[test]
+    fn test_raw_uses_source_format() {
         let processor = test_processor();
-        let entries = vec![SubtitleEntry::new(
-            Duration::from_secs(1),
-            Duration::from_secs(3),
-            "Hello, world!".to_string(),
-        )];
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:03.000\n<b>Hello</b> world\n";
+
+        let result = processor
+            .process_content(
+                vtt,
+                SubtitleType::Raw,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(result.starts_with("WEBVTT"));
+        assert!(result.contains("00:00:01.000 --> 00:00:03.000\nHello world"));
+
+        let xml =
+            r#"<?xml version="1.0"?><transcript><text start="1" dur="2">Hi</text></transcript>"#;
+        let result = processor
+            .process_content(
+                xml,
+                SubtitleType::Raw,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(result.contains("00:00:01,000 --> 00:00:03,000"));
+    }
 
-        let result = processor.to_vtt_format(&entries);
-        assert!(result.is_ok());
+    #[test]
+    fn test_same_format_pass_through() {
+        let processor = test_processor();
+        let srt = "7\n00:00:01,000 --> 00:00:03,000\n<i>Untouched</i>\n";
+
+        let result = processor
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                false,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(result, srt);
+
+        // Cleaning still forces a re-render
+        let result = processor
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(result, "1\n00:00:01,000 --> 00:00:03,000\nUntouched\n");
+    }
 
-        let vtt = result.unwrap();
-        assert!(vtt.starts_with("WEBVTT"));
-        assert!(vtt.contains("00:00:01.000 --> 00:00:03.000"));
-        assert!(vtt.contains("Hello, world!"));
+    #[test]
+    fn test_same_format_input_honors_every_option() {
+        let srt = "1\n00:00:01,000 --> 00:00:01,200\n>> BOB: hello there my friend\n\n\
+            2\n00:00:01,100 --> 00:00:04,000\nsecond line.\n\n\
+            3\n00:00:30,000 --> 00:00:30,100\nthird\n";
+        let render = |options: YdlOptions, input: &str, format, track_type| {
+            ContentProcessor::with_options(options)
+                .process_content(input, format, "en", false, false, &track_type)
+                .unwrap()
+        };
+        let manual = SubtitleTrackType::Manual;
+        assert_eq!(
+            render(YdlOptions::new(), srt, SubtitleType::Srt, manual.clone()),
+            srt
+        );
+
+        let uppercase: std::sync::Arc<dyn Fn(&str) -> String + Send + Sync> =
+            std::sync::Arc::new(|t| t.to_uppercase());
+        let cases = [
+            ("strict_output", YdlOptions::new().strict_output(true)),
+            ("normalize_timing", YdlOptions::new().normalize_timing(true)),
+            ("max_line_length", YdlOptions::new().max_line_length(10)),
+            (
+                "trim_silence",
+                YdlOptions::new().trim_silence(Duration::from_secs(20)),
+            ),
+            (
+                "start_at",
+                YdlOptions::new().start_at(Duration::from_secs(1) + Duration::from_millis(50)),
+            ),
+            ("shift_ms", YdlOptions::new().shift_ms(100)),
+            ("rebase", YdlOptions::new().rebase(Duration::ZERO)),
+            ("timecode", YdlOptions::new().timecode(Timecode::Frames)),
+            (
+                "segmentation",
+                YdlOptions::new().segmentation(Segmentation::NonOverlapping),
+            ),
+            ("detect_speakers", YdlOptions::new().detect_speakers(true)),
+            (
+                "text_transform",
+                YdlOptions::new().text_transform(uppercase),
+            ),
+        ];
+        for (name, options) in cases {
+            assert_ne!(
+                render(options, srt, SubtitleType::Srt, manual.clone()),
+                srt,
+                "{name}"
+            );
+        }
+
+        // Empty cues are dropped by default
+        let with_empty = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nhello\n\n\
+            00:00:03.000 --> 00:00:04.000\n";
+        assert!(
+            !render(
+                YdlOptions::new(),
+                with_empty,
+                SubtitleType::Vtt,
+                manual.clone()
+            )
+            .contains("00:00:03.000")
+        );
+
+        // Auto-generated rolling lines are collapsed
+        let rolling = "1\n00:00:01,000 --> 00:00:02,000\nthe quick\n\n\
+            2\n00:00:02,000 --> 00:00:03,000\nthe quick brown fox\n";
+        assert_ne!(
+            render(
+                YdlOptions::new().segmentation(Segmentation::Original),
+                rolling,
+                SubtitleType::Srt,
+                SubtitleTrackType::AutoGenerated
+            ),
+            rolling
+        );
+
+        // Trailing blank lines are normalized like converted output
+        let padded = format!("{srt}\n\n\n");
+        assert_eq!(
+            render(
+                YdlOptions::new(),
+                &padded,
+                SubtitleType::Srt,
+                manual.clone()
+            ),
+            srt
+        );
+
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nhello\n";
+        assert_ne!(
+            render(
+                YdlOptions::new().vtt_timestamp_map(900_000),
+                vtt,
+                SubtitleType::Vtt,
+                manual
+            ),
+            vtt
+        );
     }
 
     #[test]
-    fn test_convert_to_txt() {
+    fn test_normalize_durations() {
+        let entry = |start: u64, end: u64, text: &str| {
+            SubtitleEntry::new(
+                Duration::from_millis(start),
+                Duration::from_millis(end),
+                text.to_string(),
+            )
+        };
+        let entries = vec![
+            entry(0, 80, "flash"),
+            entry(1_000, 1_900, "small gap"),
+            entry(2_000, 2_050, "squeezed"),
+            entry(2_200, 3_000, "overlapping"),
+            entry(2_900, 4_000, "large gap"),
+            entry(9_000, 9_010, "last"),
+        ];
+
+        let normalized = test_processor().normalize_durations(
+            entries,
+            Duration::from_millis(500),
+            Duration::from_millis(250),
+        );
+        let times: Vec<(u128, u128)> = normalized
+            .iter()
+            .map(|e| (e.start.as_millis(), e.end.as_millis()))
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                (0, 500),
+                (1_000, 2_000),
+                (2_000, 2_200),
+                (2_200, 3_000),
+                (2_900, 4_000),
+                (9_000, 9_500),
+            ]
+        );
+        let texts: Vec<&str> = normalized.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "flash",
+                "small gap",
+                "squeezed",
+                "overlapping",
+                "large gap",
+                "last"
+            ]
+        );
+        // Only the overlap that was already there remains
+        let overlaps = normalized
+            .windows(2)
+            .filter(|pair| pair[0].end > pair[1].start)
+            .count();
+        assert_eq!(overlaps, 1);
+
+        let srt =
+            "1\n00:00:01,000 --> 00:00:01,050\nQuick\n\n2\n00:00:01,200 --> 00:00:02,000\nNext\n";
+        let processed = ContentProcessor::with_options(YdlOptions::new().normalize_timing(true))
+            .process_content(
+                srt,
+                SubtitleType::Srt,
+                "en",
+                false,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(processed.contains("00:00:01,000 --> 00:00:01,200\nQuick"));
+        assert!(processed.contains("00:00:01,200 --> 00:00:02,000\nNext"));
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("Short enough", 42, 2), "Short enough");
+        assert_eq!(
+            wrap_text("The quick brown fox jumps over the lazy dog", 20, 2),
+            "The quick brown fox\njumps over the lazy dog"
+        );
+        assert_eq!(
+            wrap_text("The quick brown fox jumps over the lazy dog", 20, 3),
+            "The quick brown fox\njumps over the lazy\ndog"
+        );
+        // Existing line breaks are refilled when a line is too long
+        assert_eq!(
+            wrap_text("one two\nthree four five six", 9, 3),
+            "one two\nthree\nfour five six"
+        );
+        // Overlong words stay whole
+        assert_eq!(
+            wrap_text("see Donaudampfschifffahrtsgesellschaft now", 10, 2),
+            "see\nDonaudampfschifffahrtsgesellschaft now"
+        );
+        // Widths count characters, so multi-byte text wraps at the same places
+        assert_eq!(
+            wrap_text("café crème brûlée über naïve", 11, 3),
+            "café crème\nbrûlée über\nnaïve"
+        );
+        assert_eq!(
+            wrap_text("日本語の 字幕を 折り返す", 8, 2),
+            "日本語の 字幕を\n折り返す"
+        );
+    }
+
+    #[test]
+    fn test_max_line_length_applies_to_srt_and_vtt() {
+        let srt = "1\n00:00:01,000 --> 00:00:03,000\nThis cue is much too long for a single line\n";
+        let processor = ContentProcessor::with_options(YdlOptions::new().max_line_length(24));
+        let process = |format| {
+            processor
+                .process_content(srt, format, "en", false, true, &SubtitleTrackType::Manual)
+                .unwrap()
+        };
+
+        assert_eq!(
+            process(SubtitleType::Srt),
+            "1\n00:00:01,000 --> 00:00:03,000\nThis cue is much too\nlong for a single line\n"
+        );
+        assert!(
+            process(SubtitleType::Vtt).contains("This cue is much too\nlong for a single line")
+        );
+        assert_eq!(
+            process(SubtitleType::Txt),
+            "This cue is much too long for a single line"
+        );
+    }
+
+    #[test]
+    fn test_strict_output() {
+        let messy = "1\n00:00:05,000 --> 00:00:09,000\nThird\n\n\
+            2\n00:00:00,000 --> 00:00:03,000\nFirst\n\n\
+            3\n00:00:02,000 --> 00:00:04,000\nFirst\n\n\
+            4\n00:00:03,500 --> 00:00:03,600\nSecond\n\n\
+            5\n00:00:05,000 --> 00:00:06,000\nAlongside\n\n\
+            6\n00:00:20,000 --> 00:00:45,000\nLong\n";
+
+        let processor = ContentProcessor::with_options(YdlOptions::new().strict_output(true));
+        let srt = processor
+            .process_content(
+                messy,
+                SubtitleType::Srt,
+                "en",
+                true,
+                false,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:03,500\nFirst\n\n\
+             2\n00:00:03,500 --> 00:00:04,000\nSecond\n\n\
+             3\n00:00:05,000 --> 00:00:09,000\nThird\nAlongside\n\n\
+             4\n00:00:20,000 --> 00:00:30,000\nLong\n"
+        );
+    }
+
+    #[test]
+    fn test_chapters_to_srt() {
+        use crate::types::{Chapter, VideoMetadata};
+
+        let metadata = VideoMetadata::new("dQw4w9WgXcQ".to_string(), "Test".to_string())
+            .with_duration(Duration::from_secs(180))
+            .with_chapters(vec![
+                Chapter::new(Duration::ZERO, "Intro".to_string()),
+                Chapter::new(Duration::from_secs(65), "Setup".to_string()),
+                Chapter::new(Duration::from_secs(120), "Demo".to_string()),
+            ]);
+
+        let srt = test_processor()
+            .convert_to_format(&metadata.chapter_entries(), SubtitleType::Srt, "en")
+            .unwrap();
+
+        assert!(srt.contains("1\n00:00:00,000 --> 00:01:05,000\nIntro"));
+        assert!(srt.contains("2\n00:01:05,000 --> 00:02:00,000\nSetup"));
+        assert!(srt.contains("3\n00:02:00,000 --> 00:03:00,000\nDemo"));
+    }
+
+    #[test]
+    fn test_convert_to_scc() {
         let processor = test_processor();
         let entries = vec![
             SubtitleEntry::new(
                 Duration::from_secs(1),
                 Duration::from_secs(3),
-                "Hello, world!".to_string(),
+                "Hi".to_string(),
             ),
             SubtitleEntry::new(
-                Duration::from_secs(4),
-                Duration::from_secs(6),
-                "This is a test.".to_string(),
+                Duration::from_secs(3),
+                Duration::from_secs(5),
+                "This caption is long enough to need a second row".to_string(),
             ),
         ];
 
-        let result = processor.to_txt_format(&entries);
-        assert!(result.is_ok());
+        let scc = processor.to_scc_format(&entries).unwrap();
+        let lines: Vec<&str> = scc.lines().filter(|l| !l.is_empty()).collect();
 
-        let txt = result.unwrap();
-        assert_eq!(txt, "Hello, world!\nThis is a test.");
+        assert_eq!(lines[0], "Scenarist_SCC V1.0");
+        // Pop-on load of "Hi" on row 15, displayed at 1s (29.97 drop-frame)
+        assert_eq!(
+            lines[1],
+            "00:00:01;00\t94ae 94ae 9420 9420 9470 9470 c8e9 942f 942f"
+        );
+        // The second cue replaces the first directly and uses rows 14 and 15
+        assert!(lines[2].starts_with("00:00:03;00\t94ae 94ae 9420 9420 94d0 94d0"));
+        assert!(lines[2].contains("9470 9470"));
+        // Only the last cue is explicitly cleared
+        assert_eq!(lines[3], "00:00:05;00\t942c 942c");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_convert_to_ttml() {
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_millis(1_500),
+                Duration::from_millis(3_250),
+                "Tom & Jerry\n<3 \"cheese\"".to_string(),
+            ),
+            SubtitleEntry::new(
+                Duration::from_secs(3_723),
+                Duration::from_millis(3_725_004),
+                "Late".to_string(),
+            ),
+        ];
+
+        let ttml = test_processor()
+            .convert_to_format(&entries, SubtitleType::Ttml, "pt-BR")
+            .unwrap();
+        assert!(ttml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\" xml:lang=\"pt-BR\">"));
+        assert!(ttml.contains(
+            "<p begin=\"00:00:01.500\" end=\"00:00:03.250\">Tom &amp; Jerry<br/>&lt;3 &quot;cheese&quot;</p>"
+        ));
+        assert!(ttml.contains("<p begin=\"01:02:03.000\" end=\"01:02:05.004\">Late</p>"));
+        assert!(ttml.trim_end().ends_with("</div>\n  </body>\n</tt>"));
+
+        // Reading the document back (keeping the line break) gives the same cues
+        let parsed = ContentProcessor::with_options(YdlOptions::new().preserve_line_breaks(true))
+            .parse_content(&ttml, "pt-BR")
+            .unwrap();
+        assert_eq!(parsed.entries, entries);
+    }
+
+    #[test]
+    fn test_convert_to_lrc() {
+        let srt = "1\n00:00:01,000 --> 00:00:03,456\nFirst line\nsecond line\n\n2\n00:00:02,000 --> 00:00:04,000\nOverlapping\n\n3\n01:02:03,004 --> 01:02:05,000\nLate\n";
+
+        let plain = test_processor()
+            .process_content(
+                srt,
+                SubtitleType::Lrc,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert_eq!(
+            plain,
+            "[00:01.00]First line second line\n[00:02.00]Overlapping\n[62:03.00]Late\n"
+        );
+
+        let tagged = ContentProcessor::new()
+            .with_metadata("Song", Some(Duration::from_secs(212)))
+            .to_lrc_format(&[SubtitleEntry::new(
+                Duration::from_millis(1_234),
+                Duration::from_millis(2_000),
+                "La".to_string(),
+            )])
+            .unwrap();
+        assert_eq!(tagged, "[ti:Song]\n[length:03:32]\n[00:01.23]La\n");
+    }
+
+    #[test]
+    fn test_convert_to_ass() {
+        let processor =
+            ContentProcessor::with_options(YdlOptions::new().preserve_line_breaks(true));
+        let srt = "1\n00:00:01,000 --> 00:00:03,456\n<i>First</i> line\nsecond {line}\n\n2\n00:01:02,000 --> 00:01:04,500\nDone\n";
+
+        let ass = processor
+            .process_content(
+                srt,
+                SubtitleType::Ass,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+
+        assert!(ass.starts_with("[Script Info]\nScriptType: v4.00+\nLanguage: en\n"));
+        let styles = ass.find("[V4+ Styles]").unwrap();
+        let events = ass.find("[Events]").unwrap();
+        assert!(styles < events);
+        assert!(ass[styles..events].contains("\nStyle: Default,"));
+
+        let dialogue: Vec<&str> = ass
+            .lines()
+            .filter(|line| line.starts_with("Dialogue:"))
+            .collect();
+        assert_eq!(
+            dialogue,
+            vec![
+                "Dialogue: 0,0:00:01.00,0:00:03.46,Default,,0,0,0,,First line\\Nsecond (line)",
+                "Dialogue: 0,0:01:02.00,0:01:04.50,Default,,0,0,0,,Done",
+            ]
+        );
     }
 
     #[test]
@@ -751,6 +3384,162 @@ This is a test.
         assert!(processor.validate_timing(&invalid_entries).is_err());
     }
 
+    /// Run `f` with a subscriber that records formatted log output at info level and above
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, String) {
+        capture_logs_at(tracing::Level::INFO, f)
+    }
+
+    /// Run `f` with a subscriber that records formatted log output at `level` and above
+    pub(crate) fn capture_logs_at<T>(level: tracing::Level, f: impl FnOnce() -> T) -> (T, String) {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(level)
+            .with_ansi(false)
+            .finish();
+        let result = tracing::subscriber::with_default(subscriber, f);
+
+        let logs = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        (result, logs)
+    }
+
+    #[test]
+    fn test_lenient_blocks_skip_corrupt_block() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n\
+                   2\n00:00:0X,000 -> garbage\nCorrupt\n\n\
+                   3\n00:00:05,000 --> 00:00:06,000\nThird\n";
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nFirst\n\n\
+                   corrupt cue without timing\n\n\
+                   00:00:05.000 --> 00:00:06.000\nThird\n";
+
+        for (content, format) in [(srt, "SRT"), (vtt, "VTT")] {
+            let (parsed, logs) =
+                capture_logs(|| test_processor().parse_subtitle_content(content, "en"));
+            let texts: Vec<String> = parsed
+                .unwrap()
+                .entries
+                .into_iter()
+                .map(|e| e.text)
+                .collect();
+            assert_eq!(texts, vec!["First", "Third"], "{format}");
+            assert!(
+                logs.contains(&format!("Skipped 1 malformed {} block(s)", format)),
+                "{format} logs: {logs}"
+            );
+
+            let strict = ContentProcessor::with_options(YdlOptions::new().lenient_blocks(false));
+            assert!(
+                strict.parse_subtitle_content(content, "en").is_err(),
+                "{format}"
+            );
+        }
+
+        // Lenient parsing still fails when nothing is salvageable
+        let all_corrupt = "1\nnot a timing line\ntext\n\n2\nstill not\ntext\n";
+        assert!(
+            test_processor()
+                .parse_srt_content(all_corrupt, "en")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_malformed_block_errors_report_line_and_context() {
+        let strict = ContentProcessor::with_options(YdlOptions::new().lenient_blocks(false));
+        let location = |result: YdlResult<ParsedSubtitles>| match result {
+            Err(YdlError::SubtitleParsing { line, context, .. }) => (line, context),
+            other => panic!("expected a parsing error, got {:?}", other),
+        };
+
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n\
+            2\n00:00:02,000 -> 00:00:03,000\nSecond\n";
+        assert_eq!(
+            location(strict.parse_srt_content(srt, "en")),
+            (Some(6), Some("00:00:02,000 -> 00:00:03,000".to_string()))
+        );
+        let streamed: Vec<_> = strict.parse_srt_iter(srt).collect();
+        let Err(err) = &streamed[1] else {
+            panic!("expected the second block to fail");
+        };
+        assert_eq!(
+            err.to_string(),
+            "Subtitle parsing error: Malformed SRT block at line 6: 00:00:02,000 -> 00:00:03,000"
+        );
+
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nFirst\n\n\n\
+            stray text without timing\n";
+        assert_eq!(
+            location(strict.parse_vtt_content(vtt, "en")),
+            (Some(7), Some("stray text without timing".to_string()))
+        );
+
+        // Long lines are truncated in the context
+        let long = format!("1\n{}\ntext\n", "x".repeat(200));
+        let (_, context) = location(strict.parse_srt_content(&long, "en"));
+        assert_eq!(context.unwrap(), format!("{}...", "x".repeat(60)));
+    }
+
+    #[test]
+    fn test_parse_ttml() {
+        let processor = test_processor();
+        let ttml = r#"<?xml version="1.0" encoding="utf-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:ttp="http://www.w3.org/ns/ttml#parameter" ttp:tickRate="10000000" xml:lang="en">
+  <body><div>
+    <p begin="00:00:01.000" end="00:00:03.500">Hello <span tts:color="white">world</span></p>
+    <p begin="4s" dur="1500ms">First line<br/>second &amp; last</p>
+    <p begin="60000000t" end="70000000t">Tick based</p>
+  </div></body>
+</tt>"#;
+
+        let parsed = processor.parse_subtitle_content(ttml, "en").unwrap();
+        assert_eq!(parsed.entries.len(), 3);
+        assert_eq!(parsed.entries[0].start, Duration::from_millis(1000));
+        assert_eq!(parsed.entries[0].end, Duration::from_millis(3500));
+        assert_eq!(parsed.entries[0].text, "Hello world");
+        assert_eq!(parsed.entries[1].end, Duration::from_millis(5500));
+        assert_eq!(parsed.entries[1].text, "First line\nsecond & last");
+        assert_eq!(parsed.entries[2].start, Duration::from_secs(6));
+
+        let srt = processor
+            .process_content(
+                ttml,
+                SubtitleType::Srt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.starts_with("1\n00:00:01,000 --> 00:00:03,500\nHello world\n"));
+
+        assert_eq!(
+            parse_ttml_time("00:00:02:15", 30.0, 1.0),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            parse_ttml_time("1.5m", 30.0, 1.0),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(parse_ttml_time("soon", 30.0, 1.0), None);
+    }
+
     #[test]
     fn test_parse_youtube_xml() {
         let processor = test_processor();
@@ -768,4 +3557,101 @@ This is a test.
         assert_eq!(parsed.entries[0].text, "Hello world");
         assert_eq!(parsed.entries[1].text, "This is a test");
     }
+
+    #[test]
+    fn test_parse_json3() {
+        let processor = test_processor();
+        let json3 = r#"{"wireMagic":"pb3","events":[
+            {"tStartMs":0,"dDurationMs":60000,"id":1,"wpWinPosId":1,"wsWinStyleId":1},
+            {"tStartMs":500,"dDurationMs":1500,"wWinId":1,"segs":[{"utf8":"we're"},{"utf8":" live","tOffsetMs":400}]},
+            {"tStartMs":2000,"wWinId":1,"aAppend":1,"segs":[{"utf8":"\n"}]},
+            {"tStartMs":2000,"wWinId":1,"segs":[{"utf8":"no duration"}]}
+        ]}"#;
+
+        let parsed = processor.parse_subtitle_content(json3, "en").unwrap();
+        assert_eq!(parsed.original_format, SubtitleType::Raw);
+        let texts: Vec<&str> = parsed.entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["we're live", "no duration"]);
+        assert_eq!(parsed.entries[0].start, Duration::from_millis(500));
+        assert_eq!(parsed.entries[0].end, Duration::from_millis(2000));
+        assert_eq!(parsed.entries[1].end, Duration::from_millis(3000));
+
+        let srt = processor
+            .process_content(
+                json3,
+                SubtitleType::Srt,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        assert!(srt.starts_with("1\n00:00:00,500 --> 00:00:02,000\nwe're live\n"));
+
+        // An events list with no text is not captions
+        assert!(
+            processor
+                .parse_subtitle_content(r#"{"events":[{"tStartMs":0}]}"#, "en")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_srv3_xml() {
+        let processor = test_processor();
+        let xml_content = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3"><body>
+<p t="0" d="1500" w="1"><s ac="0">we</s><s t="400" ac="0">&#39;re</s><s t="800"> live</s></p>
+<p t="1500">plain cue</p>
+<p t="3000" d="500">spans
+lines</p>
+<p t="4000" d="800"><s>last</s></p>
+</body></timedtext>"#;
+
+        let parsed = processor
+            .parse_youtube_xml_content(xml_content, "en")
+            .unwrap();
+        let texts: Vec<&str> = parsed.entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["we're live", "plain cue", "last"]);
+        assert_eq!(parsed.entries[0].end, Duration::from_millis(1500));
+        // A missing duration defaults to one second
+        assert_eq!(parsed.entries[1].end, Duration::from_millis(2500));
+        assert_eq!(parsed.entries[2].start, Duration::from_millis(4000));
+
+        // Per-word offsets are kept only where the source timed the words
+        assert_eq!(
+            parsed.entries[0].word_timings,
+            Some(vec![
+                (Duration::from_millis(0), "we".to_string()),
+                (Duration::from_millis(400), "'re".to_string()),
+                (Duration::from_millis(800), "live".to_string()),
+            ])
+        );
+        assert_eq!(parsed.entries[1].word_timings, None);
+        assert_eq!(parsed.entries[2].word_timings, None);
+    }
+
+    #[test]
+    fn test_json_includes_word_timings() {
+        let processor = test_processor();
+        let xml_content = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3"><body>
+<p t="1000" d="1500"><s>hello</s><s t="600"> there</s></p>
+<p t="3000" d="500">plain cue</p>
+</body></timedtext>"#;
+
+        let json = processor
+            .process_content(
+                xml_content,
+                SubtitleType::Json,
+                "en",
+                true,
+                true,
+                &SubtitleTrackType::Manual,
+            )
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["entries"][0]["words"][0]["start"], 1.0);
+        assert_eq!(value["entries"][0]["words"][1]["start"], 1.6);
+        assert_eq!(value["entries"][0]["words"][1]["text"], "there");
+        assert!(value["entries"][1].get("words").is_none());
+    }
 }
@@ -1,4 +1,9 @@
+use crate::cache::AvailabilityCache;
+use crate::metrics::{Metrics, NoopMetrics};
+use crate::youtube_client::ClientType;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Available subtitle formats
@@ -14,9 +19,45 @@ pub enum SubtitleType {
     Json,
     /// Raw format as received from source
     Raw,
+    /// Scenarist Closed Caption format (.scc, CEA-608)
+    Scc,
+    /// Advanced SubStation Alpha format (.ass), for Aegisub and libass
+    Ass,
+    /// LRC lyrics format (.lrc), for music players
+    Lrc,
+    /// W3C Timed Text (.ttml, also known as DFXP), for broadcast and OTT pipelines
+    Ttml,
 }
 
 impl SubtitleType {
+    /// Every supported format, in declaration order
+    pub const ALL: &'static [SubtitleType] = &[
+        SubtitleType::Srt,
+        SubtitleType::Vtt,
+        SubtitleType::Txt,
+        SubtitleType::Json,
+        SubtitleType::Raw,
+        SubtitleType::Scc,
+        SubtitleType::Ass,
+        SubtitleType::Lrc,
+        SubtitleType::Ttml,
+    ];
+
+    /// Canonical name of the format, as accepted by `FromStr`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubtitleType::Srt => "srt",
+            SubtitleType::Vtt => "vtt",
+            SubtitleType::Txt => "txt",
+            SubtitleType::Json => "json",
+            SubtitleType::Raw => "raw",
+            SubtitleType::Scc => "scc",
+            SubtitleType::Ass => "ass",
+            SubtitleType::Lrc => "lrc",
+            SubtitleType::Ttml => "ttml",
+        }
+    }
+
     /// Get file extension for the format
     pub fn extension(&self) -> &'static str {
         match self {
@@ -25,6 +66,10 @@ impl SubtitleType {
             SubtitleType::Txt => "txt",
             SubtitleType::Json => "json",
             SubtitleType::Raw => "xml",
+            SubtitleType::Scc => "scc",
+            SubtitleType::Ass => "ass",
+            SubtitleType::Lrc => "lrc",
+            SubtitleType::Ttml => "ttml",
         }
     }
 
@@ -36,6 +81,10 @@ impl SubtitleType {
             SubtitleType::Txt => "text/plain",
             SubtitleType::Json => "application/json",
             SubtitleType::Raw => "application/xml",
+            SubtitleType::Scc => "text/x-scc",
+            SubtitleType::Ass => "text/x-ssa",
+            SubtitleType::Lrc => "application/x-lrc",
+            SubtitleType::Ttml => "application/ttml+xml",
         }
     }
 }
@@ -50,6 +99,10 @@ impl std::str::FromStr for SubtitleType {
             "txt" => Ok(SubtitleType::Txt),
             "json" => Ok(SubtitleType::Json),
             "raw" | "xml" => Ok(SubtitleType::Raw),
+            "scc" => Ok(SubtitleType::Scc),
+            "ass" | "ssa" => Ok(SubtitleType::Ass),
+            "lrc" => Ok(SubtitleType::Lrc),
+            "ttml" | "dfxp" => Ok(SubtitleType::Ttml),
             _ => Err(crate::error::YdlError::UnsupportedFormat {
                 format: s.to_string(),
             }),
@@ -59,13 +112,7 @@ impl std::str::FromStr for SubtitleType {
 
 impl std::fmt::Display for SubtitleType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SubtitleType::Srt => write!(f, "srt"),
-            SubtitleType::Vtt => write!(f, "vtt"),
-            SubtitleType::Txt => write!(f, "txt"),
-            SubtitleType::Json => write!(f, "json"),
-            SubtitleType::Raw => write!(f, "raw"),
-        }
+        f.write_str(self.name())
     }
 }
 
@@ -81,37 +128,240 @@ pub struct YdlOptions {
     /// Whether to prefer manual over auto-generated subtitles
     pub prefer_manual: bool,
 
+    /// Whether to exclude machine-translated tracks, so a requested language only
+    /// matches a genuine track in that language
+    pub no_translations: bool,
+
     /// Maximum retry attempts for failed requests
     pub max_retries: u32,
 
-    /// Request timeout in seconds
-    pub timeout_seconds: u64,
+    /// Longest time in seconds a whole request, including reading the response, may take;
+    /// `None` (the default) leaves slow downloads to `read_timeout_seconds`
+    pub timeout_seconds: Option<u64>,
+
+    /// Longest wait in seconds for each read of a response, so a stalled download fails
+    /// while a slow but steady one keeps going
+    pub read_timeout_seconds: u64,
+
+    /// Longest wait in seconds to establish a connection
+    pub connect_timeout_seconds: u64,
 
     /// Custom User-Agent string
     pub user_agent: Option<String>,
 
-    /// Proxy settings
+    /// Proxy URL for every request; when unset, `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY`
+    /// and `NO_PROXY` from the environment apply
     pub proxy: Option<String>,
 
+    /// Directory output files are written to; `None` uses the current directory
+    pub output_dir: Option<PathBuf>,
+
+    /// Whether a missing output directory is created rather than reported as an error
+    pub create_dirs: bool,
+
+    /// Netscape-format cookies file whose YouTube cookies are sent with every request;
+    /// when unset, the file named by the `YDL_COOKIES` environment variable is used
+    pub cookies_file: Option<PathBuf>,
+
+    /// Raw `Cookie` header value sent with every request; takes precedence over
+    /// `cookies_file`
+    pub cookie_header: Option<String>,
+
     /// Whether to clean/normalize subtitle content
     pub clean_content: bool,
 
+    /// Whether cleaning keeps line breaks within a cue (including `<br>` tags);
+    /// off by default, so each cue is collapsed onto a single line
+    pub preserve_line_breaks: bool,
+
     /// Whether to validate subtitle timing
     pub validate_timing: bool,
+
+    /// Whether SRT/VTT parsing skips malformed blocks instead of failing on the first one
+    pub lenient_blocks: bool,
+
+    /// Whether output cues are sorted, de-duplicated and de-overlapped for strict players
+    pub strict_output: bool,
+
+    /// Whether short cues are lengthened and short gaps closed (see
+    /// [`ContentProcessor::normalize_durations`](crate::processor::ContentProcessor::normalize_durations))
+    pub normalize_timing: bool,
+
+    /// Whether converted output drops cues whose text is empty after cleaning and transforms
+    pub drop_empty_cues: bool,
+
+    /// Longest line, in characters, of SRT and VTT cues; longer cues are word-wrapped
+    pub max_line_length: Option<usize>,
+
+    /// Most lines a cue is wrapped into with `max_line_length`; the last line takes
+    /// whatever does not fit
+    pub max_lines: usize,
+
+    /// Silence gap that separates an intro or outro from the main body; when set, such
+    /// leading and trailing cues are dropped (see [`ParsedSubtitles::trim_silence`])
+    pub trim_silence: Option<Duration>,
+
+    /// Playback offset before which cues are dropped, e.g. a URL's `t=` start time;
+    /// `None` keeps every cue
+    pub start_at: Option<Duration>,
+
+    /// Milliseconds added to every cue time after parsing; negative values move cues
+    /// earlier (see [`ContentProcessor::shift_timing`](crate::processor::ContentProcessor::shift_timing))
+    pub shift_ms: i64,
+
+    /// Time the earliest cue is moved to, keeping the others' relative timing, applied
+    /// after `shift_ms` and `start_at` (see [`ParsedSubtitles::rebase`])
+    pub rebase: Option<Duration>,
+
+    /// Pause that ends a paragraph when TXT output merges cues into paragraphs (see
+    /// [`ContentProcessor::to_txt_paragraphs`](crate::processor::ContentProcessor::to_txt_paragraphs));
+    /// `None` writes one cue per line
+    pub txt_paragraphs: Option<Duration>,
+
+    /// Smallest download, in bytes, accepted as caption content; smaller downloads,
+    /// and ones that parse to no cues at all, fall through to the next download method
+    pub min_content_bytes: usize,
+
+    /// Fraction of the video's length a track's cues must cover before it is trusted;
+    /// `None` skips the check
+    pub min_caption_coverage: Option<f64>,
+
+    /// Whether the track is chosen by quality score instead of the manual-first heuristic
+    pub select_by_quality: bool,
+
+    /// Weights of the signals combined into a track's quality score
+    pub quality_weights: QualityWeights,
+
+    /// Whether plain-text output prefixes each paragraph with a timestamped video link
+    pub timestamp_links: bool,
+
+    /// Frame rate used for frame-based timecodes (29.97 uses drop-frame counting)
+    pub frame_rate: f64,
+
+    /// How SRT cue times are written; frame timecodes are for importing into editors
+    pub timecode: Timecode,
+
+    /// Cue segmentation mode; `None` picks one based on the selected track type
+    pub segmentation: Option<Segmentation>,
+
+    /// Whether to recognize leading speaker labels (`>> BOB:`, `- Alice:`) in cue text
+    pub detect_speakers: bool,
+
+    /// Force the `fmt=` requested from YouTube, overriding the automatic srv3 default
+    pub source_format: Option<SourceFormat>,
+
+    /// Leave caption base URLs as YouTube sent them, only adding `fmt=` when they have none
+    pub respect_base_url_fmt: bool,
+
+    /// User-Agents to rotate through when YouTube serves a bot-check or consent page
+    pub user_agent_pool: Vec<String>,
+
+    /// Instrumentation hook for requests, downloads, retries and client outcomes
+    pub metrics: Arc<dyn Metrics>,
+
+    /// Maximum number of videos downloaded at once by multi-video operations
+    pub concurrency: usize,
+
+    /// Custom rewrite of each cue's text, run after built-in cleaning
+    pub text_transform: Option<TextTransform>,
+
+    /// Discovery results shared across instances; `None` always asks YouTube
+    pub availability_cache: Option<Arc<AvailabilityCache>>,
+
+    /// MPEG-TS timestamp (90 kHz ticks) for an HLS `X-TIMESTAMP-MAP` header in VTT output;
+    /// `None` writes standard WebVTT
+    pub vtt_timestamp_map: Option<u64>,
+
+    /// Extra fields merged into every InnerTube request context, by section
+    /// (e.g. `{"client": {"clientScreen": "EMBED"}}`)
+    pub innertube_context: Option<serde_json::Value>,
+
+    /// InnerTube clients to discover tracks with, in order; `None` tries all of
+    /// [`ClientType::ALL`]
+    pub innertube_clients: Option<Vec<ClientType>>,
+
+    /// HTTP client to send every request with, instead of clients ydl builds itself
+    pub http_client: Option<reqwest::Client>,
+}
+
+/// A user-supplied function rewriting cue text (see [`YdlOptions::text_transform`])
+#[derive(Clone)]
+pub struct TextTransform(pub Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl TextTransform {
+    pub fn apply(&self, text: &str) -> String {
+        (self.0)(text)
+    }
+}
+
+impl std::fmt::Debug for TextTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TextTransform(..)")
+    }
 }
 
+/// Realistic desktop and mobile browser User-Agents used for rotation by default
+pub const DEFAULT_USER_AGENT_POOL: &[&str] = &[
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15",
+    "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+];
+
 impl Default for YdlOptions {
     fn default() -> Self {
         Self {
             language: None,             // Auto-detect
             allow_auto_generated: true, // Default to allowing auto-generated
             prefer_manual: true,
+            no_translations: false,
             max_retries: 3,
-            timeout_seconds: 30,
+            timeout_seconds: None,
+            read_timeout_seconds: 10,
+            connect_timeout_seconds: 10,
             user_agent: None, // Use default
             proxy: None,
+            cookies_file: None,
+            cookie_header: None,
+            output_dir: None,
+            create_dirs: true,
             clean_content: true,
+            preserve_line_breaks: false,
             validate_timing: true,
+            lenient_blocks: true,
+            strict_output: false,
+            normalize_timing: false,
+            drop_empty_cues: true,
+            max_line_length: None,
+            max_lines: 2,
+            trim_silence: None,
+            txt_paragraphs: None,
+            shift_ms: 0,
+            start_at: None,
+            rebase: None,
+            min_content_bytes: 1,
+            min_caption_coverage: None,
+            select_by_quality: false,
+            quality_weights: QualityWeights::default(),
+            timestamp_links: false,
+            frame_rate: 29.97,
+            timecode: Timecode::Clock,
+            segmentation: None,
+            detect_speakers: false,
+            source_format: None,
+            respect_base_url_fmt: false,
+            user_agent_pool: DEFAULT_USER_AGENT_POOL
+                .iter()
+                .map(|ua| ua.to_string())
+                .collect(),
+            metrics: Arc::new(NoopMetrics),
+            concurrency: 4,
+            text_transform: None,
+            availability_cache: None,
+            vtt_timestamp_map: None,
+            innertube_context: None,
+            innertube_clients: None,
+            http_client: None,
         }
     }
 }
@@ -138,13 +388,31 @@ impl YdlOptions {
         self
     }
 
+    pub fn no_translations(mut self, exclude: bool) -> Self {
+        self.no_translations = exclude;
+        self
+    }
+
     pub fn max_retries(mut self, retries: u32) -> Self {
         self.max_retries = retries;
         self
     }
 
+    /// Cap the whole of each request, including reading the response, at `seconds`
     pub fn timeout(mut self, seconds: u64) -> Self {
-        self.timeout_seconds = seconds;
+        self.timeout_seconds = Some(seconds);
+        self
+    }
+
+    /// Fail a response that sends nothing for `seconds`, independently of any overall timeout
+    pub fn read_timeout(mut self, seconds: u64) -> Self {
+        self.read_timeout_seconds = seconds;
+        self
+    }
+
+    /// Fail connection attempts after `seconds`, independently of the read timeout
+    pub fn connect_timeout(mut self, seconds: u64) -> Self {
+        self.connect_timeout_seconds = seconds;
         self
     }
 
@@ -158,15 +426,482 @@ impl YdlOptions {
         self
     }
 
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    pub fn create_dirs(mut self, create: bool) -> Self {
+        self.create_dirs = create;
+        self
+    }
+
+    pub fn cookies_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cookies_file = Some(path.into());
+        self
+    }
+
+    /// Send a raw `Cookie` header value (`SID=...; HSID=...`, optionally prefixed
+    /// with `Cookie:`) with every request
+    pub fn cookie_header(mut self, header: &str) -> Self {
+        let header = header.trim();
+        let header = header.strip_prefix("Cookie:").unwrap_or(header);
+        self.cookie_header = Some(header.trim().to_string());
+        self
+    }
+
+    /// Send cookies from a Netscape-format cookies file or a raw `Cookie` header value
+    ///
+    /// An existing file is always read as a cookies file; otherwise values containing
+    /// `=` are used as the header (see [`Self::cookie_header`]) and anything else is
+    /// taken as a file path. Cookies of a signed-in session let ydl fetch captions of
+    /// age-restricted and members-only videos.
+    pub fn cookies(self, cookies: &str) -> Self {
+        let cookies = cookies.trim();
+        if !Path::new(cookies).is_file() && cookies.contains('=') {
+            self.cookie_header(cookies)
+        } else {
+            self.cookies_file(cookies)
+        }
+    }
+
     pub fn clean_content(mut self, clean: bool) -> Self {
         self.clean_content = clean;
         self
     }
 
+    /// Keep line breaks within a cue when cleaning instead of collapsing them
+    pub fn preserve_line_breaks(mut self, preserve: bool) -> Self {
+        self.preserve_line_breaks = preserve;
+        self
+    }
+
     pub fn validate_timing(mut self, validate: bool) -> Self {
         self.validate_timing = validate;
         self
     }
+
+    pub fn timestamp_links(mut self, enabled: bool) -> Self {
+        self.timestamp_links = enabled;
+        self
+    }
+
+    /// Skip (and log) malformed SRT/VTT blocks rather than failing the whole parse
+    ///
+    /// Even when lenient, parsing fails if no valid cue is left.
+    pub fn lenient_blocks(mut self, lenient: bool) -> Self {
+        self.lenient_blocks = lenient;
+        self
+    }
+
+    /// Tidy output cues for picky players (VLC, TVs), after segmentation
+    ///
+    /// In order, this:
+    /// 1. drops cues with no text;
+    /// 2. sorts cues by start time;
+    /// 3. merges a cue into the previous one if it repeats its text and overlaps or
+    ///    touches it, and combines cues starting at the same time into one multi-line cue;
+    /// 4. clamps each cue's duration to between
+    ///    [`STRICT_MIN_DURATION`](crate::processor::STRICT_MIN_DURATION) and
+    ///    [`STRICT_MAX_DURATION`](crate::processor::STRICT_MAX_DURATION);
+    /// 5. ends each cue no later than the next one starts.
+    pub fn strict_output(mut self, strict: bool) -> Self {
+        self.strict_output = strict;
+        self
+    }
+
+    /// Lengthen cues shorter than [`NORMALIZE_MIN_DURATION`](crate::processor::NORMALIZE_MIN_DURATION)
+    /// and close gaps shorter than [`NORMALIZE_MAX_GAP`](crate::processor::NORMALIZE_MAX_GAP),
+    /// so auto-generated text does not flash
+    pub fn normalize_timing(mut self, normalize: bool) -> Self {
+        self.normalize_timing = normalize;
+        self
+    }
+
+    /// Drop cues left without text (on by default), so players do not flash empty boxes
+    ///
+    /// Numeric cue identifiers are renumbered when cues are dropped, keeping SRT
+    /// sequence numbers contiguous. Content already in the requested format is
+    /// re-rendered when it has an empty cue, so this applies to it as well.
+    pub fn drop_empty_cues(mut self, drop: bool) -> Self {
+        self.drop_empty_cues = drop;
+        self
+    }
+
+    /// Word-wrap SRT and VTT cues to lines of at most `length` characters
+    ///
+    /// Lines break at spaces only; a word longer than `length` is kept whole on its
+    /// own line. Cues whose lines already fit are left as they are.
+    pub fn max_line_length(mut self, length: usize) -> Self {
+        self.max_line_length = Some(length.max(1));
+        self
+    }
+
+    /// Wrap cues into at most `lines` lines (default 2) with `max_line_length`
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = lines.max(1);
+        self
+    }
+
+    /// Treat downloads shorter than `bytes` as holding no captions
+    ///
+    /// Such a download falls through to the next download method, like one that
+    /// parses to no cues at all.
+    pub fn min_content_bytes(mut self, bytes: usize) -> Self {
+        self.min_content_bytes = bytes;
+        self
+    }
+
+    /// Distrust tracks whose cues cover less than `fraction` (0.0-1.0) of the video
+    ///
+    /// A suspiciously sparse download is retried with the video's other tracks in the
+    /// same language; if none does better, the original is kept and a warning logged.
+    /// The check costs one extra player request to learn the video's length.
+    pub fn min_caption_coverage(mut self, fraction: f64) -> Self {
+        self.min_caption_coverage = Some(fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Choose the track with the best quality score (see [`QualityWeights`])
+    ///
+    /// Every candidate track is downloaded and scored before one is chosen, which costs
+    /// one download per track plus a player request to learn the video's length.
+    pub fn select_by_quality(mut self, enabled: bool) -> Self {
+        self.select_by_quality = enabled;
+        self
+    }
+
+    pub fn quality_weights(mut self, weights: QualityWeights) -> Self {
+        self.quality_weights = weights;
+        self
+    }
+
+    pub fn frame_rate(mut self, fps: f64) -> Self {
+        self.frame_rate = fps;
+        self
+    }
+
+    /// Write SRT cue times as `timecode` instead of clock time
+    ///
+    /// This only applies when cues are converted to SRT; other formats keep their own
+    /// time syntax. A non-default timecode also stops an SRT download from being passed
+    /// through unchanged, so its cue times are rewritten too.
+    pub fn timecode(mut self, timecode: Timecode) -> Self {
+        self.timecode = timecode;
+        self
+    }
+
+    /// Drop intro and outro cues separated from the main body by at least `gap` of silence
+    pub fn trim_silence(mut self, gap: Duration) -> Self {
+        self.trim_silence = Some(gap);
+        self
+    }
+
+    /// Only keep cues starting at or after `start`
+    pub fn start_at(mut self, start: Duration) -> Self {
+        self.start_at = Some(start);
+        self
+    }
+
+    /// Shift every cue by `offset` milliseconds (negative is earlier)
+    pub fn shift_ms(mut self, offset: i64) -> Self {
+        self.shift_ms = offset;
+        self
+    }
+
+    /// Move all cues so the earliest one starts at `start`
+    pub fn rebase(mut self, start: Duration) -> Self {
+        self.rebase = Some(start);
+        self
+    }
+
+    /// Merge TXT output into paragraphs, ending one at a pause longer than `gap` or a sentence end
+    pub fn txt_paragraphs(mut self, gap: Duration) -> Self {
+        self.txt_paragraphs = Some(gap);
+        self
+    }
+
+    /// Start VTT output with `X-TIMESTAMP-MAP=MPEGTS:<mpegts>,LOCAL:00:00:00.000` for HLS
+    ///
+    /// `mpegts` is in 90 kHz ticks; HLS packagers commonly use 900000 (10 seconds).
+    pub fn vtt_timestamp_map(mut self, mpegts: u64) -> Self {
+        self.vtt_timestamp_map = Some(mpegts);
+        self
+    }
+
+    /// Recognize leading speaker labels in cue text
+    ///
+    /// JSON gets the speaker as a separate field and ASS puts it in the `Name`
+    /// field; other formats keep it in the text, written as `NAME: text`.
+    pub fn detect_speakers(mut self, detect: bool) -> Self {
+        self.detect_speakers = detect;
+        self
+    }
+
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Rewrite each cue's text with `transform` (redaction, abbreviation expansion, ...)
+    ///
+    /// The transform sees the text after built-in cleaning (when enabled) and after
+    /// speaker labels have been split off, and runs before segmentation and format
+    /// conversion.
+    pub fn text_transform(mut self, transform: Arc<dyn Fn(&str) -> String + Send + Sync>) -> Self {
+        self.text_transform = Some(TextTransform(transform));
+        self
+    }
+
+    /// Reuse track discovery results from `cache`, which may be shared with other instances
+    pub fn availability_cache(mut self, cache: Arc<AvailabilityCache>) -> Self {
+        self.availability_cache = Some(cache);
+        self
+    }
+
+    /// Merge extra fields into the InnerTube request context
+    ///
+    /// `context` maps context sections (`client`, `thirdParty`, `user`, ...) to objects
+    /// whose fields are added to that section. The client name and version identify
+    /// each InnerTube client and are never overridden.
+    pub fn innertube_context(mut self, context: serde_json::Value) -> Self {
+        self.innertube_context = Some(context);
+        self
+    }
+
+    /// Only discover tracks with `clients`, tried in this order first
+    ///
+    /// Useful when a video only yields captions from specific clients, or to skip
+    /// clients known not to work.
+    pub fn innertube_clients(mut self, clients: Vec<ClientType>) -> Self {
+        self.innertube_clients = Some(clients);
+        self
+    }
+
+    /// Send all requests with `client`, e.g. to share a connection pool or custom TLS setup
+    ///
+    /// ydl still adds the headers YouTube expects (User-Agent, InnerTube client
+    /// identity, cookies) to each request. Timeouts, proxies and the redirect policy
+    /// are the client's own; the corresponding options are not applied to it.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    pub fn user_agent_pool(mut self, pool: Vec<String>) -> Self {
+        self.user_agent_pool = pool;
+        self
+    }
+
+    pub fn source_format(mut self, format: SourceFormat) -> Self {
+        self.source_format = Some(format);
+        self
+    }
+
+    /// Keep a caption base URL's existing parameters, including its `fmt=`
+    ///
+    /// Without this, interstitial parameters are stripped and `source_format`
+    /// replaces any `fmt=` already present. When set, the URL is downloaded as is,
+    /// with `fmt=` (`source_format`, or srv3) appended only if it has no `fmt`
+    /// parameter at all.
+    pub fn respect_base_url_fmt(mut self, respect: bool) -> Self {
+        self.respect_base_url_fmt = respect;
+        self
+    }
+
+    pub fn segmentation(mut self, mode: Segmentation) -> Self {
+        self.segmentation = Some(mode);
+        self
+    }
+
+    /// Segmentation mode to use for a track, honoring an explicit choice
+    pub fn segmentation_for(&self, track_type: &SubtitleTrackType) -> Segmentation {
+        self.segmentation
+            .unwrap_or_else(|| Segmentation::default_for(track_type))
+    }
+
+    /// Whether any option that shapes rendered cues differs from its default
+    ///
+    /// Content already in the requested format is only passed through unconverted
+    /// when this is false. Every option read while parsing timing or rendering cues
+    /// must be listed here, or it is ignored for such content.
+    pub fn alters_cues(&self) -> bool {
+        let default = YdlOptions::default();
+        self.preserve_line_breaks != default.preserve_line_breaks
+            || self.strict_output != default.strict_output
+            || self.normalize_timing != default.normalize_timing
+            || self.drop_empty_cues != default.drop_empty_cues
+            || self.max_line_length != default.max_line_length
+            || self.max_lines != default.max_lines
+            || self.trim_silence != default.trim_silence
+            || self.start_at != default.start_at
+            || self.shift_ms != default.shift_ms
+            || self.rebase != default.rebase
+            || self.txt_paragraphs != default.txt_paragraphs
+            || self.timestamp_links != default.timestamp_links
+            || self.frame_rate != default.frame_rate
+            || self.timecode != default.timecode
+            || self.segmentation != default.segmentation
+            || self.detect_speakers != default.detect_speakers
+            || self.text_transform.is_some()
+            || self.vtt_timestamp_map != default.vtt_timestamp_map
+    }
+}
+
+/// Caption formats YouTube can serve, selected with the `fmt=` parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceFormat {
+    /// Timed-text XML with per-word timing
+    Srv3,
+    /// Timed-text JSON
+    Json3,
+    /// WebVTT
+    Vtt,
+}
+
+impl SourceFormat {
+    /// Value of the `fmt=` query parameter
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            SourceFormat::Srv3 => "srv3",
+            SourceFormat::Json3 => "json3",
+            SourceFormat::Vtt => "vtt",
+        }
+    }
+}
+
+impl std::str::FromStr for SourceFormat {
+    type Err = crate::error::YdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "srv3" => Ok(SourceFormat::Srv3),
+            "json3" => Ok(SourceFormat::Json3),
+            "vtt" => Ok(SourceFormat::Vtt),
+            _ => Err(crate::error::YdlError::UnsupportedFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_param())
+    }
+}
+
+/// How subtitle cues are segmented before rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Segmentation {
+    /// Keep YouTube's cue timing as-is, including overlapping rolling windows
+    Original,
+    /// Clamp each cue's end to the next cue's start so cues never overlap
+    NonOverlapping,
+    /// Merge cues into whole sentences, without overlaps
+    Sentences,
+}
+
+impl Segmentation {
+    /// Auto-generated tracks use rolling, overlapping windows, so tidy them by default
+    pub fn default_for(track_type: &SubtitleTrackType) -> Self {
+        match track_type {
+            SubtitleTrackType::AutoGenerated => Segmentation::NonOverlapping,
+            SubtitleTrackType::Manual | SubtitleTrackType::Community => Segmentation::Original,
+        }
+    }
+}
+
+impl std::str::FromStr for Segmentation {
+    type Err = crate::error::YdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "original" => Ok(Segmentation::Original),
+            "non-overlapping" | "nonoverlapping" => Ok(Segmentation::NonOverlapping),
+            "sentences" => Ok(Segmentation::Sentences),
+            _ => Err(crate::error::YdlError::Configuration {
+                message: format!("Unknown segmentation mode: {}", s),
+            }),
+        }
+    }
+}
+
+/// How cue times are written in SRT output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Timecode {
+    /// Standard `HH:MM:SS,mmm` clock time
+    #[default]
+    Clock,
+    /// `HH:MM:SS:FF` frames at [`YdlOptions::frame_rate`], drop-frame (`;`) at 29.97 and 59.94
+    Frames,
+    /// `HH:MM:SS:FF` frames counted without dropping, even at 29.97 and 59.94
+    NonDropFrames,
+}
+
+impl Timecode {
+    /// Format `duration` in this timecode at `frame_rate`
+    pub fn format(&self, duration: Duration, frame_rate: f64) -> String {
+        match self {
+            Timecode::Clock => format_duration_as_srt(duration),
+            Timecode::Frames => format_duration_as_smpte(duration, frame_rate),
+            Timecode::NonDropFrames => format_duration_as_frames(duration, frame_rate, false),
+        }
+    }
+}
+
+impl std::str::FromStr for Timecode {
+    type Err = crate::error::YdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clock" => Ok(Timecode::Clock),
+            "frames" => Ok(Timecode::Frames),
+            "frames-ndf" | "ndf" => Ok(Timecode::NonDropFrames),
+            _ => Err(crate::error::YdlError::Configuration {
+                message: format!("Unknown timecode: {}", s),
+            }),
+        }
+    }
+}
+
+/// Highest reading speed, in characters per second, that counts as readable
+pub const MAX_READABLE_CPS: f64 = 25.0;
+
+/// Weights of the signals combined into a track's quality score
+///
+/// Each signal is scaled to 0..=1 before weighting, so a score ranges from 0 to the
+/// sum of the weights. The defaults favour complete tracks: a complete auto-generated
+/// track outscores a manual one that only covers part of the video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityWeights {
+    /// Manual tracks score 1, community tracks 0.5 and auto-generated tracks 0 (default 0.2)
+    pub manual: f64,
+    /// Fraction of the video's length covered by cues (default 0.4)
+    pub coverage: f64,
+    /// Cue count relative to the candidate with the most cues (default 0.1)
+    pub entries: f64,
+    /// Fraction of cues readable at [`MAX_READABLE_CPS`] or slower (default 0.1)
+    pub reading_speed: f64,
+    /// 1 if the track is in the requested language, or no language was requested (default 0.2)
+    pub language: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            manual: 0.2,
+            coverage: 0.4,
+            entries: 0.1,
+            reading_speed: 0.1,
+            language: 0.2,
+        }
+    }
 }
 
 /// Types of subtitle tracks
@@ -180,6 +915,17 @@ pub enum SubtitleTrackType {
     Community,
 }
 
+impl SubtitleTrackType {
+    /// Short tag used to tell variants of one language apart in filenames
+    pub fn file_tag(&self) -> &'static str {
+        match self {
+            SubtitleTrackType::Manual => "manual",
+            SubtitleTrackType::AutoGenerated => "auto",
+            SubtitleTrackType::Community => "community",
+        }
+    }
+}
+
 impl std::fmt::Display for SubtitleTrackType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -191,13 +937,24 @@ impl std::fmt::Display for SubtitleTrackType {
 }
 
 /// Information about available subtitle tracks
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SubtitleTrack {
     pub language_code: String,
     pub language_name: String,
     pub track_type: SubtitleTrackType,
     pub is_translatable: bool,
     pub url: Option<String>,
+    /// YouTube's unique track identifier, e.g. `.en` (manual) or `a.en` (auto-generated)
+    pub vss_id: Option<String>,
+    /// Language the track was machine-translated from, if it is a translation pseudo-track
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_from: Option<String>,
+    /// Quality score, once the track has been downloaded and scored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f64>,
+    /// Whether YouTube's player would show this track by default
+    #[serde(default)]
+    pub is_default: bool,
 }
 
 impl SubtitleTrack {
@@ -212,9 +969,18 @@ impl SubtitleTrack {
             track_type,
             is_translatable: false,
             url: None,
+            vss_id: None,
+            translated_from: None,
+            quality_score: None,
+            is_default: false,
         }
     }
 
+    pub fn with_vss_id(mut self, vss_id: String) -> Self {
+        self.vss_id = Some(vss_id);
+        self
+    }
+
     pub fn with_url(mut self, url: String) -> Self {
         self.url = Some(url);
         self
@@ -224,6 +990,21 @@ impl SubtitleTrack {
         self.is_translatable = translatable;
         self
     }
+
+    pub fn with_default(mut self, is_default: bool) -> Self {
+        self.is_default = is_default;
+        self
+    }
+
+    pub fn with_translated_from(mut self, source_language: String) -> Self {
+        self.translated_from = Some(source_language);
+        self
+    }
+
+    /// Whether this is a machine translation of another track
+    pub fn is_translated(&self) -> bool {
+        self.translated_from.is_some()
+    }
 }
 
 /// Result of a subtitle download operation
@@ -249,14 +1030,51 @@ impl SubtitleResult {
             track_type,
         }
     }
+
+    /// Filename for this result that names its language and track type,
+    /// e.g. `<stem>.en.manual.srt` or `<stem>.en.auto.srt`
+    pub fn variant_file_name(&self, stem: &str) -> String {
+        format!(
+            "{}.{}.{}.{}",
+            stem,
+            self.language,
+            self.track_type.file_tag(),
+            self.format.extension()
+        )
+    }
+
+    /// Concatenate the content of `results` into one document
+    ///
+    /// `separator` is written before each result, with `{language}` and `{type}`
+    /// replaced by its language and track type (`manual`, `auto` or `community`), so
+    /// it can double as a per-result header. Content is used as-is, whatever its
+    /// format; each result ends with a single newline.
+    pub fn combine(results: &[SubtitleResult], separator: &str) -> String {
+        let mut combined = String::new();
+        for result in results {
+            combined.push_str(
+                &separator
+                    .replace("{language}", &result.language)
+                    .replace("{type}", result.track_type.file_tag()),
+            );
+            combined.push_str(result.content.trim_end_matches('\n'));
+            combined.push('\n');
+        }
+        combined
+    }
 }
 
 /// Video metadata information
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+///
+/// Durations serialize as whole milliseconds, like every other ydl type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct VideoMetadata {
     pub video_id: String,
     pub title: String,
+    #[serde(default, with = "serde_millis::option")]
     pub duration: Option<Duration>,
+    pub thumbnail_url: Option<String>,
+    pub chapters: Vec<Chapter>,
     pub available_subtitles: Vec<SubtitleTrack>,
 }
 
@@ -266,6 +1084,8 @@ impl VideoMetadata {
             video_id,
             title,
             duration: None,
+            thumbnail_url: None,
+            chapters: Vec::new(),
             available_subtitles: Vec::new(),
         }
     }
@@ -275,10 +1095,116 @@ impl VideoMetadata {
         self
     }
 
+    pub fn with_thumbnail_url(mut self, url: String) -> Self {
+        self.thumbnail_url = Some(url);
+        self
+    }
+
     pub fn with_subtitles(mut self, subtitles: Vec<SubtitleTrack>) -> Self {
         self.available_subtitles = subtitles;
         self
     }
+
+    pub fn with_chapters(mut self, chapters: Vec<Chapter>) -> Self {
+        self.chapters = chapters;
+        self
+    }
+
+    /// Build one subtitle entry per chapter, showing its title for its whole segment
+    ///
+    /// Each chapter ends where the next one starts. The last chapter ends at the
+    /// video duration when known, otherwise it is shown for ten seconds.
+    pub fn chapter_entries(&self) -> Vec<SubtitleEntry> {
+        self.chapters
+            .iter()
+            .enumerate()
+            .map(|(i, chapter)| {
+                let end = match self.chapters.get(i + 1) {
+                    Some(next) => next.start,
+                    None => self
+                        .duration
+                        .filter(|d| *d > chapter.start)
+                        .unwrap_or(chapter.start + Duration::from_secs(10)),
+                };
+                SubtitleEntry::new(chapter.start, end, chapter.title.clone())
+            })
+            .collect()
+    }
+}
+
+/// A chapter marker within a video
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chapter {
+    #[serde(with = "serde_millis")]
+    pub start: Duration,
+    pub title: String,
+}
+
+impl Chapter {
+    pub fn new(start: Duration, title: String) -> Self {
+        Self { start, title }
+    }
+
+    /// Parse chapters from timestamp lines in a video description
+    ///
+    /// Follows YouTube's own rules: lines look like `1:23 Title` (or `1:02:03 - Title`),
+    /// the first chapter starts at 0:00, timestamps ascend, and there are at least three.
+    /// Anything else yields no chapters.
+    pub fn from_description(description: &str) -> Vec<Chapter> {
+        let mut chapters: Vec<Chapter> = Vec::new();
+
+        for line in description.lines() {
+            let line = line.trim();
+            let Some((stamp, rest)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(start) = parse_clock_timestamp(stamp) else {
+                continue;
+            };
+            let title = rest
+                .trim_start_matches(['-', '–', '—', ':', '|', ' '])
+                .trim();
+            if title.is_empty() {
+                continue;
+            }
+            if chapters.last().is_some_and(|prev| prev.start >= start) {
+                continue;
+            }
+            chapters.push(Chapter::new(start, title.to_string()));
+        }
+
+        let starts_at_zero = chapters.first().is_some_and(|c| c.start.is_zero());
+        if chapters.len() < 3 || !starts_at_zero {
+            return Vec::new();
+        }
+        chapters
+    }
+}
+
+/// Parse a `M:SS`, `MM:SS` or `H:MM:SS` clock timestamp
+fn parse_clock_timestamp(stamp: &str) -> Option<Duration> {
+    let parts: Vec<&str> = stamp.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    if !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    let values: Vec<u64> = parts
+        .iter()
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (hours, minutes, seconds) = match values.as_slice() {
+        [m, s] => (0, *m, *s),
+        [h, m, s] => (*h, *m, *s),
+        _ => return None,
+    };
+    if seconds >= 60 || (parts.len() == 3 && minutes >= 60) {
+        return None;
+    }
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
 }
 
 /// Internal representation of YouTube video page data
@@ -287,6 +1213,69 @@ pub struct PlayerResponse {
     pub captions: Option<CaptionTracks>,
     #[serde(rename = "videoDetails")]
     pub video_details: Option<VideoDetails>,
+    #[serde(rename = "playabilityStatus")]
+    pub playability_status: Option<PlayabilityStatus>,
+}
+
+/// Whether the video plays for this client, and if not, why
+#[derive(Debug, Deserialize)]
+pub struct PlayabilityStatus {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+impl PlayabilityStatus {
+    pub fn is_ok(&self) -> bool {
+        self.status == "OK"
+    }
+}
+
+impl PlayerResponse {
+    /// Whether the response describes a video but carries no captions renderer at all,
+    /// which is how YouTube reports captions turned off by the uploader
+    pub fn captions_disabled(&self) -> bool {
+        self.video_details.is_some()
+            && self
+                .captions
+                .as_ref()
+                .and_then(|captions| captions.player_captions_tracklist_renderer.as_ref())
+                .is_none()
+    }
+}
+
+/// Suffix YouTube appends to the names of speech-recognition tracks (with `hl=en`)
+const AUTO_GENERATED_SUFFIX: &str = "(auto-generated)";
+
+/// Canonical `(language_code, display_name, is_auto)` for a caption track
+///
+/// Whitespace is collapsed, an "(auto-generated)" suffix is dropped from the name
+/// and reported as `is_auto`, "English - United States" becomes
+/// "English (United States)", and an empty name falls back to the language code.
+/// The code itself is returned unchanged, since it is what YouTube matches against.
+pub fn normalize_track_name(language_code: &str, name: &str) -> (String, String, bool) {
+    let language_code = language_code.to_string();
+
+    let mut name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let suffix_start = name.len().saturating_sub(AUTO_GENERATED_SUFFIX.len());
+    let is_auto = name.is_char_boundary(suffix_start)
+        && name[suffix_start..].eq_ignore_ascii_case(AUTO_GENERATED_SUFFIX);
+    if is_auto {
+        name.truncate(suffix_start);
+        name.truncate(name.trim_end().len());
+    }
+
+    if let Some((language, region)) = name.split_once(" - ")
+        && !language.is_empty()
+        && !region.is_empty()
+        && !region.contains(" - ")
+    {
+        name = format!("{} ({})", language, region);
+    }
+
+    if name.is_empty() {
+        name = language_code.clone();
+    }
+    (language_code, name, is_auto)
 }
 
 /// Caption tracks from YouTube player response
@@ -305,11 +1294,24 @@ pub struct TrackListRenderer {
     pub audio_tracks: Option<Vec<AudioTrack>>,
 }
 
+impl TrackListRenderer {
+    /// Index into `caption_tracks` of the track the player selects by default
+    pub fn default_caption_index(&self) -> Option<usize> {
+        self.audio_tracks
+            .as_ref()?
+            .iter()
+            .find_map(|audio| audio.default_caption_track_index)
+    }
+}
+
 /// Individual caption track
 #[derive(Debug, Deserialize)]
 pub struct CaptionTrack {
-    #[serde(rename = "baseUrl")]
+    #[serde(rename = "baseUrl", default)]
     pub base_url: String,
+    /// Sent instead of `baseUrl` for some tracks: `url`, encrypted `s` and `sp` fields
+    #[serde(rename = "signatureCipher")]
+    pub signature_cipher: Option<String>,
     #[serde(rename = "languageCode")]
     pub language_code: String,
     pub name: Option<CaptionTrackName>,
@@ -320,6 +1322,52 @@ pub struct CaptionTrack {
     pub kind: Option<String>,
 }
 
+impl CaptionTrack {
+    /// Display name of the track (localized to the request's `hl`), or its language code
+    pub fn display_name(&self) -> String {
+        self.name
+            .as_ref()
+            .and_then(CaptionTrackName::text)
+            .unwrap_or_else(|| self.language_code.clone())
+    }
+
+    /// Canonical `(language_code, display_name, is_auto)`, see [`normalize_track_name`]
+    pub fn normalized_name(&self) -> (String, String, bool) {
+        normalize_track_name(&self.language_code, &self.display_name())
+    }
+
+    /// Download URL, taken from `signatureCipher` when YouTube omits `baseUrl`
+    ///
+    /// A URL from the cipher keeps its still-encrypted `s` parameter, so
+    /// [`caption_url_protection`](crate::network::caption_url_protection) flags it.
+    pub fn url(&self) -> String {
+        let Some(cipher) = self
+            .signature_cipher
+            .as_deref()
+            .filter(|_| self.base_url.is_empty())
+        else {
+            return self.base_url.clone();
+        };
+
+        let mut url = String::new();
+        let mut signature = None;
+        for (key, value) in url::form_urlencoded::parse(cipher.as_bytes()) {
+            match key.as_ref() {
+                "url" => url = value.into_owned(),
+                "s" => signature = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        if let Some(signature) = signature
+            && let Ok(mut parsed) = url::Url::parse(&url)
+        {
+            parsed.query_pairs_mut().append_pair("s", &signature);
+            url = parsed.to_string();
+        }
+        url
+    }
+}
+
 /// Caption track name
 #[derive(Debug, Deserialize)]
 pub struct CaptionTrackName {
@@ -328,6 +1376,22 @@ pub struct CaptionTrackName {
     pub runs: Option<Vec<Run>>,
 }
 
+impl CaptionTrackName {
+    /// `simpleText`, or else the concatenation of all `runs`
+    pub fn text(&self) -> Option<String> {
+        if let Some(text) = self.simple_text.as_ref().filter(|t| !t.is_empty()) {
+            return Some(text.clone());
+        }
+        let joined: String = self
+            .runs
+            .as_ref()?
+            .iter()
+            .map(|r| r.text.as_str())
+            .collect();
+        (!joined.is_empty()).then_some(joined)
+    }
+}
+
 /// Text run in caption track name
 #[derive(Debug, Deserialize)]
 pub struct Run {
@@ -339,6 +1403,8 @@ pub struct Run {
 pub struct AudioTrack {
     #[serde(rename = "captionTrackIndices")]
     pub caption_track_indices: Option<Vec<i32>>,
+    #[serde(rename = "defaultCaptionTrackIndex")]
+    pub default_caption_track_index: Option<usize>,
 }
 
 /// Video details from player response
@@ -351,19 +1417,90 @@ pub struct VideoDetails {
     pub length_seconds: Option<String>,
     #[serde(rename = "isLiveContent")]
     pub is_live_content: Option<bool>,
+    pub thumbnail: Option<ThumbnailList>,
+    #[serde(rename = "shortDescription")]
+    pub short_description: Option<String>,
+}
+
+impl VideoDetails {
+    /// Get the URL of the largest thumbnail (by width)
+    pub fn best_thumbnail_url(&self) -> Option<&str> {
+        self.thumbnail
+            .as_ref()?
+            .thumbnails
+            .iter()
+            .max_by_key(|t| t.width.unwrap_or(0))
+            .map(|t| t.url.as_str())
+    }
+}
+
+/// Thumbnail list from video details
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailList {
+    #[serde(default)]
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+/// Individual thumbnail image
+#[derive(Debug, Deserialize)]
+pub struct Thumbnail {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 /// Subtitle entry for timing and text
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Times serialize as whole milliseconds, so sub-millisecond precision does not
+/// survive a round trip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SubtitleEntry {
+    #[serde(with = "serde_millis")]
     pub start: Duration,
+    #[serde(with = "serde_millis")]
     pub end: Duration,
     pub text: String,
+    /// Speaker label split off the text, when speaker detection is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// WebVTT cue identifier (the line before the timing line), if the source had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Start time and text of each word, when the source times words individually
+    /// (srv3 `<s t="...">` spans)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_millis::word_timings"
+    )]
+    pub word_timings: Option<Vec<(Duration, String)>>,
 }
 
 impl SubtitleEntry {
     pub fn new(start: Duration, end: Duration, text: String) -> Self {
-        Self { start, end, text }
+        Self {
+            start,
+            end,
+            text,
+            speaker: None,
+            id: None,
+            word_timings: None,
+        }
+    }
+
+    pub fn with_speaker(mut self, speaker: String) -> Self {
+        self.speaker = Some(speaker);
+        self
+    }
+
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn with_word_timings(mut self, word_timings: Vec<(Duration, String)>) -> Self {
+        self.word_timings = Some(word_timings);
+        self
     }
 
     /// Get duration of this subtitle entry
@@ -393,7 +1530,7 @@ impl SubtitleEntry {
 }
 
 /// Parsed subtitle data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParsedSubtitles {
     pub entries: Vec<SubtitleEntry>,
     pub language: String,
@@ -401,7 +1538,9 @@ pub struct ParsedSubtitles {
 }
 
 impl ParsedSubtitles {
-    pub fn new(entries: Vec<SubtitleEntry>, language: String) -> Self {
+    /// Create parsed subtitles, ordering entries by start time
+    pub fn new(mut entries: Vec<SubtitleEntry>, language: String) -> Self {
+        entries.sort_by_key(|e| e.start);
         Self {
             entries,
             language,
@@ -426,6 +1565,174 @@ impl ParsedSubtitles {
     pub fn entry_count(&self) -> usize {
         self.entries.len()
     }
+
+    /// Shift every cue so the earliest one starts at `new_start`
+    ///
+    /// Unlike a flat offset, this is anchored to the first cue: rebasing to zero
+    /// removes any lead-in, whatever its length. Relative timing is preserved.
+    pub fn rebase(&mut self, new_start: Duration) {
+        let Some(earliest) = self.entries.iter().map(|e| e.start).min() else {
+            return;
+        };
+        let rebase = |time: Duration| time.saturating_sub(earliest) + new_start;
+
+        for entry in &mut self.entries {
+            entry.start = rebase(entry.start);
+            entry.end = rebase(entry.end);
+            if let Some(words) = &mut entry.word_timings {
+                for (start, _) in words.iter_mut() {
+                    *start = rebase(*start);
+                }
+            }
+        }
+    }
+
+    /// The cue on screen at time `t`, if any
+    ///
+    /// A cue is active from its start up to (but not including) its end. When cues
+    /// overlap, the one that started most recently wins. Entries sorted by start, as
+    /// `new` leaves them, are searched by bisection; others are scanned in full.
+    pub fn at(&self, t: Duration) -> Option<&SubtitleEntry> {
+        if !self.is_sorted() {
+            return self
+                .entries
+                .iter()
+                .filter(|e| e.start <= t && e.end > t)
+                .max_by_key(|e| e.start);
+        }
+
+        let started = self.entries.partition_point(|e| e.start <= t);
+        self.entries[..started].iter().rev().find(|e| e.end > t)
+    }
+
+    /// Remove leading and/or trailing cues separated from the main body by at least `gap`
+    ///
+    /// Meant for musical intros and outros with a stray caption or two. A cluster of
+    /// cues before (or after) a gap is only dropped while it holds fewer cues than what
+    /// remains, so a long pause in the middle of a talk never removes half of it.
+    /// Relies on entries being sorted by start, as `new` guarantees.
+    pub fn trim_silence(&mut self, leading: bool, trailing: bool, gap: Duration) {
+        // Indices of cues that start at least `gap` after every earlier cue ended
+        let mut splits = Vec::new();
+        let mut reached = Duration::ZERO;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 && entry.start.saturating_sub(reached) >= gap {
+                splits.push(i);
+            }
+            reached = reached.max(entry.end);
+        }
+
+        let (mut from, mut to) = (0, self.entries.len());
+        if leading {
+            for &split in &splits {
+                if split - from >= to - split {
+                    break;
+                }
+                from = split;
+            }
+        }
+        if trailing {
+            for &split in splits.iter().rev() {
+                if split <= from || to - split >= split - from {
+                    break;
+                }
+                to = split;
+            }
+        }
+
+        self.entries.truncate(to);
+        self.entries.drain(..from);
+    }
+
+    /// All cues overlapping the interval `t - window ..= t + window`, in start order
+    pub fn around(&self, t: Duration, window: Duration) -> Vec<&SubtitleEntry> {
+        let from = t.saturating_sub(window);
+        let until = t.saturating_add(window);
+        if !self.is_sorted() {
+            let mut found: Vec<&SubtitleEntry> = self
+                .entries
+                .iter()
+                .filter(|e| e.start <= until && e.end >= from)
+                .collect();
+            found.sort_by_key(|e| e.start);
+            return found;
+        }
+
+        let started = self.entries.partition_point(|e| e.start <= until);
+
+        self.entries[..started]
+            .iter()
+            .filter(|e| e.end >= from)
+            .collect()
+    }
+
+    /// Whether entries are in start order, which `entries` being public cannot promise
+    fn is_sorted(&self) -> bool {
+        self.entries.is_sorted_by_key(|e| e.start)
+    }
+}
+
+/// Serde helpers writing `Duration`s as integer milliseconds, for stable, language-agnostic JSON
+mod serde_millis {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        (duration.as_millis() as u64).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            duration.map(|d| d.as_millis() as u64).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+        }
+    }
+
+    /// `(start, word)` pairs, as `[millis, word]` arrays
+    pub mod word_timings {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            words: &Option<Vec<(Duration, String)>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            words
+                .as_ref()
+                .map(|words| {
+                    words
+                        .iter()
+                        .map(|(start, word)| (start.as_millis() as u64, word))
+                        .collect::<Vec<_>>()
+                })
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<(Duration, String)>>, D::Error> {
+            let words = Option::<Vec<(u64, String)>>::deserialize(deserializer)?;
+            Ok(words.map(|words| {
+                words
+                    .into_iter()
+                    .map(|(millis, word)| (Duration::from_millis(millis), word))
+                    .collect()
+            }))
+        }
+    }
 }
 
 /// Format duration as SRT timestamp (HH:MM:SS,mmm)
@@ -440,7 +1747,7 @@ fn format_duration_as_srt(duration: Duration) -> String {
 }
 
 /// Format duration as VTT timestamp (HH:MM:SS.mmm)
-fn format_duration_as_vtt(duration: Duration) -> String {
+pub(crate) fn format_duration_as_vtt(duration: Duration) -> String {
     let total_secs = duration.as_secs();
     let hours = total_secs / 3600;
     let minutes = (total_secs % 3600) / 60;
@@ -450,6 +1757,78 @@ fn format_duration_as_vtt(duration: Duration) -> String {
     format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
 }
 
+/// Format duration as an ASS timestamp (H:MM:SS.cc), rounded to centiseconds
+pub(crate) fn format_duration_as_ass(duration: Duration) -> String {
+    let centis = (duration.as_millis() + 5) / 10;
+    let hours = centis / 360_000;
+    let minutes = (centis % 360_000) / 6000;
+    let seconds = (centis % 6000) / 100;
+
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        hours,
+        minutes,
+        seconds,
+        centis % 100
+    )
+}
+
+/// Format duration as an LRC timestamp (MM:SS.cc), rounded to centiseconds
+///
+/// Minutes keep counting past 59, as LRC has no hours field.
+pub(crate) fn format_duration_as_lrc(duration: Duration) -> String {
+    let centis = (duration.as_millis() + 5) / 10;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        (centis % 6000) / 100,
+        centis % 100
+    )
+}
+
+/// Format duration as an SMPTE timecode (HH:MM:SS:FF)
+///
+/// 29.97 and 59.94 fps use drop-frame counting and a `;` before the frame field.
+/// Other rates count frames at the nominal (rounded) rate.
+pub(crate) fn format_duration_as_smpte(duration: Duration, frame_rate: f64) -> String {
+    let drop_frame = (frame_rate - 29.97).abs() < 0.01 || (frame_rate - 59.94).abs() < 0.01;
+    format_duration_as_frames(duration, frame_rate, drop_frame)
+}
+
+/// Format duration as an HH:MM:SS:FF timecode, optionally with drop-frame counting
+///
+/// Frames are counted at the real `frame_rate` and labelled at the nominal (rounded)
+/// rate, so without drop-frame a 29.97 timecode runs slightly behind the clock.
+fn format_duration_as_frames(duration: Duration, frame_rate: f64, drop_frame: bool) -> String {
+    let nominal = frame_rate.round().max(1.0) as u64;
+    let mut frame = (duration.as_secs_f64() * frame_rate).round() as u64;
+
+    if drop_frame {
+        // Skip frame numbers 0 and 1 (or 0-3 at 59.94) every minute except every tenth
+        let drop = nominal / 15;
+        let frames_per_minute = nominal * 60 - drop;
+        let frames_per_10_minutes = frames_per_minute * 10 + drop;
+        let tens = frame / frames_per_10_minutes;
+        let rest = frame % frames_per_10_minutes;
+        frame += drop * 9 * tens;
+        if rest > drop {
+            frame += drop * ((rest - drop) / frames_per_minute);
+        }
+    }
+
+    let frames = frame % nominal;
+    let total_secs = frame / nominal;
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+    let separator = if drop_frame { ';' } else { ':' };
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:02}",
+        hours, minutes, seconds, separator, frames
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -462,8 +1841,146 @@ mod tests {
         assert_eq!("json".parse::<SubtitleType>().unwrap(), SubtitleType::Json);
         assert_eq!("raw".parse::<SubtitleType>().unwrap(), SubtitleType::Raw);
         assert_eq!("xml".parse::<SubtitleType>().unwrap(), SubtitleType::Raw);
+        assert_eq!("dfxp".parse::<SubtitleType>().unwrap(), SubtitleType::Ttml);
+
+        assert_eq!("scc".parse::<SubtitleType>().unwrap(), SubtitleType::Scc);
 
         assert!("invalid".parse::<SubtitleType>().is_err());
+
+        for format in SubtitleType::ALL {
+            assert_eq!(format.name().parse::<SubtitleType>().unwrap(), *format);
+        }
+    }
+
+    #[test]
+    fn test_all_lists_every_format() {
+        // A new variant fails to compile here until it is given the next position
+        // and counted, which then has to match its place in `ALL`
+        const VARIANTS: usize = 9;
+        let position = |format: SubtitleType| match format {
+            SubtitleType::Srt => 0,
+            SubtitleType::Vtt => 1,
+            SubtitleType::Txt => 2,
+            SubtitleType::Json => 3,
+            SubtitleType::Raw => 4,
+            SubtitleType::Scc => 5,
+            SubtitleType::Ass => 6,
+            SubtitleType::Lrc => 7,
+            SubtitleType::Ttml => 8,
+        };
+        let positions: Vec<usize> = SubtitleType::ALL.iter().map(|f| position(*f)).collect();
+        assert_eq!(positions, (0..VARIANTS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_default_caption_index() {
+        let renderer: TrackListRenderer = serde_json::from_str(
+            r#"{"captionTracks":[],"audioTracks":[{"captionTrackIndices":[0,1],"defaultCaptionTrackIndex":1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(renderer.default_caption_index(), Some(1));
+
+        let renderer: TrackListRenderer = serde_json::from_str(r#"{"captionTracks":[]}"#).unwrap();
+        assert_eq!(renderer.default_caption_index(), None);
+    }
+
+    #[test]
+    fn test_variant_file_name() {
+        let manual = SubtitleResult::new(
+            String::new(),
+            SubtitleType::Srt,
+            "en".to_string(),
+            SubtitleTrackType::Manual,
+        );
+        let auto = SubtitleResult::new(
+            String::new(),
+            SubtitleType::Srt,
+            "en".to_string(),
+            SubtitleTrackType::AutoGenerated,
+        );
+
+        assert_eq!(manual.variant_file_name("talk"), "talk.en.manual.srt");
+        assert_eq!(auto.variant_file_name("talk"), "talk.en.auto.srt");
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let entries = vec![
+            SubtitleEntry::new(
+                Duration::from_millis(1_250),
+                Duration::from_millis(3_000),
+                "Hello world".to_string(),
+            )
+            .with_speaker("BOB".to_string())
+            .with_word_timings(vec![
+                (Duration::from_millis(1_250), "Hello".to_string()),
+                (Duration::from_millis(1_900), "world".to_string()),
+            ]),
+            SubtitleEntry::new(
+                Duration::from_secs(4),
+                Duration::from_secs(6),
+                "Second".to_string(),
+            )
+            .with_id("cue-2".to_string()),
+        ];
+        let parsed = ParsedSubtitles::new(entries, "en".to_string()).with_format(SubtitleType::Vtt);
+
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(json["entries"][0]["start"], 1250);
+        assert_eq!(json["entries"][0]["end"], 3000);
+        assert_eq!(
+            json["entries"][0]["word_timings"][1],
+            serde_json::json!([1900, "world"])
+        );
+        assert!(json["entries"][1].get("word_timings").is_none());
+
+        let back: ParsedSubtitles = serde_json::from_value(json).unwrap();
+        assert_eq!(back, parsed);
+
+        let metadata = VideoMetadata::new("dQw4w9WgXcQ".to_string(), "Title".to_string())
+            .with_duration(Duration::from_secs(212))
+            .with_chapters(vec![Chapter::new(
+                Duration::from_secs(90),
+                "Middle".to_string(),
+            )])
+            .with_subtitles(vec![SubtitleTrack::new(
+                "en".to_string(),
+                "English".to_string(),
+                SubtitleTrackType::Manual,
+            )]);
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(json["duration"], 212_000);
+        assert_eq!(json["chapters"][0]["start"], 90_000);
+        let back: VideoMetadata = serde_json::from_value(json).unwrap();
+        assert_eq!(back, metadata);
+    }
+
+    #[test]
+    fn test_combine_results() {
+        let results = [
+            SubtitleResult::new(
+                "Hello\nWorld\n".to_string(),
+                SubtitleType::Txt,
+                "en".to_string(),
+                SubtitleTrackType::Manual,
+            ),
+            SubtitleResult::new(
+                "Hola".to_string(),
+                SubtitleType::Txt,
+                "es".to_string(),
+                SubtitleTrackType::AutoGenerated,
+            ),
+        ];
+
+        assert_eq!(
+            SubtitleResult::combine(&results, "=== {language} ({type}) ===\n"),
+            "=== en (manual) ===\nHello\nWorld\n=== es (auto) ===\nHola\n"
+        );
+        assert_eq!(
+            SubtitleResult::combine(&results, ""),
+            "Hello\nWorld\nHola\n"
+        );
+        assert_eq!(SubtitleResult::combine(&[], "---\n"), "");
     }
 
     #[test]
@@ -473,6 +1990,26 @@ mod tests {
         assert_eq!(SubtitleType::Txt.extension(), "txt");
         assert_eq!(SubtitleType::Json.extension(), "json");
         assert_eq!(SubtitleType::Raw.extension(), "xml");
+        assert_eq!(SubtitleType::Scc.extension(), "scc");
+        assert_eq!(SubtitleType::Ass.extension(), "ass");
+        assert_eq!(SubtitleType::Lrc.extension(), "lrc");
+        assert_eq!(SubtitleType::Ttml.extension(), "ttml");
+    }
+
+    #[test]
+    fn test_caption_track_url_from_signature_cipher() {
+        let track: CaptionTrack = serde_json::from_value(serde_json::json!({
+            "signatureCipher": "s=AOq0QJ8w%3D&sp=sig&url=https%3A%2F%2Fwww.youtube.com%2Fapi%2Ftimedtext%3Fv%3DdQw4w9WgXcQ%26lang%3Den",
+            "languageCode": "en",
+            "vssId": ".en"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            track.url(),
+            "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&s=AOq0QJ8w%3D"
+        );
+        assert!(crate::network::caption_url_protection(&track.url()).is_some());
     }
 
     #[test]
@@ -484,7 +2021,7 @@ mod tests {
             .user_agent("custom-agent");
 
         assert_eq!(options.language, Some("en".to_string()));
-        assert_eq!(options.timeout_seconds, 60);
+        assert_eq!(options.timeout_seconds, Some(60));
         assert!(!options.allow_auto_generated);
         assert_eq!(options.user_agent, Some("custom-agent".to_string()));
     }
@@ -509,6 +2046,112 @@ mod tests {
         let duration = Duration::from_secs(3661) + Duration::from_millis(250);
         assert_eq!(format_duration_as_srt(duration), "01:01:01,250");
         assert_eq!(format_duration_as_vtt(duration), "01:01:01.250");
+        assert_eq!(format_duration_as_ass(duration), "1:01:01.25");
+        assert_eq!(
+            format_duration_as_ass(Duration::from_millis(59_996)),
+            "0:01:00.00"
+        );
+    }
+
+    #[test]
+    fn test_segmentation_defaults() {
+        let options = YdlOptions::default();
+        assert_eq!(
+            options.segmentation_for(&SubtitleTrackType::AutoGenerated),
+            Segmentation::NonOverlapping
+        );
+        assert_eq!(
+            options.segmentation_for(&SubtitleTrackType::Manual),
+            Segmentation::Original
+        );
+
+        let options = options.segmentation(Segmentation::Sentences);
+        assert_eq!(
+            options.segmentation_for(&SubtitleTrackType::AutoGenerated),
+            Segmentation::Sentences
+        );
+        assert_eq!(
+            "non-overlapping".parse::<Segmentation>().unwrap(),
+            Segmentation::NonOverlapping
+        );
+    }
+
+    #[test]
+    fn test_smpte_drop_frame_formatting() {
+        assert_eq!(
+            format_duration_as_smpte(Duration::from_secs(1), 29.97),
+            "00:00:01;00"
+        );
+        // The first minute boundary skips frames 00 and 01
+        assert_eq!(
+            format_duration_as_smpte(Duration::from_secs_f64(1800.0 / 29.97), 29.97),
+            "00:01:00;02"
+        );
+        // Every tenth minute keeps them, so ten wall-clock minutes stay exact
+        assert_eq!(
+            format_duration_as_smpte(Duration::from_secs(600), 29.97),
+            "00:10:00;00"
+        );
+    }
+
+    #[test]
+    fn test_frame_timecodes() {
+        let duration = Duration::from_millis(3_723_500); // 1:02:03.5
+        assert_eq!(Timecode::Frames.format(duration, 24.0), "01:02:03:12");
+        assert_eq!(Timecode::Frames.format(duration, 25.0), "01:02:03:13");
+        assert_eq!(
+            Timecode::Frames.format(Duration::from_millis(1_040), 25.0),
+            "00:00:01:01"
+        );
+
+        // One hour of 29.97 video is 107892 frames: exact in drop-frame, 3.6s behind without
+        let hour = Duration::from_secs(3600);
+        assert_eq!(Timecode::Frames.format(hour, 29.97), "01:00:00;00");
+        assert_eq!(Timecode::NonDropFrames.format(hour, 29.97), "00:59:56:12");
+
+        assert_eq!(Timecode::Clock.format(duration, 25.0), "01:02:03,500");
+        assert_eq!(
+            "frames-ndf".parse::<Timecode>().unwrap(),
+            Timecode::NonDropFrames
+        );
+        assert!("smpte".parse::<Timecode>().is_err());
+    }
+
+    #[test]
+    fn test_chapters_from_description() {
+        let description = "Great talk!\n\n0:00 Intro\n1:30 - The problem\n1:02:03 Wrap up\nThanks";
+        let chapters = Chapter::from_description(description);
+
+        assert_eq!(
+            chapters,
+            vec![
+                Chapter::new(Duration::ZERO, "Intro".to_string()),
+                Chapter::new(Duration::from_secs(90), "The problem".to_string()),
+                Chapter::new(Duration::from_secs(3723), "Wrap up".to_string()),
+            ]
+        );
+
+        // Fewer than three timestamps, or not starting at zero, is not a chapter list
+        assert!(Chapter::from_description("0:00 Intro\n1:00 End").is_empty());
+        assert!(Chapter::from_description("0:10 A\n1:00 B\n2:00 C").is_empty());
+    }
+
+    #[test]
+    fn test_chapter_entries() {
+        let metadata = VideoMetadata::new("dQw4w9WgXcQ".to_string(), "Test".to_string())
+            .with_duration(Duration::from_secs(300))
+            .with_chapters(vec![
+                Chapter::new(Duration::ZERO, "Intro".to_string()),
+                Chapter::new(Duration::from_secs(60), "Middle".to_string()),
+                Chapter::new(Duration::from_secs(120), "End".to_string()),
+            ]);
+
+        let entries = metadata.chapter_entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].end, Duration::from_secs(60));
+        assert_eq!(entries[1].end, Duration::from_secs(120));
+        assert_eq!(entries[2].end, Duration::from_secs(300));
+        assert_eq!(entries[2].text, "End");
     }
 
     #[test]
@@ -531,4 +2174,251 @@ mod tests {
         assert_eq!(subtitles.total_duration(), Duration::from_secs(5));
         assert_eq!(subtitles.language, "en");
     }
+
+    #[test]
+    fn test_normalize_track_name() {
+        let normalize = normalize_track_name;
+        assert_eq!(
+            normalize("en", "English"),
+            ("en".to_string(), "English".to_string(), false)
+        );
+        assert_eq!(
+            normalize("en", "English (auto-generated)"),
+            ("en".to_string(), "English".to_string(), true)
+        );
+        assert_eq!(
+            normalize("en-US", "English - United States"),
+            (
+                "en-US".to_string(),
+                "English (United States)".to_string(),
+                false
+            )
+        );
+        assert_eq!(
+            normalize("pt-BR", "  Portuguese   (Brazil)  (Auto-Generated) "),
+            ("pt-BR".to_string(), "Portuguese (Brazil)".to_string(), true)
+        );
+        assert_eq!(
+            normalize("zh-Hans", "Chinese (Simplified)"),
+            (
+                "zh-Hans".to_string(),
+                "Chinese (Simplified)".to_string(),
+                false
+            )
+        );
+        assert_eq!(
+            normalize("es-419", "Spanish - Latin America"),
+            (
+                "es-419".to_string(),
+                "Spanish (Latin America)".to_string(),
+                false
+            )
+        );
+        // Names that only carry the suffix fall back to the code
+        assert_eq!(
+            normalize("ja", "(auto-generated)"),
+            ("ja".to_string(), "ja".to_string(), true)
+        );
+        // Localized suffixes are left in the name
+        assert_eq!(
+            normalize("de", "Deutsch (automatisch erzeugt)").1,
+            "Deutsch (automatisch erzeugt)"
+        );
+        // Codes are kept as YouTube sent them
+        assert_eq!(normalize("a.en", "English").0, "a.en");
+        assert_eq!(normalize("zh_hans", "").1, "zh_hans");
+    }
+
+    #[test]
+    fn test_rebase() {
+        let entry = |start: u64, end: u64| {
+            SubtitleEntry::new(
+                Duration::from_millis(start),
+                Duration::from_millis(end),
+                "cue".to_string(),
+            )
+        };
+        let original = ParsedSubtitles::new(
+            vec![
+                entry(4_000, 6_000)
+                    .with_word_timings(vec![(Duration::from_millis(5_000), "w".to_string())]),
+                entry(7_500, 9_000),
+            ],
+            "en".to_string(),
+        );
+        let times = |subtitles: &ParsedSubtitles| {
+            subtitles
+                .entries
+                .iter()
+                .map(|e| (e.start.as_millis(), e.end.as_millis()))
+                .collect::<Vec<_>>()
+        };
+
+        // Forward: the first cue lands on the new start, gaps are unchanged
+        let mut forward = original.clone();
+        forward.rebase(Duration::from_secs(10));
+        assert_eq!(times(&forward), vec![(10_000, 12_000), (13_500, 15_000)]);
+        assert_eq!(
+            forward.entries[0].word_timings.as_ref().unwrap()[0].0,
+            Duration::from_millis(11_000)
+        );
+
+        // To zero: the lead-in disappears
+        let mut zero = original.clone();
+        zero.rebase(Duration::ZERO);
+        assert_eq!(times(&zero), vec![(0, 2_000), (3_500, 5_000)]);
+
+        let mut empty = ParsedSubtitles::new(Vec::new(), "en".to_string());
+        empty.rebase(Duration::from_secs(1));
+        assert_eq!(empty.entry_count(), 0);
+    }
+
+    #[test]
+    fn test_trim_silence() {
+        let entry = |start: u64, end: u64, text: &str| {
+            SubtitleEntry::new(
+                Duration::from_secs(start),
+                Duration::from_secs(end),
+                text.to_string(),
+            )
+        };
+        // A lone intro cue, 30s of music, the talk (with a short pause), then an outro
+        let subtitles = ParsedSubtitles::new(
+            vec![
+                entry(0, 3, "[Music]"),
+                entry(33, 36, "Welcome"),
+                entry(36, 40, "to the talk"),
+                entry(48, 52, "after a pause"),
+                entry(52, 55, "thanks"),
+                entry(120, 122, "[Applause]"),
+            ],
+            "en".to_string(),
+        );
+        let texts = |subtitles: &ParsedSubtitles| {
+            subtitles
+                .entries
+                .iter()
+                .map(|e| e.text.clone())
+                .collect::<Vec<_>>()
+        };
+        let gap = Duration::from_secs(30);
+
+        let mut trimmed = subtitles.clone();
+        trimmed.trim_silence(true, false, gap);
+        assert_eq!(trimmed.entries.first().unwrap().text, "Welcome");
+        assert_eq!(trimmed.entries.last().unwrap().text, "[Applause]");
+
+        let mut trimmed = subtitles.clone();
+        trimmed.trim_silence(true, true, gap);
+        assert_eq!(
+            texts(&trimmed),
+            ["Welcome", "to the talk", "after a pause", "thanks"]
+        );
+
+        // Gaps shorter than the threshold keep everything
+        let mut trimmed = subtitles.clone();
+        trimmed.trim_silence(true, true, Duration::from_secs(90));
+        assert_eq!(texts(&trimmed), texts(&subtitles));
+
+        // A pause in the middle never removes the larger half
+        let mut halves = ParsedSubtitles::new(
+            vec![entry(0, 2, "a"), entry(2, 4, "b"), entry(60, 62, "c")],
+            "en".to_string(),
+        );
+        halves.trim_silence(true, false, gap);
+        assert_eq!(texts(&halves), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_parsed_subtitles_at() {
+        let entry = |start: u64, end: u64, text: &str| {
+            SubtitleEntry::new(
+                Duration::from_secs(start),
+                Duration::from_secs(end),
+                text.to_string(),
+            )
+        };
+        // Deliberately unsorted, with a gap (5..7) and an overlap (8..10)
+        let subtitles = ParsedSubtitles::new(
+            vec![
+                entry(7, 10, "Third"),
+                entry(0, 2, "First"),
+                entry(2, 5, "Second"),
+                entry(8, 12, "Fourth"),
+            ],
+            "en".to_string(),
+        );
+        let text_at = |secs: u64| {
+            subtitles
+                .at(Duration::from_secs(secs))
+                .map(|e| e.text.as_str())
+        };
+
+        // Boundaries: a cue is active at its start but not at its end
+        assert_eq!(text_at(0), Some("First"));
+        assert_eq!(text_at(2), Some("Second"));
+        assert_eq!(text_at(5), None);
+        // Gaps and the end of the track
+        assert_eq!(text_at(6), None);
+        assert_eq!(text_at(12), None);
+        // Overlaps prefer the most recently started cue
+        assert_eq!(text_at(7), Some("Third"));
+        assert_eq!(text_at(9), Some("Fourth"));
+        assert_eq!(text_at(11), Some("Fourth"));
+
+        let around: Vec<&str> = subtitles
+            .around(Duration::from_secs(6), Duration::from_secs(1))
+            .iter()
+            .map(|e| e.text.as_str())
+            .collect();
+        assert_eq!(around, vec!["Second", "Third"]);
+        assert!(
+            subtitles
+                .around(Duration::from_secs(30), Duration::from_secs(5))
+                .is_empty()
+        );
+
+        // Entries assembled out of order by hand give the same answers
+        let mut reversed = subtitles.entries.clone();
+        reversed.reverse();
+        let unsorted = ParsedSubtitles {
+            entries: reversed,
+            ..subtitles.clone()
+        };
+        for secs in 0..13 {
+            let t = Duration::from_secs(secs);
+            assert_eq!(unsorted.at(t), subtitles.at(t), "at {}s", secs);
+            assert_eq!(
+                unsorted.around(t, Duration::from_secs(1)),
+                subtitles.around(t, Duration::from_secs(1)),
+                "around {}s",
+                secs
+            );
+        }
+    }
+
+    #[test]
+    fn test_best_thumbnail_url() {
+        let json = r#"{
+            "videoId": "dQw4w9WgXcQ",
+            "title": "Test",
+            "thumbnail": {
+                "thumbnails": [
+                    {"url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/default.jpg", "width": 120, "height": 90},
+                    {"url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg", "width": 1920, "height": 1080},
+                    {"url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg", "width": 480, "height": 360}
+                ]
+            }
+        }"#;
+
+        let details: VideoDetails = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            details.best_thumbnail_url(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg")
+        );
+
+        let details: VideoDetails =
+            serde_json::from_str(r#"{"videoId": "dQw4w9WgXcQ", "title": "Test"}"#).unwrap();
+        assert_eq!(details.best_thumbnail_url(), None);
+    }
 }
@@ -1,16 +1,33 @@
 // YouTube client simulation based on yt-dlp implementation
 use crate::error::{YdlError, YdlResult};
-use crate::types::{PlayerResponse, SubtitleTrack, SubtitleTrackType};
+use crate::extractor::fetch_text;
+use crate::metrics::{Metrics, NoopMetrics};
+use crate::network::{self, HttpClient, HttpConfig};
+use crate::types::{PlayabilityStatus, PlayerResponse, SubtitleTrack, SubtitleTrackType};
 use reqwest::{
-    Client,
+    StatusCode,
     header::{HeaderMap, HeaderValue},
 };
-use serde::Deserialize;
 use serde_json::json;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// Upper bound for a client's success score so the ordering can still adapt
+const MAX_CLIENT_SCORE: u32 = 16;
+
+/// InnerTube player endpoint
+const PLAYER_ENDPOINT: &str = "https://www.youtube.com/youtubei/v1/player";
+
+/// InnerTube browse endpoint, used for channel and playlist pages
+const BROWSE_ENDPOINT: &str = "https://www.youtube.com/youtubei/v1/browse";
+
+/// Client context fields that identify the client and must not be overridden
+const PROTECTED_CLIENT_FIELDS: &[&str] = &["clientName", "clientVersion"];
+
 /// YouTube client types that work for subtitle extraction
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClientType {
     Web,
     TvEmbedded,
@@ -19,7 +36,21 @@ pub enum ClientType {
 }
 
 impl ClientType {
-    fn client_name(&self) -> &str {
+    /// Every client, in the default discovery order
+    pub const ALL: &'static [ClientType] = &[
+        ClientType::TvEmbedded,
+        ClientType::Web,
+        ClientType::Ios,
+        ClientType::Android,
+    ];
+
+    /// Whether this is a mobile app client, which Shorts captions are often only served to
+    pub fn is_mobile(&self) -> bool {
+        matches!(self, ClientType::Ios | ClientType::Android)
+    }
+
+    /// Name sent as `clientName` in the InnerTube context
+    pub fn client_name(&self) -> &str {
         match self {
             ClientType::Web => "WEB",
             ClientType::TvEmbedded => "TVHTML5_SIMPLY_EMBEDDED_PLAYER",
@@ -28,7 +59,8 @@ impl ClientType {
         }
     }
 
-    fn client_version(&self) -> &str {
+    /// Version sent as `clientVersion` in the InnerTube context
+    pub fn client_version(&self) -> &str {
         match self {
             ClientType::Web => "2.20240815.00.00",
             ClientType::TvEmbedded => "2.0",
@@ -37,6 +69,29 @@ impl ClientType {
         }
     }
 
+    /// Add this client's `c`/`cver` parameters to a caption URL that has no `c`
+    ///
+    /// Some timedtext endpoints only return content to the client that discovered the
+    /// track. A `c` already in the URL is kept, along with its `cver`.
+    pub fn with_client_params(&self, url: &str) -> String {
+        let Ok(mut parsed) = url::Url::parse(url) else {
+            return url.to_string();
+        };
+        if parsed.query_pairs().any(|(k, _)| k == "c") {
+            return url.to_string();
+        }
+
+        let has_cver = parsed.query_pairs().any(|(k, _)| k == "cver");
+        {
+            let mut query = parsed.query_pairs_mut();
+            query.append_pair("c", self.client_name());
+            if !has_cver {
+                query.append_pair("cver", self.client_version());
+            }
+        }
+        parsed.to_string()
+    }
+
     // These API keys are public and can be found in: https://github.com/zerodytrash/YouTube-Internal-Clients/tree/main?tab=readme-ov-file#api-keys
     fn api_key(&self) -> &str {
         match self {
@@ -65,14 +120,61 @@ impl ClientType {
     }
 }
 
+impl std::str::FromStr for ClientType {
+    type Err = YdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "web" => Ok(ClientType::Web),
+            "tv" | "tv_embedded" | "tvhtml5_simply_embedded_player" => Ok(ClientType::TvEmbedded),
+            "ios" => Ok(ClientType::Ios),
+            "android" => Ok(ClientType::Android),
+            _ => Err(YdlError::Configuration {
+                message: format!(
+                    "Unknown InnerTube client: {} (expected web, tv, ios or android)",
+                    s
+                ),
+            }),
+        }
+    }
+}
+
 /// YouTube InnerTube client for API requests
 pub struct InnerTubeClient {
-    client: Client,
+    client: HttpClient,
     client_type: ClientType,
+    player_endpoint: String,
+    browse_endpoint: String,
+    /// User-supplied fields merged into the request context, keyed by section
+    context_overrides: serde_json::Map<String, serde_json::Value>,
+    /// `visitorData` of the signed-in session, shared by the clients of one extractor
+    visitor_data: Arc<RwLock<Option<String>>>,
+    metrics: Arc<dyn Metrics>,
 }
 
 impl InnerTubeClient {
+    /// Client with default timeouts and no proxy or cookies
     pub fn new(client_type: ClientType) -> YdlResult<Self> {
+        Self::with_config(client_type, &HttpConfig::default())
+    }
+
+    /// Client sending its requests with `config`'s timeouts, proxies and cookies
+    pub(crate) fn with_config(client_type: ClientType, config: &HttpConfig) -> YdlResult<Self> {
+        let client = Self::http_client(&client_type, config)?;
+
+        Ok(Self {
+            client,
+            client_type,
+            player_endpoint: PLAYER_ENDPOINT.to_string(),
+            browse_endpoint: BROWSE_ENDPOINT.to_string(),
+            context_overrides: serde_json::Map::new(),
+            visitor_data: Arc::new(RwLock::new(None)),
+            metrics: Arc::new(NoopMetrics),
+        })
+    }
+
+    /// HTTP client sending `client_type`'s identifying headers
+    fn http_client(client_type: &ClientType, config: &HttpConfig) -> YdlResult<HttpClient> {
         let mut headers = HeaderMap::new();
         headers.insert(
             reqwest::header::USER_AGENT,
@@ -102,21 +204,16 @@ impl InnerTubeClient {
             HeaderValue::from_static("https://www.youtube.com"),
         );
 
-        let client = Client::builder()
-            .default_headers(headers)
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-
-        Ok(Self {
-            client,
-            client_type,
+        config.client(headers, |builder| {
+            builder.redirect(crate::network::redirect_policy())
         })
     }
 
     /// Get player response using InnerTube API
     pub async fn get_player(&self, video_id: &str) -> YdlResult<PlayerResponse> {
         let url = format!(
-            "https://www.youtube.com/youtubei/v1/player?key={}&prettyPrint=false",
+            "{}?key={}&prettyPrint=false",
+            self.player_endpoint,
             self.client_type.api_key()
         );
 
@@ -134,7 +231,12 @@ impl InnerTubeClient {
             video_id
         );
 
-        let response = self.client.post(&url).json(&body).send().await?;
+        let response = network::send(
+            self.client.post(&url).json(&body),
+            self.client_type.client_name(),
+            &*self.metrics,
+        )
+        .await?;
 
         if !response.status().is_success() {
             warn!(
@@ -159,13 +261,57 @@ impl InnerTubeClient {
         Ok(player_response)
     }
 
+    /// Send a browse request (`browseId` or `continuation`), adding the client context
+    ///
+    /// Returns `None` if YouTube has nothing under that browse ID (HTTP 404).
+    pub async fn browse(
+        &self,
+        mut request: serde_json::Value,
+    ) -> YdlResult<Option<serde_json::Value>> {
+        let url = format!(
+            "{}?key={}&prettyPrint=false",
+            self.browse_endpoint,
+            self.client_type.api_key()
+        );
+        request["context"] = self.build_context();
+
+        let response = network::send(
+            self.client.post(&url).json(&request),
+            self.client_type.client_name(),
+            &*self.metrics,
+        )
+        .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(Some(response.json().await?)),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(YdlError::SubtitleDiscoveryError {
+                message: format!("Failed to get browse response: {}", status),
+            }),
+        }
+    }
+
     fn build_context(&self) -> serde_json::Value {
-        let client = json!({
+        let mut context = self.base_context();
+        merge_context(&mut context, &self.context_overrides);
+        context
+    }
+
+    fn base_context(&self) -> serde_json::Value {
+        let mut client = json!({
             "clientName": self.client_type.client_name(),
             "clientVersion": self.client_type.client_version(),
             "gl": "US",
             "hl": "en",
         });
+        if let Some(visitor_data) = self
+            .visitor_data
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            client["visitorData"] = json!(visitor_data);
+        }
 
         match self.client_type {
             ClientType::Web => {
@@ -201,25 +347,14 @@ impl InnerTubeClient {
             && let Some(tracklist) = &captions.player_captions_tracklist_renderer
             && let Some(caption_tracks) = &tracklist.caption_tracks
         {
-            for track in caption_tracks {
-                // The base_url is not optional in our types, so we can use it directly
-                let base_url = &track.base_url;
+            let default_index = tracklist.default_caption_index();
+            for (index, track) in caption_tracks.iter().enumerate() {
+                // The download's `fmt=` is chosen later, by `SubtitleExtractor::content_url`
+                let url = self.client_type.with_client_params(&track.url());
 
-                // Parse existing URL to check for required parameters
-                let url = if base_url.contains("fmt=") {
-                    base_url.clone()
-                } else {
-                    // Add format parameter for srv3 (XML format)
-                    format!("{}&fmt=srv3", base_url)
-                };
+                let (language_code, language_name, is_auto) = track.normalized_name();
 
-                let language_name = track
-                    .name
-                    .as_ref()
-                    .and_then(|n| n.simple_text.as_deref())
-                    .unwrap_or(&track.language_code);
-
-                let track_type = if track.kind.as_deref() == Some("asr") {
+                let track_type = if is_auto || track.kind.as_deref() == Some("asr") {
                     SubtitleTrackType::AutoGenerated
                 } else {
                     SubtitleTrackType::Manual
@@ -227,16 +362,14 @@ impl InnerTubeClient {
 
                 debug!(
                     "Found subtitle track: {} ({}) - {:?}",
-                    language_name, track.language_code, track_type
+                    language_name, language_code, track_type
                 );
 
-                let subtitle_track = SubtitleTrack::new(
-                    track.language_code.clone(),
-                    language_name.to_string(),
-                    track_type,
-                )
-                .with_url(url)
-                .with_translatable(track.is_translatable.unwrap_or(false));
+                let subtitle_track = SubtitleTrack::new(language_code, language_name, track_type)
+                    .with_url(url)
+                    .with_translatable(track.is_translatable.unwrap_or(false))
+                    .with_vss_id(track.vss_id.clone())
+                    .with_default(default_index == Some(index));
 
                 tracks.push(subtitle_track);
             }
@@ -249,19 +382,149 @@ impl InnerTubeClient {
 /// YouTube subtitle extractor using multiple client strategies
 pub struct YouTubeSubtitleExtractor {
     clients: Vec<InnerTubeClient>,
+    /// WEB client for browse requests, independent of the clients used for discovery
+    browser: InnerTubeClient,
+    /// Bounded per-client success scores, used to try recently successful clients first
+    scores: Vec<AtomicU32>,
+    metrics: Arc<dyn Metrics>,
 }
 
 impl YouTubeSubtitleExtractor {
     pub fn new() -> YdlResult<Self> {
+        Self::with_client_types(ClientType::ALL)
+    }
+
+    /// Use only `client_types`, tried in the given order before adapting to success
+    ///
+    /// Repeated client types are ignored; an empty list is a configuration error.
+    pub fn with_client_types(client_types: &[ClientType]) -> YdlResult<Self> {
+        Self::with_config(client_types, &HttpConfig::default())
+    }
+
+    /// Like [`Self::with_client_types`], sending every request with `config`'s
+    /// timeouts, proxies and cookies
+    pub(crate) fn with_config(client_types: &[ClientType], config: &HttpConfig) -> YdlResult<Self> {
+        if client_types.is_empty() {
+            return Err(YdlError::Configuration {
+                message: "At least one InnerTube client is required".to_string(),
+            });
+        }
+
         // Initialize multiple clients for fallback
-        let clients = vec![
-            InnerTubeClient::new(ClientType::TvEmbedded)?,
-            InnerTubeClient::new(ClientType::Web)?,
-            InnerTubeClient::new(ClientType::Ios)?,
-            InnerTubeClient::new(ClientType::Android)?,
-        ];
+        let mut clients: Vec<InnerTubeClient> = Vec::new();
+        for client_type in client_types {
+            if !clients
+                .iter()
+                .any(|client| &client.client_type == client_type)
+            {
+                clients.push(InnerTubeClient::with_config(client_type.clone(), config)?);
+            }
+        }
+        let mut browser = InnerTubeClient::with_config(ClientType::Web, config)?;
+        // One session, so every client shares the same visitorData
+        let visitor_data = Arc::new(RwLock::new(None));
+        for client in clients.iter_mut().chain([&mut browser]) {
+            client.visitor_data = visitor_data.clone();
+        }
+        let scores = clients.iter().map(|_| AtomicU32::new(0)).collect();
+
+        Ok(Self {
+            clients,
+            browser,
+            scores,
+            metrics: Arc::new(NoopMetrics),
+        })
+    }
+
+    /// `visitorData` sent in every client's context, if known
+    pub fn visitor_data(&self) -> Option<String> {
+        let shared = &self.clients.first()?.visitor_data;
+        shared.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Send `visitor_data` in every client's context, tying requests to a signed-in session
+    pub fn set_visitor_data(&self, visitor_data: String) {
+        if let Some(client) = self.clients.first() {
+            *client
+                .visitor_data
+                .write()
+                .unwrap_or_else(|e| e.into_inner()) = Some(visitor_data);
+        }
+    }
+
+    /// Report requests and client outcomes to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        for client in self.clients.iter_mut().chain([&mut self.browser]) {
+            client.metrics = metrics.clone();
+        }
+        self.metrics = metrics;
+        self
+    }
+
+    /// Merge `overrides` into every client's request context (see
+    /// [`YdlOptions::innertube_context`](crate::YdlOptions::innertube_context))
+    pub fn with_context_overrides(mut self, overrides: serde_json::Value) -> YdlResult<Self> {
+        let invalid = |message: &str| YdlError::Configuration {
+            message: format!("Invalid InnerTube context: {}", message),
+        };
 
-        Ok(Self { clients })
+        let serde_json::Value::Object(sections) = overrides else {
+            return Err(invalid("expected a JSON object of context sections"));
+        };
+        if let Some((name, _)) = sections.iter().find(|(_, fields)| !fields.is_object()) {
+            return Err(invalid(&format!("section \"{}\" must be an object", name)));
+        }
+
+        for client in self.clients.iter_mut().chain([&mut self.browser]) {
+            client.context_overrides = sections.clone();
+        }
+        Ok(self)
+    }
+
+    /// Send InnerTube requests to `endpoint` instead of YouTube
+    #[cfg(test)]
+    pub(crate) fn with_player_endpoint(mut self, endpoint: &str) -> Self {
+        for client in &mut self.clients {
+            client.player_endpoint = endpoint.to_string();
+        }
+        self
+    }
+
+    /// Send InnerTube browse requests to `endpoint` instead of YouTube
+    #[cfg(test)]
+    pub(crate) fn with_browse_endpoint(mut self, endpoint: &str) -> Self {
+        for client in self.clients.iter_mut().chain([&mut self.browser]) {
+            client.browse_endpoint = endpoint.to_string();
+        }
+        self
+    }
+
+    /// Send a browse request with the WEB client, which serves channel and playlist pages
+    ///
+    /// The WEB client is always used here, whichever clients discovery is limited to.
+    pub async fn browse(&self, request: serde_json::Value) -> YdlResult<Option<serde_json::Value>> {
+        self.browser.browse(request).await
+    }
+
+    /// Client indices in the order they should be tried
+    ///
+    /// Clients with higher success scores come first; ties keep the default order.
+    fn client_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.clients.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.scores[i].load(Ordering::Relaxed)));
+        order
+    }
+
+    /// Record the outcome of a discovery attempt for the client at `index`
+    fn record_result(&self, index: usize, success: bool) {
+        let score = &self.scores[index];
+        let _ = score.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+            Some(if success {
+                (current + 1).min(MAX_CLIENT_SCORE)
+            } else {
+                current.saturating_sub(1)
+            })
+        });
     }
 
     /// Discover subtitle tracks using multiple client strategies
@@ -271,50 +534,203 @@ impl YouTubeSubtitleExtractor {
             video_id
         );
 
-        // Try each client until we get subtitles
+        // Try each client until we get subtitles, starting with those that worked recently
+        self.discover_in_order(video_id, self.client_order()).await
+    }
+
+    /// Discover subtitle tracks of a YouTube Short, trying the mobile clients first
+    ///
+    /// Shorts often list captions (usually only auto-generated ones) for the iOS and
+    /// Android clients alone. Configured clients are kept; among mobile and other
+    /// clients, those that worked recently still go first.
+    pub async fn discover_short_tracks(&self, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
+        info!(
+            "Discovering subtitles for Short {} using InnerTube API",
+            video_id
+        );
+
+        let mut order = self.client_order();
+        order.sort_by_key(|&index| !self.clients[index].client_type.is_mobile());
+        self.discover_in_order(video_id, order).await
+    }
+
+    /// Try the clients at `order` in turn until one lists tracks
+    async fn discover_in_order(
+        &self,
+        video_id: &str,
+        order: Vec<usize>,
+    ) -> YdlResult<Vec<SubtitleTrack>> {
+        let mut all_disabled = true;
+        for index in order {
+            match self.try_client(index, video_id).await {
+                Ok(tracks) => return Ok(tracks),
+                Err(YdlError::CaptionsDisabled { .. }) => {}
+                Err(_) => all_disabled = false,
+            }
+        }
+
+        // Only trust the missing renderer when every client agrees on it
+        if all_disabled && !self.clients.is_empty() {
+            return Err(YdlError::CaptionsDisabled {
+                video_id: video_id.to_string(),
+            });
+        }
+        Err(YdlError::NoSubtitlesAvailable {
+            video_id: video_id.to_string(),
+        })
+    }
+
+    /// Discover subtitle tracks using one specific client
+    pub async fn discover_with_client(
+        &self,
+        client_type: ClientType,
+        video_id: &str,
+    ) -> YdlResult<Vec<SubtitleTrack>> {
+        let index = self
+            .clients
+            .iter()
+            .position(|client| client.client_type == client_type)
+            .ok_or_else(|| YdlError::Configuration {
+                message: format!("No {:?} client configured", client_type),
+            })?;
+
+        self.try_client(index, video_id).await.map_err(|e| match e {
+            YdlError::CaptionsDisabled { .. } => e,
+            _ => YdlError::NoSubtitlesAvailable {
+                video_id: video_id.to_string(),
+            },
+        })
+    }
+
+    /// Ask every client for tracks independently, in the default order
+    ///
+    /// Unlike `discover_tracks`, this neither stops at the first success nor affects
+    /// the client ordering.
+    pub async fn probe_all(&self, video_id: &str) -> Vec<(ClientType, YdlResult<usize>)> {
+        let mut results = Vec::with_capacity(self.clients.len());
         for client in &self.clients {
-            match client.get_player(video_id).await {
-                Ok(player_response) => {
-                    let tracks = client.extract_subtitle_tracks(&player_response, video_id);
-                    if !tracks.is_empty() {
-                        info!(
-                            "Successfully found {} subtitle tracks using {} client",
-                            tracks.len(),
-                            client.client_type.client_name()
-                        );
-                        return Ok(tracks);
-                    }
-                }
-                Err(e) => {
-                    debug!(
-                        "Failed to get subtitles with {} client: {}",
-                        client.client_type.client_name(),
-                        e
+            let result = client
+                .get_player(video_id)
+                .await
+                .map(|response| client.extract_subtitle_tracks(&response, video_id).len());
+            results.push((client.client_type.clone(), result));
+        }
+        results
+    }
+
+    /// Ask the client at `index` for tracks, recording the outcome
+    ///
+    /// Fails with `CaptionsDisabled` if the response has no captions renderer,
+    /// `NoSubtitlesAvailable` if the renderer lists no tracks, or the request error.
+    async fn try_client(&self, index: usize, video_id: &str) -> YdlResult<Vec<SubtitleTrack>> {
+        let client = &self.clients[index];
+        match client.get_player(video_id).await {
+            Ok(player_response) => {
+                let tracks = client.extract_subtitle_tracks(&player_response, video_id);
+                if !tracks.is_empty() {
+                    info!(
+                        "Successfully found {} subtitle tracks using {} client",
+                        tracks.len(),
+                        client.client_type.client_name()
                     );
+                    self.record_result(index, true);
+                    self.metrics
+                        .client_succeeded(client.client_type.client_name());
+                    return Ok(tracks);
+                }
+                self.record_result(index, false);
+                if player_response.captions_disabled() {
+                    Err(YdlError::CaptionsDisabled {
+                        video_id: video_id.to_string(),
+                    })
+                } else {
+                    Err(YdlError::NoSubtitlesAvailable {
+                        video_id: video_id.to_string(),
+                    })
                 }
             }
+            Err(e) => {
+                debug!(
+                    "Failed to get subtitles with {} client: {}",
+                    client.client_type.client_name(),
+                    e
+                );
+                self.record_result(index, false);
+                Err(e)
+            }
         }
+    }
 
-        Err(YdlError::NoSubtitlesAvailable {
-            video_id: video_id.to_string(),
+    /// Length of the video as reported by the first client that answers
+    pub async fn video_duration(&self, video_id: &str) -> Option<Duration> {
+        for index in self.client_order() {
+            let Ok(player_response) = self.clients[index].get_player(video_id).await else {
+                continue;
+            };
+            if let Some(seconds) = player_response
+                .video_details
+                .and_then(|details| details.length_seconds)
+                .and_then(|length| length.parse::<u64>().ok())
+            {
+                return Some(Duration::from_secs(seconds));
+            }
+        }
+        None
+    }
+
+    /// Playability of the video as reported by the first client that answers
+    pub async fn playability(&self, video_id: &str) -> Option<PlayabilityStatus> {
+        for index in self.client_order() {
+            let Ok(player_response) = self.clients[index].get_player(video_id).await else {
+                continue;
+            };
+            if let Some(status) = player_response.playability_status {
+                return Some(status);
+            }
+        }
+        None
+    }
+
+    /// Client named by the URL's `c` parameter, so downloads match the discovering client
+    ///
+    /// Falls back to the first client when the URL names none of ours.
+    fn download_client(&self, url: &str) -> &InnerTubeClient {
+        let name = url::Url::parse(url).ok().and_then(|parsed| {
+            parsed
+                .query_pairs()
+                .find(|(k, _)| k == "c")
+                .map(|(_, v)| v.into_owned())
+        });
+        name.and_then(|name| {
+            self.clients
+                .iter()
+                .find(|client| client.client_type.client_name() == name)
         })
+        .unwrap_or(&self.clients[0])
     }
 
     /// Download subtitle content from URL
     pub async fn download_content(&self, url: &str) -> YdlResult<String> {
-        info!("Downloading subtitle from URL: {}", url);
+        info!(
+            "Downloading subtitle from URL: {}",
+            network::redact_url(url)
+        );
 
-        // Use the first client for downloading
-        let response = self.clients[0].client.get(url).send().await?;
+        let downloader = self.download_client(url);
+        let (status, content) = fetch_text(
+            &downloader.client,
+            url,
+            downloader.client_type.client_name(),
+            &*self.metrics,
+        )
+        .await?;
 
-        if !response.status().is_success() {
+        if !status.is_success() {
             return Err(YdlError::SubtitleDiscoveryError {
-                message: format!("Failed to download subtitle: {}", response.status()),
+                message: format!("Failed to download subtitle: {}", status),
             });
         }
 
-        let content = response.text().await?;
-
         debug!("Downloaded subtitle content length: {}", content.len());
         debug!(
             "First 500 chars of content: {}",
@@ -322,18 +738,289 @@ impl YouTubeSubtitleExtractor {
         );
 
         if content.is_empty() {
-            return Err(YdlError::SubtitleParsing {
-                message: "Empty subtitle content".to_string(),
-            });
+            return Err(YdlError::subtitle_parsing("Empty subtitle content"));
         }
 
         Ok(content)
     }
 }
 
-// Additional InnerTube API response structures
-#[derive(Debug, Deserialize)]
-pub struct PlayabilityStatus {
-    pub status: String,
-    pub reason: Option<String>,
+/// Add user-supplied fields to the sections of an InnerTube context
+///
+/// Sections missing from `context` are created. Protected client fields are kept.
+fn merge_context(
+    context: &mut serde_json::Value,
+    overrides: &serde_json::Map<String, serde_json::Value>,
+) {
+    let Some(context) = context.as_object_mut() else {
+        return;
+    };
+
+    for (section, fields) in overrides {
+        let Some(fields) = fields.as_object() else {
+            continue;
+        };
+        let target = context.entry(section.clone()).or_insert_with(|| json!({}));
+        let Some(target) = target.as_object_mut() else {
+            continue;
+        };
+
+        for (key, value) in fields {
+            if section == "client" && PROTECTED_CLIENT_FIELDS.contains(&key.as_str()) {
+                warn!("Ignoring InnerTube context override of client.{}", key);
+                continue;
+            }
+            target.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tests::{MockResponse, test_server};
+
+    #[test]
+    fn test_client_order_defaults() {
+        let extractor = YouTubeSubtitleExtractor::new().unwrap();
+        assert_eq!(extractor.client_order(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_configured_client_types() {
+        assert_eq!("iOS".parse::<ClientType>().unwrap(), ClientType::Ios);
+        assert_eq!("tv".parse::<ClientType>().unwrap(), ClientType::TvEmbedded);
+        assert_eq!(
+            "tv-embedded".parse::<ClientType>().unwrap(),
+            ClientType::TvEmbedded
+        );
+        assert!(matches!(
+            "mweb".parse::<ClientType>(),
+            Err(YdlError::Configuration { .. })
+        ));
+
+        // Order is kept and repeats are dropped
+        let extractor = YouTubeSubtitleExtractor::with_client_types(&[
+            ClientType::Ios,
+            ClientType::Web,
+            ClientType::Ios,
+        ])
+        .unwrap();
+        let types: Vec<ClientType> = extractor
+            .clients
+            .iter()
+            .map(|client| client.client_type.clone())
+            .collect();
+        assert_eq!(types, vec![ClientType::Ios, ClientType::Web]);
+        assert_eq!(extractor.client_order(), vec![0, 1]);
+
+        assert!(YouTubeSubtitleExtractor::with_client_types(&[]).is_err());
+    }
+
+    #[test]
+    fn test_client_order_prefers_successful_client() {
+        let extractor = YouTubeSubtitleExtractor::new().unwrap();
+
+        // Android (index 3) keeps succeeding while the TV client keeps failing
+        for _ in 0..3 {
+            extractor.record_result(0, false);
+            extractor.record_result(3, true);
+        }
+        assert_eq!(extractor.client_order(), vec![3, 0, 1, 2]);
+
+        // Scores are bounded, so a client can be overtaken again
+        for _ in 0..100 {
+            extractor.record_result(3, true);
+        }
+        assert_eq!(
+            extractor.scores[3].load(Ordering::Relaxed),
+            MAX_CLIENT_SCORE
+        );
+        for _ in 0..MAX_CLIENT_SCORE {
+            extractor.record_result(3, false);
+        }
+        extractor.record_result(1, true);
+        assert_eq!(extractor.client_order(), vec![1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_client_params_appended_when_missing() {
+        let player_response: PlayerResponse = serde_json::from_value(json!({
+            "captions": {"playerCaptionsTracklistRenderer": {"captionTracks": [
+                {"baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en", "languageCode": "en", "vssId": ".en"},
+                {"baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=fr&c=IOS&cver=19.29.1", "languageCode": "fr", "vssId": ".fr"},
+            ]}}
+        }))
+        .unwrap();
+
+        let client = InnerTubeClient::new(ClientType::Web).unwrap();
+        let tracks = client.extract_subtitle_tracks(&player_response, "dQw4w9WgXcQ");
+        let en = tracks[0].url.as_deref().unwrap();
+        assert!(en.contains("&c=WEB&cver=2.20240815.00.00"));
+        assert!(!en.contains("fmt="));
+
+        // The client already named by the URL is kept
+        let fr = tracks[1].url.as_deref().unwrap();
+        assert!(fr.contains("c=IOS&cver=19.29.1"));
+        assert!(!fr.contains("c=WEB"));
+
+        // Downloads go through the client named by `c`, defaulting to the first
+        let extractor = YouTubeSubtitleExtractor::new().unwrap();
+        assert_eq!(extractor.download_client(fr).client_type, ClientType::Ios);
+        assert_eq!(
+            extractor
+                .download_client("https://www.youtube.com/api/timedtext?v=x")
+                .client_type,
+            ClientType::TvEmbedded
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_all_reports_every_client() {
+        let track = |lang: &str| {
+            format!(
+                r#"{{"baseUrl":"https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang={lang}","vssId":".{lang}","languageCode":"{lang}"}}"#
+            )
+        };
+        let addr = test_server(move |request| {
+            // TV sees two tracks, WEB none, iOS is blocked and Android sees one
+            match request.header("x-youtube-client-name").unwrap_or("") {
+                "85" => MockResponse::ok(format!(
+                    r#"{{"captions":{{"playerCaptionsTracklistRenderer":{{"captionTracks":[{},{}]}}}}}}"#,
+                    track("en"),
+                    track("de")
+                )),
+                "1" => MockResponse::ok("{}"),
+                "3" => MockResponse::ok(format!(
+                    r#"{{"captions":{{"playerCaptionsTracklistRenderer":{{"captionTracks":[{}]}}}}}}"#,
+                    track("en")
+                )),
+                _ => MockResponse::status(403, ""),
+            }
+        })
+        .await;
+
+        let extractor = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+        // Earlier successes must not reorder the probe
+        extractor.record_result(3, true);
+
+        let results = extractor.probe_all("dQw4w9WgXcQ").await;
+        let clients: Vec<ClientType> = results.iter().map(|(client, _)| client.clone()).collect();
+        assert_eq!(
+            clients,
+            vec![
+                ClientType::TvEmbedded,
+                ClientType::Web,
+                ClientType::Ios,
+                ClientType::Android
+            ]
+        );
+        assert_eq!(results[0].1.as_ref().unwrap(), &2);
+        assert_eq!(results[1].1.as_ref().unwrap(), &0);
+        assert!(matches!(
+            results[2].1,
+            Err(YdlError::SubtitleDiscoveryError { .. })
+        ));
+        assert_eq!(results[3].1.as_ref().unwrap(), &1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_captions_field_means_disabled() {
+        async fn serve(body: &'static str) -> String {
+            let addr = test_server(move |_| MockResponse::ok(body)).await;
+            format!("http://{}/youtubei/v1/player", addr)
+        }
+
+        // A playable video whose player response has no captions field at all
+        let endpoint =
+            serve(r#"{"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"No captions"}}"#).await;
+        let extractor = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&endpoint);
+        let result = extractor.discover_tracks("dQw4w9WgXcQ").await;
+        assert!(matches!(result, Err(YdlError::CaptionsDisabled { .. })));
+
+        // A captions renderer that lists nothing is not the uploader's setting
+        let endpoint = serve(r#"{"videoDetails":{"videoId":"dQw4w9WgXcQ","title":"Empty"},"captions":{"playerCaptionsTracklistRenderer":{"captionTracks":[]}}}"#).await;
+        let extractor = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&endpoint);
+        let result = extractor.discover_tracks("dQw4w9WgXcQ").await;
+        assert!(matches!(result, Err(YdlError::NoSubtitlesAvailable { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_custom_http_client_gets_youtube_headers() {
+        let (request_tx, mut request_rx) = tokio::sync::mpsc::unbounded_channel();
+        let addr = test_server(move |request| {
+            let _ = request_tx.send(request.to_lowercase());
+            MockResponse::ok("{}")
+        })
+        .await;
+
+        let mut defaults = HeaderMap::new();
+        defaults.insert("x-pool", HeaderValue::from_static("shared"));
+        let client = reqwest::Client::builder()
+            .default_headers(defaults)
+            .build()
+            .unwrap();
+        let config =
+            HttpConfig::from_options(&crate::types::YdlOptions::new().http_client(client)).unwrap();
+
+        let extractor = YouTubeSubtitleExtractor::with_config(ClientType::ALL, &config)
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr));
+        extractor.clients[0]
+            .get_player("dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        // The caller's client is used, with the InnerTube client's identity layered on
+        let request = request_rx.recv().await.unwrap();
+        assert!(request.contains("x-pool: shared"));
+        assert!(request.contains("x-youtube-client-name: 85"));
+        assert!(request.contains("x-youtube-client-version: 2.0"));
+        assert_eq!(request.matches("user-agent:").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_context_overrides_in_request_body() {
+        let (body_tx, mut body_rx) = tokio::sync::mpsc::unbounded_channel();
+        let addr = test_server(move |request| {
+            let _ = body_tx.send(request.body().to_string());
+            MockResponse::ok("{}")
+        })
+        .await;
+
+        let extractor = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_player_endpoint(&format!("http://{}/youtubei/v1/player", addr))
+            .with_context_overrides(json!({
+                "client": {"clientScreen": "EMBED", "clientName": "SPOOFED"},
+                "thirdParty": {"embedUrl": "https://example.com/"},
+            }))
+            .unwrap();
+
+        extractor.clients[0]
+            .get_player("dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&body_rx.recv().await.unwrap()).unwrap();
+        let context = &body["context"];
+        assert_eq!(context["client"]["clientScreen"], "EMBED");
+        assert_eq!(
+            context["client"]["clientName"],
+            "TVHTML5_SIMPLY_EMBEDDED_PLAYER"
+        );
+        assert_eq!(context["client"]["clientVersion"], "2.0");
+        assert_eq!(context["thirdParty"]["embedUrl"], "https://example.com/");
+
+        let invalid = YouTubeSubtitleExtractor::new()
+            .unwrap()
+            .with_context_overrides(json!({"client": "EMBED"}));
+        assert!(matches!(invalid, Err(YdlError::Configuration { .. })));
+    }
 }